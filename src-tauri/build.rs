@@ -1,3 +1,28 @@
 fn main() {
+    // The snapshots are stamped with the s2protocol version that wrote them.
+    println!("cargo:rerun-if-changed=../Cargo.lock");
+    println!(
+        "cargo:rustc-env=S2PROTOCOL_VERSION={}",
+        locked_version("s2protocol")
+    );
     tauri_build::build()
 }
+
+/// The version of `package` in the workspace lock file, "unknown" if it is not there.
+fn locked_version(package: &str) -> String {
+    let lock = std::fs::read_to_string("../Cargo.lock").unwrap_or_default();
+    let name = format!("name = \"{}\"", package);
+    let mut lines = lock.lines();
+    while let Some(line) = lines.next() {
+        if line != name {
+            continue;
+        }
+        if let Some(version) = lines
+            .next()
+            .and_then(|line| line.strip_prefix("version = "))
+        {
+            return version.trim_matches('"').to_string();
+        }
+    }
+    String::from("unknown")
+}
@@ -0,0 +1,223 @@
+//! Labels the openings of the snapshot with user-defined rules.
+
+use crate::jobs::JobRunner;
+use crate::mpq_file_scan::manifest::SnapshotManifest;
+use crate::snapshot_registry::SnapshotRegistry;
+use polars::prelude::*;
+use std::collections::HashMap;
+use std::path::Path;
+use swarmy_tauri_common::*;
+use tauri::Manager;
+use tauri_plugin_store::StoreBuilder;
+
+/// The rules used when no rules file is configured.
+pub const DEFAULT_BUILD_LABEL_RULES: &str = include_str!("default_rules.toml");
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn classify_build_orders(
+    app_handle: tauri::AppHandle,
+    replay_paths: Vec<String>,
+    rules_path: String,
+) -> ApiResponse<BuildLabelSummary> {
+    match StoreBuilder::new(&app_handle, "settings.json").build() {
+        Ok(store) => {
+            // If there are no saved settings yet, this will return an error so we ignore the return value.
+            let _ = store.reload();
+            store.set("build_rules_path", rules_path.clone());
+        }
+        Err(e) => log::error!("Error building store: {}", e),
+    }
+    let init_time = std::time::Instant::now();
+    let snapshots = app_handle.state::<SnapshotRegistry>().inner().clone();
+    let res = app_handle
+        .state::<JobRunner>()
+        .run_background("classify_build_orders", move || {
+            load_build_label_rules(&rules_path).and_then(|rules| {
+                // A query may target several libraries, each snapshot has its own labels.
+                for replay_path in &replay_paths {
+                    try_classify_build_orders(&snapshots.ipc_dir(replay_path), &rules)?;
+                }
+                try_get_build_labels(&snapshots, &replay_paths, &rules_path, &rules)
+            })
+        })
+        .await;
+    if let Err(e) = &res {
+        log::error!("Error classifying build orders: {}", e);
+    }
+    ApiResponse::from_result(res, init_time.elapsed().as_millis() as u64)
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn get_build_labels(
+    app_handle: tauri::AppHandle,
+    replay_paths: Vec<String>,
+    rules_path: String,
+) -> ApiResponse<BuildLabelSummary> {
+    let init_time = std::time::Instant::now();
+    let snapshots = app_handle.state::<SnapshotRegistry>().inner().clone();
+    let res = app_handle
+        .state::<JobRunner>()
+        .run("get_build_labels", move || {
+            load_build_label_rules(&rules_path).and_then(|rules| {
+                try_get_build_labels(&snapshots, &replay_paths, &rules_path, &rules)
+            })
+        })
+        .await;
+    if let Err(e) = &res {
+        log::error!("Error getting build labels: {}", e);
+    }
+    ApiResponse::from_result(res, init_time.elapsed().as_millis() as u64)
+}
+
+/// Reads the rules from a JSON file if its extension is `json` or from a TOML file otherwise.
+/// An empty path loads [`DEFAULT_BUILD_LABEL_RULES`].
+pub fn load_build_label_rules(rules_path: &str) -> Result<BuildLabelRules, SwarmyTauriError> {
+    if rules_path.is_empty() {
+        return parse_toml_rules(DEFAULT_BUILD_LABEL_RULES);
+    }
+    let contents = std::fs::read_to_string(rules_path)?;
+    if rules_path.to_lowercase().ends_with(".json") {
+        Ok(serde_json::from_str(&contents)?)
+    } else {
+        parse_toml_rules(&contents)
+    }
+}
+
+fn parse_toml_rules(contents: &str) -> Result<BuildLabelRules, SwarmyTauriError> {
+    toml::from_str(contents)
+        .map_err(|e| SwarmyTauriError::Other(format!("Invalid build label rules: {}", e)))
+}
+
+/// Labels the build order of every player of every game in the snapshot in `ipc_dir` and writes
+/// them to build_labels.ipc next to details.ipc.
+pub fn try_classify_build_orders(
+    ipc_dir: &Path,
+    rules: &BuildLabelRules,
+) -> Result<(), SwarmyTauriError> {
+    log::info!(
+        "Classifying build orders in: {} with {} rules",
+        ipc_dir.display(),
+        rules.rules.len()
+    );
+    let manifest = SnapshotManifest::load(ipc_dir)?.unwrap_or_default();
+    let scan = |file_name: &str| -> Result<LazyFrame, SwarmyTauriError> {
+        manifest.scan_ipc(ipc_dir, file_name)?.ok_or_else(|| {
+            SwarmyTauriError::Other(format!(
+                "The snapshot at {} has no {}, optimize the replays first",
+                ipc_dir.display(),
+                file_name
+            ))
+        })
+    };
+    let units = scan(UNIT_BORN_IPC)?
+        .filter(
+            // The units of the start of the game are born in the first loop.
+            col("ext_replay_loop")
+                .gt(lit(0i64))
+                .and(col("ext_replay_loop").lt_eq(lit(rules.max_loop())))
+                .and(col("player_name").is_not_null()),
+        )
+        .select([
+            col("ext_fs_id"),
+            col("player_name"),
+            col("unit_type_name"),
+            col("ext_replay_loop"),
+        ])
+        .sort(
+            ["ext_fs_id", "player_name", "ext_replay_loop"],
+            SortMultipleOptions::default().with_maintain_order(true),
+        )
+        .collect()?;
+    let unit_fs_ids = units.column("ext_fs_id")?.u64()?;
+    let unit_player_names = units.column("player_name")?.str()?;
+    let unit_names = units.column("unit_type_name")?.str()?;
+    let unit_loops = units.column("ext_replay_loop")?.i64()?;
+    let mut steps: HashMap<(u64, &str), Vec<(String, i64)>> = HashMap::new();
+    for idx in 0..units.height() {
+        let (Some(ext_fs_id), Some(player_name)) =
+            (unit_fs_ids.get(idx), unit_player_names.get(idx))
+        else {
+            continue;
+        };
+        steps.entry((ext_fs_id, player_name)).or_default().push((
+            unit_names.get(idx).unwrap_or("").to_string(),
+            unit_loops.get(idx).unwrap_or(0),
+        ));
+    }
+
+    let players = scan(DETAILS_IPC)?
+        .select([col("ext_fs_id"), col("player_name"), col("player_race")])
+        .unique_stable(None, UniqueKeepStrategy::First)
+        .collect()?;
+    let fs_ids = players.column("ext_fs_id")?.u64()?;
+    let player_names = players.column("player_name")?.str()?;
+    let player_races = players.column("player_race")?.str()?;
+    let labels: Vec<&str> = (0..players.height())
+        .map(|idx| {
+            let player_steps = match (fs_ids.get(idx), player_names.get(idx)) {
+                (Some(ext_fs_id), Some(player_name)) => steps.get(&(ext_fs_id, player_name)),
+                _ => None,
+            };
+            rules.classify(
+                player_races.get(idx).unwrap_or(""),
+                player_steps.map(|steps| steps.as_slice()).unwrap_or(&[]),
+            )
+        })
+        .collect();
+
+    let mut df = players
+        .select(["ext_fs_id", "player_name", "player_race"])?
+        .hstack(&[Column::new("label".into(), labels)])?;
+    let mut file = std::fs::File::create(ipc_dir.join(BUILD_LABELS_IPC))?;
+    IpcWriter::new(&mut file).finish(&mut df)?;
+    Ok(())
+}
+
+/// Counts the build orders per label in build_labels.ipc, which is empty until the snapshot is
+/// classified.
+pub fn try_get_build_labels(
+    snapshots: &SnapshotRegistry,
+    replay_paths: &[String],
+    rules_path: &str,
+    rules: &BuildLabelRules,
+) -> Result<BuildLabelSummary, SwarmyTauriError> {
+    let mut summary = BuildLabelSummary {
+        rules_path: rules_path.to_string(),
+        num_rules: rules.rules.len(),
+        ..Default::default()
+    };
+    let Some(labels) = snapshots
+        .open(replay_paths)?
+        .scan_optional(BUILD_LABELS_IPC)
+    else {
+        return Ok(summary);
+    };
+    let counts = labels
+        .group_by([col("label")])
+        .agg([len().cast(DataType::UInt64).alias("count")])
+        .sort_by_exprs(
+            [col("count"), col("label")],
+            SortMultipleOptions::default().with_order_descending_multi([true, false]),
+        )
+        .collect()?;
+    let labels = counts.column("label")?.str()?;
+    let label_counts = counts.column("count")?.u64()?;
+    summary.labels = (0..counts.height())
+        .map(|idx| BuildLabelCount {
+            label: labels.get(idx).unwrap_or("").to_string(),
+            count: label_counts.get(idx).unwrap_or(0),
+        })
+        .collect();
+    summary.num_build_orders = summary.labels.iter().map(|label| label.count).sum();
+    Ok(summary)
+}
+
+/// Adds the `label` column to details rows, matched by game and player name.
+pub fn join_build_labels(rows: LazyFrame, labels: LazyFrame) -> LazyFrame {
+    rows.join(
+        labels.select([col("ext_fs_id"), col("player_name"), col("label")]),
+        [col("ext_fs_id"), col("player_name")],
+        [col("ext_fs_id"), col("player_name")],
+        JoinArgs::new(JoinType::Inner),
+    )
+}
@@ -0,0 +1,178 @@
+//! Opening build orders from the unit born IPC file.
+
+use crate::jobs::JobRunner;
+use crate::player_profile::player_name_filter;
+use crate::snapshot_registry::{Snapshot, SnapshotRegistry};
+use polars::prelude::*;
+use swarmy_tauri_common::*;
+use tauri::Manager;
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn query_build_order(
+    app_handle: tauri::AppHandle,
+    query: BuildOrderQuery,
+) -> ApiResponse<BuildOrder> {
+    let init_time = std::time::Instant::now();
+    let snapshots = app_handle.state::<SnapshotRegistry>().inner().clone();
+    let res = app_handle
+        .state::<JobRunner>()
+        .run("query_build_order", move || {
+            try_query_build_order(&snapshots, &query)
+        })
+        .await;
+    if let Err(e) = &res {
+        log::error!("Error querying build order: {}", e);
+    }
+    ApiResponse::from_result(res, init_time.elapsed().as_millis() as u64)
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn compare_build_orders(
+    app_handle: tauri::AppHandle,
+    query: BuildOrderComparisonQuery,
+) -> ApiResponse<BuildOrderComparison> {
+    let init_time = std::time::Instant::now();
+    let snapshots = app_handle.state::<SnapshotRegistry>().inner().clone();
+    let res = app_handle
+        .state::<JobRunner>()
+        .run("compare_build_orders", move || {
+            try_query_build_order(&snapshots, &query.a)
+                .and_then(|a| Ok(a.compare(&try_query_build_order(&snapshots, &query.b)?)))
+        })
+        .await;
+    if let Err(e) = &res {
+        log::error!("Error comparing build orders: {}", e);
+    }
+    ApiResponse::from_result(res, init_time.elapsed().as_millis() as u64)
+}
+
+/// Extracts the units and structures of a player in a game from the unit_born.ipc file along
+/// with the supply as of the last stats sample before each of them.
+pub fn try_query_build_order(
+    snapshots: &SnapshotRegistry,
+    query: &BuildOrderQuery,
+) -> Result<BuildOrder, SwarmyTauriError> {
+    log::info!(
+        "Querying build order from: {:?} for game: {} and player: {}",
+        query.replay_paths,
+        query.ext_fs_id,
+        query.player_name
+    );
+    let max_loop = if query.max_loop == 0 {
+        BUILD_ORDER_DEFAULT_MAX_LOOP
+    } else {
+        query.max_loop
+    };
+    let snapshot = snapshots.open(&query.replay_paths)?;
+    let units = snapshot
+        .scan(UNIT_BORN_IPC)?
+        .filter(
+            col("ext_fs_id")
+                .eq(lit(query.ext_fs_id))
+                .and(player_name_filter(&query.player_name))
+                // The units of the start of the game are born in the first loop.
+                .and(col("ext_replay_loop").gt(lit(0i64)))
+                .and(col("ext_replay_loop").lt_eq(lit(max_loop)))
+                .and(
+                    col("unit_type_name")
+                        .is_in(
+                            lit(Series::new(
+                                "ignored_units".into(),
+                                &BUILD_ORDER_IGNORED_UNITS[..],
+                            ))
+                            .implode(),
+                            false,
+                        )
+                        .not(),
+                ),
+        )
+        .select([
+            col("unit_type_name"),
+            col("ext_replay_loop"),
+            col("ext_replay_seconds"),
+            col("control_player_id").cast(DataType::UInt8),
+            col("player_name"),
+        ])
+        .sort(
+            ["ext_replay_loop"],
+            SortMultipleOptions::default().with_maintain_order(true),
+        )
+        .collect()?;
+    if units.height() == 0 {
+        return Err(SwarmyTauriError::Other(format!(
+            "No units found for player: {} in game: {}",
+            query.player_name, query.ext_fs_id
+        )));
+    }
+    let names = units.column("unit_type_name")?.str()?;
+    let loops = units.column("ext_replay_loop")?.i64()?;
+    let seconds = units.column("ext_replay_seconds")?.u32()?;
+    let player_id = units.column("control_player_id")?.u8()?.get(0).unwrap_or(0);
+    let player_name = units
+        .column("player_name")?
+        .str()?
+        .get(0)
+        .unwrap_or(&query.player_name)
+        .to_string();
+
+    let supply = read_supply_samples(&snapshot, query.ext_fs_id, player_id, max_loop)?;
+    let mut next_sample = 0;
+    let mut steps = Vec::with_capacity(units.height());
+    for idx in 0..units.height() {
+        let ext_replay_loop = loops.get(idx).unwrap_or(0);
+        while next_sample < supply.len() && supply[next_sample].0 <= ext_replay_loop {
+            next_sample += 1;
+        }
+        let (supply_used, supply_made) = next_sample
+            .checked_sub(1)
+            .map(|sample| (supply[sample].1, supply[sample].2))
+            .unwrap_or_default();
+        steps.push(BuildOrderStep {
+            name: names.get(idx).unwrap_or("Unknown").to_string(),
+            ext_replay_loop,
+            game_seconds: seconds.get(idx).unwrap_or(0),
+            supply_used,
+            supply_made,
+        });
+    }
+    Ok(BuildOrder {
+        ext_fs_id: query.ext_fs_id,
+        player_name,
+        steps,
+    })
+}
+
+/// Returns the game loop, food used and food made of the stats samples of a player in a game,
+/// oldest first, or none if the snapshot has no stats.
+fn read_supply_samples(
+    snapshot: &Snapshot,
+    ext_fs_id: u64,
+    player_id: u8,
+    max_loop: i64,
+) -> Result<Vec<(i64, i32, i32)>, SwarmyTauriError> {
+    let Some(stats) = snapshot.scan_optional(STATS_IPC) else {
+        return Ok(vec![]);
+    };
+    let stats = stats
+        .filter(
+            col("ext_fs_id")
+                .eq(lit(ext_fs_id))
+                .and(col("player_id").eq(lit(player_id as u32)))
+                .and(col("ext_replay_loop").lt_eq(lit(max_loop))),
+        )
+        .select([col("ext_replay_loop"), col("food_used"), col("food_made")])
+        .sort(["ext_replay_loop"], Default::default())
+        .collect()?;
+    let loops = stats.column("ext_replay_loop")?.i64()?;
+    let food_used = stats.column("food_used")?.i32()?;
+    let food_made = stats.column("food_made")?.i32()?;
+    Ok((0..stats.height())
+        .map(|idx| {
+            (
+                loops.get(idx).unwrap_or(0),
+                food_used.get(idx).unwrap_or(0),
+                food_made.get(idx).unwrap_or(0),
+            )
+        })
+        .collect())
+}
@@ -0,0 +1,258 @@
+//! Benchmarks of the stats of a game against the stats of similar games.
+
+use super::scan_tracker_players;
+use crate::jobs::JobRunner;
+use crate::player_profile::player_name_filter;
+use crate::snapshot_registry::{Snapshot, SnapshotRegistry};
+use polars::prelude::*;
+use swarmy_tauri_common::*;
+use tauri::Manager;
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn query_benchmark(
+    app_handle: tauri::AppHandle,
+    query: BenchmarkQuery,
+) -> ApiResponse<Benchmark> {
+    let init_time = std::time::Instant::now();
+    let snapshots = app_handle.state::<SnapshotRegistry>().inner().clone();
+    let res = app_handle
+        .state::<JobRunner>()
+        .run("query_benchmark", move || {
+            try_query_benchmark(&snapshots, &query)
+        })
+        .await;
+    if let Err(e) = &res {
+        log::error!("Error querying benchmark: {}", e);
+    }
+    ApiResponse::from_result(res, init_time.elapsed().as_millis() as u64)
+}
+
+/// Compares the workers and army value of a player in a game against the median and
+/// interquartile range of the games in the scope of the query, bucketed by game loop.
+pub fn try_query_benchmark(
+    snapshots: &SnapshotRegistry,
+    query: &BenchmarkQuery,
+) -> Result<Benchmark, SwarmyTauriError> {
+    log::info!(
+        "Querying benchmark from: {:?} for game: {} and player: {}",
+        query.replay_paths,
+        query.ext_fs_id,
+        query.player_name
+    );
+    let snapshot = snapshots.open(&query.replay_paths)?;
+    let details = snapshot.scan(DETAILS_IPC)?;
+    let player = details
+        .clone()
+        .filter(
+            col("ext_fs_id")
+                .eq(lit(query.ext_fs_id))
+                .and(player_name_filter(&query.player_name)),
+        )
+        .select([
+            col("player_name"),
+            col("player_race"),
+            col("player_team_id").cast(DataType::UInt8),
+            col("title"),
+        ])
+        .collect()?;
+    if player.height() == 0 {
+        return Err(SwarmyTauriError::Other(format!(
+            "Player: {} not found in game: {}",
+            query.player_name, query.ext_fs_id
+        )));
+    }
+    let player_name = player
+        .column("player_name")?
+        .str()?
+        .get(0)
+        .unwrap_or("")
+        .to_string();
+    // The races are named in the language of the client that saved each replay.
+    let race = race_id(player.column("player_race")?.str()?.get(0).unwrap_or("")).to_string();
+    let team = player.column("player_team_id")?.u8()?.get(0);
+    let title = player
+        .column("title")?
+        .str()?
+        .get(0)
+        .unwrap_or("")
+        .to_string();
+
+    let (description, scope_rows) = match query.scope {
+        BenchmarkScope::Matchup => {
+            let game_players = details
+                .clone()
+                .filter(col("ext_fs_id").eq(lit(query.ext_fs_id)))
+                .select([
+                    col("player_race"),
+                    col("player_team_id").cast(DataType::UInt8),
+                ])
+                .collect()?;
+            let game_races = game_players.column("player_race")?.str()?;
+            let game_teams = game_players.column("player_team_id")?.u8()?;
+            let opponent_race = (0..game_players.height())
+                .find(|idx| game_teams.get(*idx) != team)
+                .and_then(|idx| game_races.get(idx))
+                .map(race_id)
+                .filter(|_| game_players.height() == 2)
+                .ok_or_else(|| {
+                    SwarmyTauriError::Other(String::from(
+                        "The matchup benchmark is only available for 1v1 games",
+                    ))
+                })?
+                .to_string();
+            let matchup = MatchupWinRate::new(race.clone(), opponent_race.clone(), 0, 0).matchup;
+            let one_v_one_games = details
+                .clone()
+                .group_by([col("ext_fs_id")])
+                .agg([len().alias("num_players")])
+                .filter(col("num_players").eq(lit(2)))
+                .select([col("ext_fs_id")]);
+            let opponents = details
+                .clone()
+                .filter(race_id_expr(col("player_race")).eq(lit(opponent_race)))
+                .select([
+                    col("ext_fs_id"),
+                    col("player_team_id").alias("opponent_team_id"),
+                ]);
+            let rows = details
+                // The same player under any clan tag.
+                .filter(
+                    player_name_filter(&split_clan_tag(&player_name).1)
+                        .and(race_id_expr(col("player_race")).eq(lit(race))),
+                )
+                .inner_join(one_v_one_games, col("ext_fs_id"), col("ext_fs_id"))
+                .inner_join(opponents, col("ext_fs_id"), col("ext_fs_id"))
+                .filter(col("player_team_id").neq(col("opponent_team_id")))
+                .select([col("ext_fs_id"), col("player_name")]);
+            (matchup, rows)
+        }
+        BenchmarkScope::Map => (
+            title.clone(),
+            details
+                .filter(col("title").eq(lit(title)))
+                .select([col("ext_fs_id"), col("player_name")]),
+        ),
+    };
+    let scope_rows = scope_rows.filter(col("ext_fs_id").neq(lit(query.ext_fs_id)));
+    let game_rows = df!(
+        "ext_fs_id" => [query.ext_fs_id],
+        "player_name" => [player_name.clone()],
+    )?
+    .lazy();
+
+    let benchmark_stats = scan_benchmark_stats(&snapshot, scope_rows)?.cache();
+    let game_stats = scan_benchmark_stats(&snapshot, game_rows)?.cache();
+    let num_games = benchmark_stats
+        .clone()
+        .select([col("ext_fs_id").n_unique().cast(DataType::UInt64)])
+        .collect()?
+        .column("ext_fs_id")?
+        .u64()?
+        .get(0)
+        .unwrap_or(0);
+    if num_games == 0 {
+        return Err(SwarmyTauriError::Other(format!(
+            "There are no other games with stats to benchmark against in: {}",
+            description
+        )));
+    }
+    let series = |metric: &str, column: &str| -> Result<BenchmarkSeries, SwarmyTauriError> {
+        Ok(BenchmarkSeries {
+            metric: metric.to_string(),
+            scope: query.scope,
+            description: description.clone(),
+            num_games,
+            points: benchmark_points(benchmark_stats.clone(), game_stats.clone(), column)?,
+        })
+    };
+    Ok(Benchmark {
+        ext_fs_id: query.ext_fs_id,
+        workers: series("Workers", "workers")?,
+        army_value: series("Army Value", "army_value")?,
+        player_name,
+    })
+}
+
+/// Returns the stats samples of the `rows` games and player names, with the game loop of the
+/// start of their bucket.
+fn scan_benchmark_stats(
+    snapshot: &Snapshot,
+    rows: LazyFrame,
+) -> Result<LazyFrame, SwarmyTauriError> {
+    let tracker_players = rows.join(
+        scan_tracker_players(snapshot)?,
+        [col("ext_fs_id"), col("player_name")],
+        [col("ext_fs_id"), col("player_name")],
+        JoinArgs::new(JoinType::Inner),
+    );
+    Ok(snapshot
+        .scan(STATS_IPC)?
+        .select([
+            col("ext_fs_id"),
+            col("player_id").cast(DataType::UInt8),
+            col("ext_replay_loop")
+                .floor_div(lit(BENCHMARK_BUCKET_LOOPS))
+                .alias("bucket"),
+            col("ext_replay_seconds"),
+            col("workers_active_count")
+                .cast(DataType::Float64)
+                .alias("workers"),
+            (col("minerals_used_current_army") + col("vespene_used_current_army"))
+                .cast(DataType::Float64)
+                .alias("army_value"),
+        ])
+        .join(
+            tracker_players,
+            [col("ext_fs_id"), col("player_id")],
+            [col("ext_fs_id"), col("player_id")],
+            JoinArgs::new(JoinType::Inner),
+        ))
+}
+
+/// Aggregates a metric of the benchmark games per bucket and lines it up with the game.
+fn benchmark_points(
+    benchmark_stats: LazyFrame,
+    game_stats: LazyFrame,
+    column: &str,
+) -> Result<Vec<BenchmarkPoint>, SwarmyTauriError> {
+    let game_values = game_stats
+        .group_by([col("bucket")])
+        .agg([col(column).mean().alias("value")]);
+    let res = benchmark_stats
+        .group_by([col("bucket")])
+        .agg([
+            col("ext_replay_seconds").min().alias("game_seconds"),
+            col(column).median().alias("median"),
+            col(column)
+                .quantile(lit(0.25), QuantileMethod::Linear)
+                .alias("q1"),
+            col(column)
+                .quantile(lit(0.75), QuantileMethod::Linear)
+                .alias("q3"),
+            col("ext_fs_id")
+                .n_unique()
+                .cast(DataType::UInt64)
+                .alias("games"),
+        ])
+        .left_join(game_values, col("bucket"), col("bucket"))
+        .sort(["bucket"], Default::default())
+        .collect()?;
+    let buckets = res.column("bucket")?.i64()?;
+    let seconds = res.column("game_seconds")?.u32()?;
+    let values = res.column("value")?.f64()?;
+    let medians = res.column("median")?.f64()?;
+    let q1s = res.column("q1")?.f64()?;
+    let q3s = res.column("q3")?.f64()?;
+    let games = res.column("games")?.u64()?;
+    Ok((0..res.height())
+        .map(|idx| BenchmarkPoint {
+            ext_replay_loop: buckets.get(idx).unwrap_or(0) * BENCHMARK_BUCKET_LOOPS,
+            game_seconds: seconds.get(idx).unwrap_or(0),
+            value: values.get(idx),
+            median: medians.get(idx).unwrap_or(0.0),
+            q1: q1s.get(idx).unwrap_or(0.0),
+            q3: q3s.get(idx).unwrap_or(0.0),
+            games: games.get(idx).unwrap_or(0),
+        })
+        .collect())
+}
@@ -0,0 +1,129 @@
+//! Economy timelines from the stats IPC file.
+
+use crate::jobs::JobRunner;
+use crate::snapshot_registry::{Snapshot, SnapshotRegistry};
+use polars::prelude::*;
+use swarmy_tauri_common::*;
+use tauri::Manager;
+
+pub mod benchmark;
+pub use benchmark::*;
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn query_economy_timeline(
+    app_handle: tauri::AppHandle,
+    query: EconomyQuery,
+) -> ApiResponse<EconomyTimeline> {
+    let init_time = std::time::Instant::now();
+    let snapshots = app_handle.state::<SnapshotRegistry>().inner().clone();
+    let res = app_handle
+        .state::<JobRunner>()
+        .run("query_economy_timeline", move || {
+            try_query_economy_timeline(&snapshots, &query)
+        })
+        .await;
+    if let Err(e) = &res {
+        log::error!("Error querying economy timeline: {}", e);
+    }
+    ApiResponse::from_result(res, init_time.elapsed().as_millis() as u64)
+}
+
+/// Returns the player ids of the tracker events of each game along with the name of each player.
+/// The stats only carry the player id, the units born also carry who controls them.
+pub fn scan_tracker_players(snapshot: &Snapshot) -> Result<LazyFrame, SwarmyTauriError> {
+    Ok(snapshot
+        .scan(UNIT_BORN_IPC)?
+        .filter(col("player_name").is_not_null())
+        .select([
+            col("ext_fs_id"),
+            col("control_player_id")
+                .cast(DataType::UInt8)
+                .alias("player_id"),
+            col("player_name"),
+        ])
+        .unique(None, UniqueKeepStrategy::First))
+}
+
+/// Reads the stats samples of each player of a game, in the order of their player id.
+pub fn try_query_economy_timeline(
+    snapshots: &SnapshotRegistry,
+    query: &EconomyQuery,
+) -> Result<EconomyTimeline, SwarmyTauriError> {
+    log::info!(
+        "Querying economy timeline from: {:?} for game: {}",
+        query.replay_paths,
+        query.ext_fs_id
+    );
+    let snapshot = snapshots.open(&query.replay_paths)?;
+    let stats = snapshot
+        .scan(STATS_IPC)?
+        .filter(col("ext_fs_id").eq(lit(query.ext_fs_id)))
+        .select([
+            col("ext_fs_id"),
+            col("player_id").cast(DataType::UInt8),
+            col("ext_replay_loop"),
+            col("ext_replay_seconds"),
+            col("minerals_collection_rate"),
+            col("vespene_collection_rate"),
+            col("workers_active_count"),
+            col("food_used"),
+            col("food_made"),
+            (col("minerals_used_current_army") + col("vespene_used_current_army"))
+                .alias("army_value"),
+        ])
+        .join(
+            scan_tracker_players(&snapshot)?.filter(col("ext_fs_id").eq(lit(query.ext_fs_id))),
+            [col("ext_fs_id"), col("player_id")],
+            [col("ext_fs_id"), col("player_id")],
+            JoinArgs::new(JoinType::Left),
+        )
+        .sort(["player_id", "ext_replay_loop"], Default::default())
+        .collect()?;
+    if stats.height() == 0 {
+        return Err(SwarmyTauriError::Other(format!(
+            "No stats found for game: {}",
+            query.ext_fs_id
+        )));
+    }
+    let player_ids = stats.column("player_id")?.u8()?;
+    let player_names = stats.column("player_name")?.str()?;
+    let loops = stats.column("ext_replay_loop")?.i64()?;
+    let seconds = stats.column("ext_replay_seconds")?.u32()?;
+    let minerals = stats.column("minerals_collection_rate")?.i32()?;
+    let vespene = stats.column("vespene_collection_rate")?.i32()?;
+    let workers = stats.column("workers_active_count")?.i32()?;
+    let food_used = stats.column("food_used")?.i32()?;
+    let food_made = stats.column("food_made")?.i32()?;
+    let army_value = stats.column("army_value")?.i32()?;
+
+    let mut players: Vec<PlayerEconomy> = vec![];
+    for idx in 0..stats.height() {
+        let player_id = player_ids.get(idx).unwrap_or(0);
+        if players.last().map(|player| player.player_id) != Some(player_id) {
+            players.push(PlayerEconomy {
+                player_id,
+                player_name: player_names
+                    .get(idx)
+                    .map(str::to_string)
+                    .unwrap_or_else(|| format!("Player {}", player_id)),
+                samples: vec![],
+            });
+        }
+        if let Some(player) = players.last_mut() {
+            player.samples.push(EconomySample {
+                ext_replay_loop: loops.get(idx).unwrap_or(0),
+                game_seconds: seconds.get(idx).unwrap_or(0),
+                minerals_collection_rate: minerals.get(idx).unwrap_or(0),
+                vespene_collection_rate: vespene.get(idx).unwrap_or(0),
+                workers: workers.get(idx).unwrap_or(0),
+                supply_used: food_used.get(idx).unwrap_or(0),
+                supply_made: food_made.get(idx).unwrap_or(0),
+                army_value: army_value.get(idx).unwrap_or(0),
+            });
+        }
+    }
+    Ok(EconomyTimeline {
+        ext_fs_id: query.ext_fs_id,
+        players,
+    })
+}
@@ -0,0 +1,166 @@
+//! Exports query results to CSV, Parquet or NDJSON files.
+
+use crate::jobs::JobRunner;
+use crate::map_stats::{map_stats_frame, map_stats_rows};
+use crate::player_profile::player_filter;
+use crate::snapshot_registry::SnapshotRegistry;
+use crate::sql_query::sql_frame;
+use flate2::write::GzEncoder;
+use polars::prelude::*;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use swarmy_tauri_common::*;
+use tauri::Manager;
+use tauri_plugin_dialog::DialogExt;
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn export_query(
+    app_handle: tauri::AppHandle,
+    query: ExportQuery,
+) -> ApiResponse<Option<ExportResult>> {
+    let init_time = std::time::Instant::now();
+    let res = match pick_export_path(&app_handle, &query).await {
+        Ok(Some(path)) => {
+            let snapshots = app_handle.state::<SnapshotRegistry>().inner().clone();
+            app_handle
+                .state::<JobRunner>()
+                .run_background("export_query", move || {
+                    try_export_query(&snapshots, &query, &path).map(Some)
+                })
+                .await
+        }
+        // The user closed the dialog.
+        Ok(None) => Ok(None),
+        Err(e) => Err(e),
+    };
+    if let Err(e) = &res {
+        log::error!("Error exporting query: {}", e);
+    }
+    ApiResponse::from_result(res, init_time.elapsed().as_millis() as u64)
+}
+
+/// Asks the user where to save the export, None if the dialog is closed.
+async fn pick_export_path(
+    app_handle: &tauri::AppHandle,
+    query: &ExportQuery,
+) -> Result<Option<PathBuf>, SwarmyTauriError> {
+    let extension = query.format.extension(query.compression);
+    // The dialogs only match the last extension, i.e. `gz` of `csv.gz`.
+    let filter_extension = extension.rsplit('.').next().unwrap_or(extension);
+    let (sender, receiver) = tokio::sync::oneshot::channel();
+    app_handle
+        .dialog()
+        .file()
+        .add_filter(query.format.name(), &[filter_extension])
+        .set_file_name(format!("{}.{}", query.source.file_stem(), extension))
+        .save_file(move |path| {
+            let _ = sender.send(path);
+        });
+    match receiver.await {
+        Ok(Some(path)) => path
+            .into_path()
+            .map(Some)
+            .map_err(|e| SwarmyTauriError::Other(format!("Invalid export path: {}", e))),
+        _ => Ok(None),
+    }
+}
+
+/// Writes all the rows of the source of the query to `path`.
+/// The rows are streamed to the file, the result is never fully in memory.
+pub fn try_export_query(
+    snapshots: &SnapshotRegistry,
+    query: &ExportQuery,
+    path: &Path,
+) -> Result<ExportResult, SwarmyTauriError> {
+    log::info!(
+        "Exporting {} as {} to: {}",
+        query.source.file_stem(),
+        query.format.name(),
+        path.display()
+    );
+    let lf = export_frame(snapshots, &query.source)?;
+    if query.compression && query.format != ExportFormat::Parquet {
+        // The text sinks cannot compress, the plain file is gzipped once written.
+        let plain_path = path.with_extension("partial");
+        let res = sink_frame(lf, query.format, false, &plain_path)
+            .and_then(|_| gzip_file(&plain_path, path));
+        let _ = std::fs::remove_file(&plain_path);
+        res?;
+    } else {
+        sink_frame(lf, query.format, query.compression, path)?;
+    }
+    Ok(ExportResult {
+        path: path.display().to_string(),
+        file_size: std::fs::metadata(path)?.len(),
+    })
+}
+
+/// Plans all the rows of the source, without the paging and row limits of its query.
+pub fn export_frame(
+    snapshots: &SnapshotRegistry,
+    source: &ExportSource,
+) -> Result<LazyFrame, SwarmyTauriError> {
+    match source {
+        ExportSource::MapStats(query) => {
+            let snapshot = snapshots.open(&query.replay_paths)?;
+            Ok(map_stats_frame(map_stats_rows(&snapshot, query)?))
+        }
+        ExportSource::PlayerGames(query) => {
+            if query.player.trim().is_empty() {
+                return Err(SwarmyTauriError::Other(String::from("Player is empty")));
+            }
+            Ok(snapshots
+                .open(&query.replay_paths)?
+                .scan(DETAILS_IPC)?
+                .filter(player_filter(&query.player))
+                .sort(["ext_datetime"], Default::default()))
+        }
+        ExportSource::Sql(query) => sql_frame(&*snapshots.open(&query.replay_paths)?, &query.sql),
+    }
+}
+
+fn sink_frame(
+    lf: LazyFrame,
+    format: ExportFormat,
+    compression: bool,
+    path: &Path,
+) -> Result<(), SwarmyTauriError> {
+    let target = SinkTarget::Path(PlPath::new(&path.to_string_lossy()));
+    let lf = match format {
+        ExportFormat::Csv => lf.sink_csv(
+            target,
+            CsvWriterOptions::default(),
+            None,
+            SinkOptions::default(),
+        )?,
+        ExportFormat::Parquet => {
+            let options = ParquetWriteOptions {
+                compression: if compression {
+                    ParquetCompression::Zstd(None)
+                } else {
+                    ParquetCompression::Uncompressed
+                },
+                ..Default::default()
+            };
+            lf.sink_parquet(target, options, None, SinkOptions::default())?
+        }
+        ExportFormat::NdJson => lf.sink_json(
+            target,
+            JsonWriterOptions::default(),
+            None,
+            SinkOptions::default(),
+        )?,
+    };
+    // Collecting a sink runs the streaming engine, which writes the rows in batches.
+    lf.collect()?;
+    Ok(())
+}
+
+fn gzip_file(src: &Path, dst: &Path) -> Result<(), SwarmyTauriError> {
+    let mut reader = std::io::BufReader::new(std::fs::File::open(src)?);
+    let writer = std::io::BufWriter::new(std::fs::File::create(dst)?);
+    let mut encoder = GzEncoder::new(writer, flate2::Compression::default());
+    std::io::copy(&mut reader, &mut encoder)?;
+    encoder.finish()?.flush()?;
+    Ok(())
+}
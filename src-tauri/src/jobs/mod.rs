@@ -0,0 +1,181 @@
+//! Runs the work of the commands on a bounded thread pool and keeps track of it.
+
+use std::collections::VecDeque;
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use swarmy_tauri_common::*;
+
+#[tauri::command]
+pub fn list_jobs(jobs: tauri::State<JobRunner>) -> ApiResponse<Vec<JobInfo>> {
+    ApiResponse::ok(ResponseMetaBuilder::new(true).build(), jobs.list())
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub fn get_job_status(jobs: tauri::State<JobRunner>, job_id: u64) -> ApiResponse<JobInfo> {
+    let res = jobs
+        .get(job_id)
+        .ok_or_else(|| SwarmyTauriError::Other(format!("Job: {} not found", job_id)));
+    ApiResponse::from_result(res, 0)
+}
+
+/// The job runner, kept in the Tauri managed state.
+/// Jobs run in submission order on a pool of a fixed number of threads, the parallel iterators
+/// of a job share the same pool.
+/// Long jobs such as optimize run on a pool of their own so that the queries do not wait for them.
+pub struct JobRunner {
+    pool: rayon::ThreadPool,
+    background_pool: rayon::ThreadPool,
+    next_id: AtomicU64,
+    /// The running and most recent jobs, newest first.
+    jobs: Arc<Mutex<VecDeque<JobInfo>>>,
+}
+
+impl Default for JobRunner {
+    fn default() -> Self {
+        let num_threads = std::thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(2);
+        Self::new(num_threads)
+    }
+}
+
+impl JobRunner {
+    pub fn new(num_threads: usize) -> Self {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(num_threads)
+            .thread_name(|idx| format!("swarmy-job-{}", idx))
+            .build()
+            .expect("Unable to create the job runner thread pool");
+        let background_pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(num_threads)
+            .thread_name(|idx| format!("swarmy-background-job-{}", idx))
+            .build()
+            .expect("Unable to create the background job thread pool");
+        Self {
+            pool,
+            background_pool,
+            next_id: AtomicU64::new(1),
+            jobs: Arc::new(Mutex::new(VecDeque::new())),
+        }
+    }
+
+    /// Queues `job` and waits for its result without blocking the async runtime.
+    /// A panic in the job is returned as an error.
+    pub async fn run<T, F>(&self, name: &str, job: F) -> Result<T, SwarmyTauriError>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> Result<T, SwarmyTauriError> + Send + 'static,
+    {
+        self.run_on(&self.pool, name, job).await
+    }
+
+    /// Like [`JobRunner::run`] for the long jobs, i.e. scanning and optimizing a replay folder or
+    /// exporting a query, their parallel iterators run on the background pool.
+    pub async fn run_background<T, F>(&self, name: &str, job: F) -> Result<T, SwarmyTauriError>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> Result<T, SwarmyTauriError> + Send + 'static,
+    {
+        self.run_on(&self.background_pool, name, job).await
+    }
+
+    async fn run_on<T, F>(
+        &self,
+        pool: &rayon::ThreadPool,
+        name: &str,
+        job: F,
+    ) -> Result<T, SwarmyTauriError>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> Result<T, SwarmyTauriError> + Send + 'static,
+    {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        self.push(JobInfo {
+            id,
+            name: name.to_string(),
+            status: JobStatus::Queued,
+            queued_at_ms: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_millis() as u64)
+                .unwrap_or_default(),
+            duration_ms: 0,
+            error: None,
+        });
+        let (sender, receiver) = tokio::sync::oneshot::channel();
+        let jobs = self.jobs.clone();
+        pool.spawn(move || {
+            update_job(&jobs, id, |info| info.status = JobStatus::Running);
+            let init_time = std::time::Instant::now();
+            let res = std::panic::catch_unwind(AssertUnwindSafe(job))
+                .unwrap_or_else(|panic| Err(SwarmyTauriError::Other(panic_message(panic))));
+            update_job(&jobs, id, |info| {
+                info.duration_ms = init_time.elapsed().as_millis() as u64;
+                match &res {
+                    Ok(_) => info.status = JobStatus::Finished,
+                    Err(e) => {
+                        info.status = JobStatus::Failed;
+                        info.error = Some(e.to_string());
+                    }
+                }
+            });
+            // The command may have been dropped, there is nobody to tell then.
+            let _ = sender.send(res);
+        });
+        receiver
+            .await
+            .map_err(|_| SwarmyTauriError::Other(format!("Job: {} was dropped", id)))?
+    }
+
+    /// Returns the running and most recent jobs, newest first.
+    pub fn list(&self) -> Vec<JobInfo> {
+        self.jobs
+            .lock()
+            .map(|jobs| jobs.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    pub fn get(&self, id: u64) -> Option<JobInfo> {
+        self.jobs
+            .lock()
+            .ok()
+            .and_then(|jobs| jobs.iter().find(|info| info.id == id).cloned())
+    }
+
+    /// Adds a job, forgetting the oldest finished ones past [`JOB_HISTORY_SIZE`].
+    fn push(&self, info: JobInfo) {
+        if let Ok(mut jobs) = self.jobs.lock() {
+            jobs.push_front(info);
+            while jobs.len() > JOB_HISTORY_SIZE {
+                match jobs.iter().rposition(|info| {
+                    matches!(info.status, JobStatus::Finished | JobStatus::Failed)
+                }) {
+                    Some(idx) => {
+                        jobs.remove(idx);
+                    }
+                    None => break,
+                }
+            }
+        }
+    }
+}
+
+fn update_job<F>(jobs: &Mutex<VecDeque<JobInfo>>, id: u64, update: F)
+where
+    F: FnOnce(&mut JobInfo),
+{
+    if let Ok(mut jobs) = jobs.lock() {
+        if let Some(info) = jobs.iter_mut().find(|info| info.id == id) {
+            update(info);
+        }
+    }
+}
+
+fn panic_message(panic: Box<dyn std::any::Any + Send>) -> String {
+    let message = panic
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| panic.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| String::from("unknown panic"));
+    format!("The job panicked: {}", message)
+}
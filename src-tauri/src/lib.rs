@@ -10,6 +10,29 @@ pub mod common;
 pub use common::*;
 pub mod map_stats;
 pub use map_stats::*;
+pub mod sql_query;
+pub use sql_query::*;
+pub mod player_profile;
+pub use player_profile::*;
+pub mod build_order;
+pub use build_order::*;
+pub mod build_labels;
+pub use build_labels::*;
+pub mod economy;
+pub use economy::*;
+pub mod jobs;
+pub use jobs::*;
+pub mod snapshot_registry;
+pub use snapshot_registry::*;
+pub mod export;
+pub use export::*;
+pub mod library;
+pub use library::*;
+pub mod replay_watcher;
+pub use replay_watcher::*;
+
+use swarmy_tauri_common::SwarmyTauriError;
+use tauri::Manager;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -23,12 +46,52 @@ pub fn run() {
         )
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_opener::init())
+        .manage(OptimizeState::default())
+        .manage(JobRunner::default())
+        .manage(ReplayWatcher::default())
+        .manage(AbandonedQueries::default())
+        .setup(|app| {
+            // The queries resolve the snapshots under the configured directory from the start.
+            let store = tauri_plugin_store::StoreBuilder::new(app, "settings.json")
+                .build()
+                .map_err(|e| SwarmyTauriError::Other(format!("Error building store: {}", e)))?;
+            // If there are no saved settings yet, this will return an error so we ignore the return value.
+            let _ = store.reload();
+            let snapshot_root = snapshot_root_from_store(&store, app.handle())?;
+            app.manage(SnapshotRegistry::new(snapshot_root));
+            // A missing replay folder should not keep the app from starting.
+            if let Err(e) = refresh_replay_watcher(app.handle(), &store) {
+                log::error!("Error starting the replay watcher: {}", e);
+            }
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             get_current_app_config,
             basic_scan_replay_path,
             optimize_replay_path,
+            cancel_optimize,
             get_snapshot_metadata,
             query_map_stats,
+            run_sql_query,
+            query_player_profile,
+            query_head_to_head,
+            query_map_versions,
+            query_build_order,
+            compare_build_orders,
+            classify_build_orders,
+            get_build_labels,
+            query_economy_timeline,
+            query_benchmark,
+            list_jobs,
+            get_job_status,
+            autocomplete_map_titles,
+            autocomplete_player_names,
+            export_query,
+            save_library,
+            remove_library,
+            select_libraries,
+            set_snapshot_dir,
+            set_replay_watcher,
         ])
         .plugin(tauri_plugin_store::Builder::default().build())
         .run(tauri::generate_context!())
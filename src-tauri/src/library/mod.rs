@@ -0,0 +1,155 @@
+//! Manages the replay libraries and which of them the queries target.
+
+use crate::mpq_file_scan::try_get_current_app_config;
+use crate::replay_watcher::refresh_replay_watcher;
+use crate::settings::{load_libraries_from_store, save_libraries_to_store};
+use swarmy_tauri_common::*;
+use tauri_plugin_store::StoreBuilder;
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn save_library(
+    app_handle: tauri::AppHandle,
+    library: ReplayLibrary,
+) -> ApiResponse<AppSettings> {
+    update_libraries(&app_handle, |libraries, selection| {
+        try_save_library(libraries, selection, library)
+    })
+    .await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn remove_library(
+    app_handle: tauri::AppHandle,
+    name: String,
+) -> ApiResponse<AppSettings> {
+    update_libraries(&app_handle, |libraries, selection| {
+        try_remove_library(libraries, selection, &name)
+    })
+    .await
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn select_libraries(
+    app_handle: tauri::AppHandle,
+    selection: LibrarySelection,
+) -> ApiResponse<AppSettings> {
+    update_libraries(&app_handle, |libraries, current| {
+        try_select_libraries(libraries, current, selection)
+    })
+    .await
+}
+
+/// Applies `update` to the libraries in the store and returns the settings with the result.
+async fn update_libraries<F>(app_handle: &tauri::AppHandle, update: F) -> ApiResponse<AppSettings>
+where
+    F: FnOnce(&mut Vec<ReplayLibrary>, &mut LibrarySelection) -> Result<(), SwarmyTauriError>,
+{
+    let init_time = std::time::Instant::now();
+    let res = match StoreBuilder::new(app_handle, "settings.json").build() {
+        Ok(store) => {
+            // If there are no saved settings yet, this will return an error so we ignore the return value.
+            let _ = store.reload();
+            let (mut libraries, mut selection) = load_libraries_from_store(&store);
+            match update(&mut libraries, &mut selection) {
+                Ok(()) => {
+                    save_libraries_to_store(&store, &libraries, &selection);
+                    // The watcher follows the active library.
+                    if let Err(e) = refresh_replay_watcher(app_handle, &store) {
+                        log::error!("Error refreshing the replay watcher: {}", e);
+                    }
+                    try_get_current_app_config(app_handle).await
+                }
+                Err(e) => Err(e),
+            }
+        }
+        Err(e) => Err(SwarmyTauriError::Other(format!(
+            "Error building store: {}",
+            e
+        ))),
+    };
+    if let Err(e) = &res {
+        log::error!("Error updating libraries: {}", e);
+    }
+    ApiResponse::from_result(res, init_time.elapsed().as_millis() as u64)
+}
+
+/// Adds `library` or replaces the library with the same name.
+/// The first library becomes the active one.
+pub fn try_save_library(
+    libraries: &mut Vec<ReplayLibrary>,
+    selection: &mut LibrarySelection,
+    library: ReplayLibrary,
+) -> Result<(), SwarmyTauriError> {
+    let library = ReplayLibrary {
+        name: library.name.trim().to_string(),
+        ..library
+    };
+    if library.name.is_empty() {
+        return Err(SwarmyTauriError::Other(String::from(
+            "Library name is empty",
+        )));
+    }
+    if selection.active.is_empty() {
+        selection.active = library.name.clone();
+    }
+    match libraries
+        .iter_mut()
+        .find(|existing| existing.name == library.name)
+    {
+        Some(existing) => *existing = library,
+        None => libraries.push(library),
+    }
+    Ok(())
+}
+
+/// Removes the library `name`, its snapshot is kept on disk.
+pub fn try_remove_library(
+    libraries: &mut Vec<ReplayLibrary>,
+    selection: &mut LibrarySelection,
+    name: &str,
+) -> Result<(), SwarmyTauriError> {
+    let count = libraries.len();
+    libraries.retain(|library| library.name != name);
+    if libraries.len() == count {
+        return Err(SwarmyTauriError::Other(format!(
+            "Unknown library: {}",
+            name
+        )));
+    }
+    selection.union.retain(|union_name| union_name != name);
+    if selection.active == name {
+        selection.active = libraries
+            .first()
+            .map(|library| library.name.clone())
+            .unwrap_or_default();
+    }
+    Ok(())
+}
+
+/// Makes `selection` the libraries the queries target, all of them must exist.
+pub fn try_select_libraries(
+    libraries: &[ReplayLibrary],
+    current: &mut LibrarySelection,
+    selection: LibrarySelection,
+) -> Result<(), SwarmyTauriError> {
+    let unknown = std::iter::once(&selection.active)
+        .chain(selection.union.iter())
+        .find(|name| !libraries.iter().any(|library| library.name == **name));
+    if let Some(name) = unknown {
+        return Err(SwarmyTauriError::Other(format!(
+            "Unknown library: {}",
+            name
+        )));
+    }
+    let mut union = vec![];
+    for name in selection.union {
+        if name != selection.active && !union.contains(&name) {
+            union.push(name);
+        }
+    }
+    *current = LibrarySelection {
+        active: selection.active,
+        union,
+    };
+    Ok(())
+}
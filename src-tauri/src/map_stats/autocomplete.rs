@@ -0,0 +1,77 @@
+//! Suggestions for the map title and player name inputs.
+
+use crate::jobs::JobRunner;
+use crate::snapshot_registry::{Snapshot, SnapshotRegistry};
+use swarmy_tauri_common::*;
+use tauri::Manager;
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn autocomplete_map_titles(
+    app_handle: tauri::AppHandle,
+    query: AutocompleteQuery,
+) -> ApiResponse<Vec<String>> {
+    let init_time = std::time::Instant::now();
+    let snapshots = app_handle.state::<SnapshotRegistry>().inner().clone();
+    let res = app_handle
+        .state::<JobRunner>()
+        .run("autocomplete_map_titles", move || {
+            try_autocomplete(&snapshots, &query, Snapshot::map_titles)
+        })
+        .await;
+    if let Err(e) = &res {
+        log::error!("Error autocompleting map titles: {}", e);
+    }
+    ApiResponse::from_result(res, init_time.elapsed().as_millis() as u64)
+}
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn autocomplete_player_names(
+    app_handle: tauri::AppHandle,
+    query: AutocompleteQuery,
+) -> ApiResponse<Vec<String>> {
+    let init_time = std::time::Instant::now();
+    let snapshots = app_handle.state::<SnapshotRegistry>().inner().clone();
+    let res = app_handle
+        .state::<JobRunner>()
+        .run("autocomplete_player_names", move || {
+            try_autocomplete(&snapshots, &query, Snapshot::player_names)
+        })
+        .await;
+    if let Err(e) = &res {
+        log::error!("Error autocompleting player names: {}", e);
+    }
+    ApiResponse::from_result(res, init_time.elapsed().as_millis() as u64)
+}
+
+/// Returns the `values` of the snapshot that contain the term of the query, ignoring case, the
+/// ones that start with it first.
+pub fn try_autocomplete<F>(
+    snapshots: &SnapshotRegistry,
+    query: &AutocompleteQuery,
+    values: F,
+) -> Result<Vec<String>, SwarmyTauriError>
+where
+    F: FnOnce(&Snapshot) -> Result<&[String], SwarmyTauriError>,
+{
+    let term = query.term.trim().to_lowercase();
+    if term.is_empty() {
+        return Ok(vec![]);
+    }
+    let limit = if query.limit == 0 {
+        AUTOCOMPLETE_DEFAULT_LIMIT
+    } else {
+        query.limit
+    };
+    let snapshot = snapshots.open(&query.replay_paths)?;
+    let (mut prefixed, mut contained): (Vec<&String>, Vec<&String>) = (vec![], vec![]);
+    for value in values(&*snapshot)? {
+        let lowercase = value.to_lowercase();
+        if lowercase.starts_with(&term) {
+            prefixed.push(value);
+        } else if lowercase.contains(&term) {
+            contained.push(value);
+        }
+    }
+    prefixed.append(&mut contained);
+    Ok(prefixed.into_iter().take(limit).cloned().collect())
+}
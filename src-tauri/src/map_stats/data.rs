@@ -1,116 +1,77 @@
 //! Dataframe for map statistics.
 
+use super::{map_stats_frame, map_stats_rows};
+use crate::jobs::JobRunner;
+use crate::snapshot_registry::SnapshotRegistry;
 use polars::prelude::*;
 use swarmy_tauri_common::*;
+use tauri::Manager;
 
 #[tauri::command(rename_all = "snake_case")]
-pub async fn get_map_list(replay_path: String, player_name: String) -> ApiResponse {
-    let t = std::thread::spawn(move || {
-        let init_time = std::time::Instant::now();
-        match try_get_map_list(replay_path, player_name) {
-            Ok(val) => ApiResponse::new(
-                ResponseMetaBuilder::new(true)
-                    .duration_ms(init_time.elapsed().as_millis() as u64)
-                    .build(),
-                serde_json::to_string(&val).unwrap_or_default(),
-            ),
-            Err(e) => {
-                log::error!("Error getting snapshot metadata: {}", e);
-                ApiResponse::new(
-                    ResponseMetaBuilder::new(false)
-                        .duration_ms(init_time.elapsed().as_millis() as u64)
-                        .build(),
-                    format!("Error getting snapshot metadata: {:?}", e),
-                )
-            }
-        }
-    });
-    t.join().unwrap()
-}
-
-/// Gets the list of maps from the details.ipc file
-pub fn try_get_map_list(replay_path: String, player_name: String) -> Result<MapStats, SwarmyTauriError> {
-    let replay_path = format!("{}/ipcs/", replay_path);
-    log::info!("Getting map list from: {}", replay_path);
-    // Add the size of all the files in state.source_dir
-    let mut directory_size = 0;
-    for entry in std::fs::read_dir(&replay_path)? {
-        let entry = entry?;
-        let path = entry.path();
-        let metadata = std::fs::metadata(path)?;
-        let size = metadata.len();
-        directory_size += size;
-    }
-    // get the date_modified of the details.ipc file
-    let details_ipc_filename = format!("{}/{}", replay_path, DETAILS_IPC);
-    let date_modified = std::fs::metadata(details_ipc_filename)?.modified()?;
-    let mut details_query = LazyFrame::scan_ipc(
-        PlPath::new(&format!("{}/{}", replay_path, DETAILS_IPC)),
-        Default::default(),
-        Default::default(),
-    )?;
-
-    if !player_name.is_empty() {
-        details_query = details_query.filter(col("player").str().contains_literal(lit(player_name)));
+pub async fn get_map_list(
+    app_handle: tauri::AppHandle,
+    replay_paths: Vec<String>,
+    player_name: String,
+) -> ApiResponse<Vec<MapStats>> {
+    let init_time = std::time::Instant::now();
+    let snapshots = app_handle.state::<SnapshotRegistry>().inner().clone();
+    let res = app_handle
+        .state::<JobRunner>()
+        .run("get_map_list", move || {
+            try_get_map_list(&snapshots, replay_paths, player_name)
+        })
+        .await;
+    if let Err(e) = &res {
+        log::error!("Error getting map list: {}", e);
     }
+    ApiResponse::from_result(res, init_time.elapsed().as_millis() as u64)
+}
 
-    let res = details_query
-        .group_by([col("ext_datetime"), col("ext_fs_id"), col("title"), col("cache_handles")])
-        .agg([
-            col("ext_datetime")
-                .min()
-                .dt()
-                .to_string("%Y-%m-%d")
-                .alias("min_date"),
-            col("ext_datetime")
-                .max()
-                .dt()
-                .to_string("%Y-%m-%d")
-                .alias("max_date"),
-            col("title")
-                .unique()
-                .dt()
-                .to_string("%Y-%m-%d")
-                .alias("max_date"),
-            col("ext_fs_id").count().alias("num_games"),
+/// Gets the list of maps from the details.ipc file, one entry per title and cache_handles, most
+/// played first. The maps are not broken down by matchup.
+pub fn try_get_map_list(
+    snapshots: &SnapshotRegistry,
+    replay_paths: Vec<String>,
+    player_name: String,
+) -> Result<Vec<MapStats>, SwarmyTauriError> {
+    log::info!("Getting map list from: {:?}", replay_paths);
+    let snapshot = snapshots.open(&replay_paths)?;
+    let query = MapStatsQuery {
+        replay_paths,
+        player_name,
+        ..Default::default()
+    };
+    let directory_size = snapshot.directory_size();
+    let date_modified = snapshot.modified(DETAILS_IPC)?;
+    let res = map_stats_frame(map_stats_rows(&snapshot, &query)?)
+        .select([
+            col("title"),
+            col("cache_handles"),
+            col("min_date"),
+            col("max_date"),
+            col("num_games"),
         ])
         .collect()?;
-
-    let min_date_str = res
-        .column("min_date")?
-        .str()?
-        .get(0)
-        .unwrap_or("1970-01-01");
-    let max_date_str = res
-        .column("max_date")?
-        .str()?
-        .get(0)
-        .unwrap_or("1970-01-01");
-    let num_games = res.column("num_games")?.u64()?.get(0).unwrap_or(0) + 1;
-    let min_date = chrono::NaiveDate::parse_from_str(min_date_str, "%Y-%m-%d")
-        .unwrap_or(chrono::NaiveDate::from_ymd_opt(1970, 1, 1).unwrap());
-    let max_date = chrono::NaiveDate::parse_from_str(max_date_str, "%Y-%m-%d")
-        .unwrap_or(chrono::NaiveDate::from_ymd_opt(1970, 1, 1).unwrap());
-    let title = res
-        .column("title")?
-        .str()?
-        .get(0)
-        .unwrap_or("Unknown Map")
-        .to_string();
-    let cache_handles = res
-        .column("cache_handles")?
-        .str()?
-        .get(0)
-        .unwrap_or("")
-        .to_string();
-
-    Ok(MapStats {
-        directory_size,
-        date_modified,
-        max_date,
-        min_date,
-        num_games,
-        title,
-        cache_handles,
-    })
+    let titles = res.column("title")?.str()?;
+    let cache_handles = res.column("cache_handles")?.str()?;
+    let min_dates = res.column("min_date")?.str()?;
+    let max_dates = res.column("max_date")?.str()?;
+    let num_games = res.column("num_games")?.u64()?;
+    let default_date = chrono::NaiveDate::from_ymd_opt(1970, 1, 1).unwrap();
+    let parse_date = |date: Option<&str>| {
+        chrono::NaiveDate::parse_from_str(date.unwrap_or("1970-01-01"), "%Y-%m-%d")
+            .unwrap_or(default_date)
+    };
+    Ok((0..res.height())
+        .map(|idx| MapStats {
+            directory_size,
+            date_modified,
+            num_games: num_games.get(idx).unwrap_or(0),
+            title: titles.get(idx).unwrap_or("Unknown Map").to_string(),
+            cache_handles: cache_handles.get(idx).unwrap_or("").to_string(),
+            min_date: parse_date(min_dates.get(idx)),
+            max_date: parse_date(max_dates.get(idx)),
+            matchups: Vec::new(),
+        })
+        .collect())
 }
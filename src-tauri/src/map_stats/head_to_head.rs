@@ -0,0 +1,190 @@
+//! Games between two players in the details IPC file.
+
+use crate::jobs::JobRunner;
+use crate::player_profile::{player_filter, player_record_aggs, player_records_by};
+use crate::snapshot_registry::SnapshotRegistry;
+use polars::prelude::*;
+use swarmy_tauri_common::*;
+use tauri::Manager;
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn query_head_to_head(
+    app_handle: tauri::AppHandle,
+    query: HeadToHeadQuery,
+) -> ApiResponse<HeadToHead> {
+    let init_time = std::time::Instant::now();
+    let snapshots = app_handle.state::<SnapshotRegistry>().inner().clone();
+    let res = app_handle
+        .state::<JobRunner>()
+        .run("query_head_to_head", move || {
+            try_query_head_to_head(&snapshots, query)
+        })
+        .await;
+    if let Err(e) = &res {
+        log::error!("Error querying head to head: {}", e);
+    }
+    ApiResponse::from_result(res, init_time.elapsed().as_millis() as u64)
+}
+
+/// Finds the games where both players played in different teams and aggregates them from the
+/// side of player A.
+pub fn try_query_head_to_head(
+    snapshots: &SnapshotRegistry,
+    query: HeadToHeadQuery,
+) -> Result<HeadToHead, SwarmyTauriError> {
+    log::info!(
+        "Querying head to head from: {:?} for: {} vs {}",
+        query.replay_paths,
+        query.player_a,
+        query.player_b
+    );
+    if query.player_a.trim().is_empty() || query.player_b.trim().is_empty() {
+        return Err(SwarmyTauriError::Other(String::from(
+            "Both players are required",
+        )));
+    }
+    let snapshot = snapshots.open(&query.replay_paths)?;
+    let details = snapshot.scan(DETAILS_IPC)?;
+    let player_a_rows = details
+        .clone()
+        .filter(player_filter(&query.player_a))
+        .select([
+            col("ext_fs_id"),
+            col("ext_datetime"),
+            col("title"),
+            col("player_name").alias("player_name_a"),
+            race_id_expr(col("player_race")).alias("race_a"),
+            col("player_result"),
+            col("player_team_id").alias("team_a"),
+        ]);
+    let player_b_rows = details.filter(player_filter(&query.player_b)).select([
+        col("ext_fs_id"),
+        col("player_name").alias("player_name_b"),
+        race_id_expr(col("player_race")).alias("race_b"),
+        col("player_team_id").alias("team_b"),
+    ]);
+    let mut games = player_a_rows
+        .inner_join(player_b_rows, col("ext_fs_id"), col("ext_fs_id"))
+        .filter(col("team_a").neq(col("team_b")))
+        .with_columns([
+            concat_str([col("race_a"), col("race_b")], " vs ", false).alias("races"),
+            col("ext_datetime").dt().to_string("%Y-%m").alias("month"),
+        ]);
+    // The stats are sampled every few seconds during the game, the last one is close to its end.
+    games = if let Some(stats) = snapshot.scan_optional(STATS_IPC) {
+        let game_lengths = stats
+            .group_by([col("ext_fs_id")])
+            .agg([col("ext_replay_seconds").max().alias("game_seconds")]);
+        games
+            .left_join(game_lengths, col("ext_fs_id"), col("ext_fs_id"))
+            .with_column(
+                col("game_seconds")
+                    .cast(DataType::UInt32)
+                    .fill_null(lit(0u32)),
+            )
+    } else {
+        games.with_column(lit(0u32).alias("game_seconds"))
+    };
+    let games = games.cache();
+
+    let mut overall_aggs = player_record_aggs();
+    overall_aggs.push(
+        col("game_seconds")
+            .filter(col("game_seconds").gt(lit(0u32)))
+            .mean()
+            .fill_null(lit(0.0))
+            .alias("avg_game_seconds"),
+    );
+    let overall = games.clone().select(overall_aggs).collect()?;
+    let record = PlayerRecord {
+        games: overall.column("games")?.u64()?.get(0).unwrap_or(0),
+        wins: overall.column("wins")?.u64()?.get(0).unwrap_or(0),
+        losses: overall.column("losses")?.u64()?.get(0).unwrap_or(0),
+    };
+    let avg_game_seconds = overall
+        .column("avg_game_seconds")?
+        .cast(&DataType::Float64)?
+        .f64()?
+        .get(0)
+        .unwrap_or(0.0);
+
+    let maps = player_records_by(games.clone(), "title", None)?
+        .into_iter()
+        .map(|(title, record)| PlayerMapRecord { title, record })
+        .collect();
+    let races = player_records_by(games.clone(), "races", None)?
+        .into_iter()
+        .map(|(races, record)| {
+            let (race_a, race_b) = races.split_once(" vs ").unwrap_or((&races, ""));
+            HeadToHeadRaceRecord {
+                race_a: race_a.to_string(),
+                race_b: race_b.to_string(),
+                record,
+            }
+        })
+        .collect();
+    let mut months: Vec<HeadToHeadMonthRecord> = player_records_by(games.clone(), "month", None)?
+        .into_iter()
+        .map(|(month, record)| HeadToHeadMonthRecord { month, record })
+        .collect();
+    months.sort_by(|a, b| a.month.cmp(&b.month));
+
+    let games_df = games
+        .select([
+            col("ext_fs_id"),
+            col("ext_datetime")
+                .dt()
+                .to_string("%Y-%m-%dT%H:%M:%S")
+                .alias("date"),
+            col("title"),
+            col("player_name_a"),
+            col("player_name_b"),
+            col("race_a"),
+            col("race_b"),
+            col("player_result"),
+            col("game_seconds"),
+        ])
+        .sort(
+            ["date"],
+            SortMultipleOptions::default().with_order_descending(true),
+        )
+        .collect()?;
+    let ext_fs_ids = games_df.column("ext_fs_id")?.u64()?;
+    let dates = games_df.column("date")?.str()?;
+    let titles = games_df.column("title")?.str()?;
+    let player_names_a = games_df.column("player_name_a")?.str()?;
+    let player_names_b = games_df.column("player_name_b")?.str()?;
+    let races_a = games_df.column("race_a")?.str()?;
+    let races_b = games_df.column("race_b")?.str()?;
+    let results = games_df.column("player_result")?.str()?;
+    let game_seconds = games_df.column("game_seconds")?.u32()?;
+    let games = (0..games_df.height())
+        .map(|idx| HeadToHeadGame {
+            ext_fs_id: ext_fs_ids.get(idx).unwrap_or(0),
+            date: dates
+                .get(idx)
+                .and_then(|date| {
+                    chrono::NaiveDateTime::parse_from_str(date, "%Y-%m-%dT%H:%M:%S").ok()
+                })
+                .unwrap_or_default(),
+            title: titles.get(idx).unwrap_or("Unknown Map").to_string(),
+            player_name_a: player_names_a.get(idx).unwrap_or("").to_string(),
+            player_name_b: player_names_b.get(idx).unwrap_or("").to_string(),
+            race_a: races_a.get(idx).unwrap_or("").to_string(),
+            race_b: races_b.get(idx).unwrap_or("").to_string(),
+            result_a: results.get(idx).unwrap_or("").to_string(),
+            game_seconds: game_seconds.get(idx).unwrap_or(0),
+        })
+        .collect();
+
+    Ok(HeadToHead {
+        player_a: query.player_a,
+        player_b: query.player_b,
+        record,
+        maps,
+        races,
+        months,
+        avg_game_seconds,
+        games,
+    })
+}
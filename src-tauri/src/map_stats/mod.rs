@@ -1,50 +1,249 @@
-use std::path::PathBuf;
-
+use crate::build_labels::join_build_labels;
+use crate::jobs::JobRunner;
+use crate::player_profile::player_filter;
+use crate::snapshot_registry::{Snapshot, SnapshotRegistry};
+use polars::prelude::*;
+use std::collections::HashMap;
 use swarmy_tauri_common::*;
+use tauri::Manager;
 
+pub mod autocomplete;
+pub use autocomplete::*;
 pub mod data;
+pub mod head_to_head;
+pub use head_to_head::*;
+pub mod versions;
+pub use versions::*;
 
 #[tauri::command(rename_all = "snake_case")]
 pub async fn query_map_stats(
-    _app_handle: tauri::AppHandle,
+    app_handle: tauri::AppHandle,
     query: MapStatsQuery,
-) -> ApiResponse {
-    // create a thread to scan the directory in the background:
-    let t = std::thread::spawn(move || {
-        let init_time = std::time::Instant::now();
-        match try_query_map_stats(query)
-        {
-            Ok(val) => ApiResponse::new(
-            ResponseMetaBuilder::new(true)
-                .duration_ms(init_time.elapsed().as_millis() as u64)
-                .build(),
-            val,
-        ),
-            Err(e) => {
-            log::error!("Error query maps: {}", e);
-        ApiResponse::new(
-            ResponseMetaBuilder::new(true)
-                .duration_ms(init_time.elapsed().as_millis() as u64)
-                .build(),
-            format!("Error optimizing replays: {:?}", e))
-        }}
-    });
-    t.join().unwrap()
+) -> ApiResponse<MapStatsDataFrame> {
+    let init_time = std::time::Instant::now();
+    let snapshots = app_handle.state::<SnapshotRegistry>().inner().clone();
+    let res = app_handle
+        .state::<JobRunner>()
+        .run("query_map_stats", move || {
+            try_query_map_stats(&snapshots, query)
+        })
+        .await;
+    if let Err(e) = &res {
+        log::error!("Error querying map stats: {}", e);
+    }
+    ApiResponse::from_result(res, init_time.elapsed().as_millis() as u64)
 }
 
-fn try_query_map_stats(
+/// Aggregates the details.ipc file per map title and cache_handles and returns the requested page.
+pub fn try_query_map_stats(
+    snapshots: &SnapshotRegistry,
     query: MapStatsQuery,
-) -> Result<String, SwarmyTauriError> {
-    let path = PathBuf::from(&query.replay_path);
-    let destination = path.join("ipcs");
-    if !destination.exists() {
-        std::fs::create_dir_all(&destination)?;
-    }
+) -> Result<MapStatsDataFrame, SwarmyTauriError> {
+    let snapshot = snapshots.open(&query.replay_paths)?;
     log::info!(
-        "Querying map stats from replay path: {} for map_title: {} and player_name: {}",
-        query.replay_path,
+        "Querying map stats from: {} for map_title: {} and player_name: {}",
+        snapshot.ipc_path().display(),
         query.map_title,
         query.player_name
     );
-    Ok("Not implemented yet".to_string())
+    let directory_size = snapshot.directory_size();
+    let date_modified = snapshot.modified(DETAILS_IPC)?;
+    let details_query = map_stats_rows(&snapshot, &query)?;
+    let res = map_stats_frame(details_query.clone()).collect()?;
+
+    let total = res.height();
+    let per_page = if query.per_page == 0 {
+        MAP_STATS_DEFAULT_PER_PAGE
+    } else {
+        query.per_page
+    };
+    let start = query.page.saturating_mul(per_page).min(total);
+    let end = start.saturating_add(per_page).min(total);
+    let page_df = res.slice(start as i64, end - start);
+
+    let titles = page_df.column("title")?.str()?;
+    let cache_handles = page_df.column("cache_handles")?.str()?;
+    let min_dates = page_df.column("min_date")?.str()?;
+    let max_dates = page_df.column("max_date")?.str()?;
+    let num_games = page_df.column("num_games")?.u64()?;
+    let default_date = chrono::NaiveDate::from_ymd_opt(1970, 1, 1).unwrap();
+    let parse_date = |date: Option<&str>| {
+        chrono::NaiveDate::parse_from_str(date.unwrap_or("1970-01-01"), "%Y-%m-%d")
+            .unwrap_or(default_date)
+    };
+    // Only the maps of the page are broken down by matchup.
+    let page_maps = page_df.select(["title", "cache_handles"])?.lazy();
+    let page_rows = details_query.join(
+        page_maps,
+        [col("title"), col("cache_handles")],
+        [col("title"), col("cache_handles")],
+        JoinArgs::new(JoinType::Inner),
+    );
+    let mut matchups = query_matchup_win_rates(page_rows, snapshot.scan(DETAILS_IPC)?)?;
+    let mut rows = Vec::with_capacity(page_df.height());
+    for idx in 0..page_df.height() {
+        rows.push(MapStats {
+            directory_size,
+            date_modified,
+            num_games: num_games.get(idx).unwrap_or(0),
+            title: titles.get(idx).unwrap_or("Unknown Map").to_string(),
+            cache_handles: cache_handles.get(idx).unwrap_or("").to_string(),
+            min_date: parse_date(min_dates.get(idx)),
+            max_date: parse_date(max_dates.get(idx)),
+            matchups: matchups
+                .remove(&(
+                    titles.get(idx).unwrap_or("").to_string(),
+                    cache_handles.get(idx).unwrap_or("").to_string(),
+                ))
+                .unwrap_or_default(),
+        });
+    }
+
+    Ok(MapStatsDataFrame {
+        total,
+        res: rows,
+        start,
+        end,
+        page: query.page,
+        per_page,
+    })
+}
+
+/// Returns the details rows of the games matching the map, player and opening of the query.
+pub fn map_stats_rows(
+    snapshot: &Snapshot,
+    query: &MapStatsQuery,
+) -> Result<LazyFrame, SwarmyTauriError> {
+    let mut details_query = snapshot.scan(DETAILS_IPC)?;
+    if !query.map_title.is_empty() {
+        details_query =
+            details_query.filter(col("title").str().contains_literal(lit(query.map_title.clone())));
+    }
+    // Each row is a player in a game, so keeping only the rows of the player still yields one
+    // row per game they played. The player is matched the same way as by the Player page.
+    if !query.player_name.trim().is_empty() {
+        details_query = details_query.filter(player_filter(&query.player_name));
+    }
+    if !query.opening.is_empty() {
+        let labels = snapshot.scan_optional(BUILD_LABELS_IPC).ok_or_else(|| {
+            SwarmyTauriError::Other(String::from(
+                "The snapshot has no build labels, classify the build orders first",
+            ))
+        })?;
+        details_query = join_build_labels(details_query, labels)
+            .filter(col("label").eq(lit(query.opening.clone())));
+    }
+    Ok(details_query)
+}
+
+/// Aggregates the details rows per map title and cache_handles, most played first.
+pub fn map_stats_frame(details_query: LazyFrame) -> LazyFrame {
+    details_query
+        .group_by([col("title"), col("cache_handles")])
+        .agg([
+            col("ext_datetime")
+                .min()
+                .dt()
+                .to_string("%Y-%m-%d")
+                .alias("min_date"),
+            col("ext_datetime")
+                .max()
+                .dt()
+                .to_string("%Y-%m-%d")
+                .alias("max_date"),
+            col("ext_fs_id")
+                .n_unique()
+                .cast(DataType::UInt64)
+                .alias("num_games"),
+        ])
+        .sort_by_exprs(
+            [col("num_games"), col("title")],
+            SortMultipleOptions::default().with_order_descending_multi([true, false]),
+        )
+}
+
+/// Computes the win rate of each race pairing per map, keyed by title and cache_handles, from the
+/// side of the `player_rows` in the 1v1 games of `details`.
+/// A game whose both players are in `player_rows` is counted once, from the side of the race that
+/// sorts first, or of the lowest team in mirror matchups.
+pub fn query_matchup_win_rates(
+    player_rows: LazyFrame,
+    details: LazyFrame,
+) -> Result<HashMap<(String, String), Vec<MatchupWinRate>>, SwarmyTauriError> {
+    let one_v_one_games = details
+        .clone()
+        .group_by([col("ext_fs_id")])
+        .agg([len().alias("num_players")])
+        .filter(col("num_players").eq(lit(2)))
+        .select([col("ext_fs_id")]);
+    let opponents = details.select([
+        col("ext_fs_id"),
+        col("player_team_id").alias("opponent_team_id"),
+        race_id_expr(col("player_race")).alias("opponent_race"),
+    ]);
+    let sides = player_rows
+        .select([
+            col("ext_fs_id"),
+            col("title"),
+            col("cache_handles"),
+            race_id_expr(col("player_race")).alias("player_race"),
+            col("player_result"),
+            col("player_team_id"),
+        ])
+        .inner_join(one_v_one_games, col("ext_fs_id"), col("ext_fs_id"))
+        .inner_join(opponents, col("ext_fs_id"), col("ext_fs_id"))
+        .filter(col("player_team_id").neq(col("opponent_team_id")));
+    let sides_per_game = sides
+        .clone()
+        .group_by([col("ext_fs_id")])
+        .agg([len().alias("sides")]);
+    let res = sides
+        .inner_join(sides_per_game, col("ext_fs_id"), col("ext_fs_id"))
+        .filter(
+            col("sides")
+                .eq(lit(1))
+                .or(col("player_race")
+                    .lt(col("opponent_race"))
+                    .or(col("player_race")
+                        .eq(col("opponent_race"))
+                        .and(col("player_team_id").lt(col("opponent_team_id"))))),
+        )
+        .group_by([
+            col("title"),
+            col("cache_handles"),
+            col("player_race"),
+            col("opponent_race"),
+        ])
+        .agg([
+            len().cast(DataType::UInt64).alias("games"),
+            col("player_result")
+                .eq(lit("Win"))
+                .sum()
+                .cast(DataType::UInt64)
+                .alias("wins"),
+        ])
+        .sort(["player_race", "opponent_race"], Default::default())
+        .collect()?;
+    let titles = res.column("title")?.str()?;
+    let cache_handles = res.column("cache_handles")?.str()?;
+    let races = res.column("player_race")?.str()?;
+    let opponent_races = res.column("opponent_race")?.str()?;
+    let games = res.column("games")?.u64()?;
+    let wins = res.column("wins")?.u64()?;
+    let mut matchups: HashMap<(String, String), Vec<MatchupWinRate>> = HashMap::new();
+    for idx in 0..res.height() {
+        matchups
+            .entry((
+                titles.get(idx).unwrap_or("").to_string(),
+                cache_handles.get(idx).unwrap_or("").to_string(),
+            ))
+            .or_default()
+            .push(MatchupWinRate::new(
+                races.get(idx).unwrap_or("").to_string(),
+                opponent_races.get(idx).unwrap_or("").to_string(),
+                games.get(idx).unwrap_or(0),
+                wins.get(idx).unwrap_or(0),
+            ));
+    }
+    Ok(matchups)
 }
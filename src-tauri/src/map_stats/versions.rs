@@ -0,0 +1,114 @@
+//! The versions of a map, told apart by their cache_handles.
+
+use super::query_matchup_win_rates;
+use crate::jobs::JobRunner;
+use crate::snapshot_registry::SnapshotRegistry;
+use polars::prelude::*;
+use swarmy_tauri_common::*;
+use tauri::Manager;
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn query_map_versions(
+    app_handle: tauri::AppHandle,
+    query: MapVersionsQuery,
+) -> ApiResponse<MapVersions> {
+    let init_time = std::time::Instant::now();
+    let snapshots = app_handle.state::<SnapshotRegistry>().inner().clone();
+    let res = app_handle
+        .state::<JobRunner>()
+        .run("query_map_versions", move || {
+            try_query_map_versions(&snapshots, query)
+        })
+        .await;
+    if let Err(e) = &res {
+        log::error!("Error querying map versions: {}", e);
+    }
+    ApiResponse::from_result(res, init_time.elapsed().as_millis() as u64)
+}
+
+/// Aggregates the games of a map per cache_handles, oldest version first, along with how the
+/// win rate of each matchup changed from the previous version.
+pub fn try_query_map_versions(
+    snapshots: &SnapshotRegistry,
+    query: MapVersionsQuery,
+) -> Result<MapVersions, SwarmyTauriError> {
+    log::info!(
+        "Querying map versions from: {:?} for map_title: {}",
+        query.replay_paths,
+        query.map_title
+    );
+    if query.map_title.trim().is_empty() {
+        return Err(SwarmyTauriError::Other(String::from(
+            "The map title is required",
+        )));
+    }
+    let details = snapshots.open(&query.replay_paths)?.scan(DETAILS_IPC)?;
+    let map_rows = details
+        .clone()
+        .filter(col("title").eq(lit(query.map_title.clone())));
+    let res = map_rows
+        .clone()
+        .group_by([col("cache_handles")])
+        .agg([
+            col("ext_datetime").min().dt().date().alias("min_date"),
+            col("ext_datetime").max().dt().date().alias("max_date"),
+            col("ext_fs_id")
+                .n_unique()
+                .cast(DataType::UInt64)
+                .alias("num_games"),
+        ])
+        .sort_by_exprs(
+            [col("min_date"), col("max_date")],
+            SortMultipleOptions::default(),
+        )
+        .select([
+            col("cache_handles"),
+            col("min_date").dt().to_string("%Y-%m-%d"),
+            col("max_date").dt().to_string("%Y-%m-%d"),
+            col("num_games"),
+        ])
+        .collect()?;
+    let mut matchups = query_matchup_win_rates(map_rows, details)?;
+
+    let cache_handles = res.column("cache_handles")?.str()?;
+    let min_dates = res.column("min_date")?.str()?;
+    let max_dates = res.column("max_date")?.str()?;
+    let num_games = res.column("num_games")?.u64()?;
+    let default_date = chrono::NaiveDate::from_ymd_opt(1970, 1, 1).unwrap();
+    let parse_date = |date: Option<&str>| {
+        chrono::NaiveDate::parse_from_str(date.unwrap_or("1970-01-01"), "%Y-%m-%d")
+            .unwrap_or(default_date)
+    };
+    let mut versions: Vec<MapVersion> = Vec::with_capacity(res.height());
+    for idx in 0..res.height() {
+        let cache_handles = cache_handles.get(idx).unwrap_or("").to_string();
+        let version_matchups = matchups
+            .remove(&(query.map_title.clone(), cache_handles.clone()))
+            .unwrap_or_default();
+        let shifts = match versions.last() {
+            Some(previous) => version_matchups
+                .iter()
+                .filter_map(|current| {
+                    previous
+                        .matchups
+                        .iter()
+                        .find(|matchup| matchup.matchup == current.matchup)
+                        .map(|previous| MatchupShift::between(previous, current))
+                })
+                .collect(),
+            None => vec![],
+        };
+        versions.push(MapVersion {
+            cache_handles,
+            min_date: parse_date(min_dates.get(idx)),
+            max_date: parse_date(max_dates.get(idx)),
+            num_games: num_games.get(idx).unwrap_or(0),
+            matchups: version_matchups,
+            shifts,
+        });
+    }
+    Ok(MapVersions {
+        title: query.map_title,
+        versions,
+    })
+}
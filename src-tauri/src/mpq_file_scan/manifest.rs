@@ -0,0 +1,368 @@
+//! Tracks the replays that are already part of a snapshot so that later optimize runs only
+//! process the new or changed files.
+
+use polars::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use swarmy_tauri_common::*;
+
+/// The file stored next to the IPC files with the processed replays.
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// The directory with the IPC files written by the incremental runs, see
+/// [`SnapshotManifest::parts`].
+pub const SNAPSHOT_PARTS_DIR: &str = "parts";
+
+/// A replay that has been processed into the snapshot.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ManifestEntry {
+    /// The size of the file in bytes.
+    pub size: u64,
+    /// The modification time of the file in seconds since the unix epoch.
+    pub mtime: u64,
+    /// The sha256 of the file contents.
+    pub sha256: String,
+    /// The ext_fs_id the rows of this replay have in the IPC files.
+    pub ext_fs_id: u64,
+    /// Whether the file could not be parsed, these are retried only if they change.
+    pub failed: bool,
+}
+
+/// The processed replays keyed by their path.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct SnapshotManifest {
+    /// The next ext_fs_id to assign, ids are never reused.
+    pub next_ext_fs_id: u64,
+    pub files: HashMap<String, ManifestEntry>,
+    /// The number of incremental runs since the IPC files were written in full, each one wrote
+    /// the rows of its replays to the IPC files of its own part directory, `parts/1`, `parts/2`...
+    #[serde(default)]
+    pub parts: u32,
+    /// The replays that were deleted or changed since their rows were written, their rows are
+    /// left out when the snapshot is scanned.
+    #[serde(default)]
+    pub dropped_ext_fs_ids: Vec<u64>,
+}
+
+/// A replay that must be parsed in this run.
+#[derive(Debug, Clone)]
+pub struct PendingReplay {
+    pub path: PathBuf,
+    pub entry: ManifestEntry,
+}
+
+impl SnapshotManifest {
+    /// Loads the manifest from the snapshot directory, if there is one.
+    pub fn load(ipc_dir: &Path) -> Result<Option<Self>, SwarmyTauriError> {
+        let manifest_path = ipc_dir.join(MANIFEST_FILE);
+        if !manifest_path.exists() {
+            return Ok(None);
+        }
+        let contents = std::fs::read_to_string(manifest_path)?;
+        Ok(Some(serde_json::from_str(&contents)?))
+    }
+
+    /// Writes the manifest to the snapshot directory.
+    pub fn save(&self, ipc_dir: &Path) -> Result<(), SwarmyTauriError> {
+        let contents = serde_json::to_string(self)?;
+        std::fs::write(ipc_dir.join(MANIFEST_FILE), contents)?;
+        Ok(())
+    }
+
+    /// Returns the files that are new or whose contents changed since they were processed, both
+    /// get a fresh ext_fs_id and the rows of the changed files are dropped.
+    /// Files whose size or mtime changed but whose contents did not are only refreshed.
+    /// The replays that no longer exist are forgotten and their rows dropped, the ones that were
+    /// not found because of the scan limits are kept.
+    pub fn pending_replays(
+        &mut self,
+        files: &[PathBuf],
+    ) -> Result<Vec<PendingReplay>, SwarmyTauriError> {
+        let mut pending = vec![];
+        for file in files {
+            let metadata = std::fs::metadata(file)?;
+            let size = metadata.len();
+            let mtime = metadata
+                .modified()?
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs();
+            let key = file.to_string_lossy().to_string();
+            if let Some(entry) = self.files.get_mut(&key) {
+                if entry.size == size && entry.mtime == mtime {
+                    continue;
+                }
+                let sha256 = sha256::try_digest(file.as_path())?;
+                if entry.sha256 == sha256 {
+                    entry.size = size;
+                    entry.mtime = mtime;
+                    continue;
+                }
+                // The new rows are told apart from the old ones by their id.
+                if !entry.failed {
+                    self.dropped_ext_fs_ids.push(entry.ext_fs_id);
+                }
+                pending.push(PendingReplay {
+                    path: file.clone(),
+                    entry: ManifestEntry {
+                        size,
+                        mtime,
+                        sha256,
+                        ext_fs_id: self.next_ext_fs_id,
+                        failed: false,
+                    },
+                });
+            } else {
+                let sha256 = sha256::try_digest(file.as_path())?;
+                pending.push(PendingReplay {
+                    path: file.clone(),
+                    entry: ManifestEntry {
+                        size,
+                        mtime,
+                        sha256,
+                        ext_fs_id: self.next_ext_fs_id,
+                        failed: false,
+                    },
+                });
+            }
+            self.next_ext_fs_id += 1;
+        }
+        let found: HashSet<String> = files
+            .iter()
+            .map(|file| file.to_string_lossy().to_string())
+            .collect();
+        let deleted: Vec<String> = self
+            .files
+            .keys()
+            .filter(|key| !found.contains(*key) && !Path::new(key).exists())
+            .cloned()
+            .collect();
+        for key in deleted {
+            if let Some(entry) = self.files.remove(&key) {
+                if !entry.failed {
+                    self.dropped_ext_fs_ids.push(entry.ext_fs_id);
+                }
+            }
+        }
+        Ok(pending)
+    }
+
+    /// Forgets a pending replay that was left out of the snapshot, i.e. by the version range or
+    /// process_max_files, so that a later run with other limits processes it as a new replay.
+    pub fn forget(&mut self, replay: &PendingReplay) {
+        self.files
+            .remove(&replay.path.to_string_lossy().to_string());
+    }
+
+    /// Records the outcome of parsing a pending replay.
+    pub fn record(&mut self, replay: &PendingReplay, failed: bool) {
+        self.files.insert(
+            replay.path.to_string_lossy().to_string(),
+            ManifestEntry {
+                failed,
+                ..replay.entry.clone()
+            },
+        );
+    }
+
+    /// The directory of the IPC files written by the incremental run `part`, counted from 1.
+    pub fn part_dir(ipc_dir: &Path, part: u32) -> PathBuf {
+        ipc_dir.join(SNAPSHOT_PARTS_DIR).join(part.to_string())
+    }
+
+    /// Scans the rows of `file_name` in the snapshot of `ipc_dir`, the rows of the file followed
+    /// by the ones of its parts, without the rows of the dropped replays.
+    /// None if neither the snapshot nor its parts have the file.
+    pub fn scan_ipc(
+        &self,
+        ipc_dir: &Path,
+        file_name: &str,
+    ) -> Result<Option<LazyFrame>, SwarmyTauriError> {
+        let mut frames = std::iter::once(ipc_dir.join(file_name))
+            .chain((1..=self.parts).map(|part| Self::part_dir(ipc_dir, part).join(file_name)))
+            .filter(|path| path.exists())
+            .map(|path| {
+                LazyFrame::scan_ipc(
+                    PlPath::new(&path.to_string_lossy()),
+                    Default::default(),
+                    Default::default(),
+                )
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut frame = match frames.len() {
+            0 => return Ok(None),
+            1 => frames.remove(0),
+            _ => concat(frames, UnionArgs::default())?,
+        };
+        if self.dropped_ext_fs_ids.is_empty() || !frame.collect_schema()?.contains("ext_fs_id") {
+            return Ok(Some(frame));
+        }
+        let dropped = Series::new("ext_fs_id".into(), &self.dropped_ext_fs_ids);
+        Ok(Some(frame.filter(
+            col("ext_fs_id").is_in(lit(dropped).implode(), false).not(),
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write_replay(dir: &TempDir, name: &str, contents: &str) -> PathBuf {
+        let path = dir.path().join(name);
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    fn record_all(manifest: &mut SnapshotManifest, pending: &[PendingReplay]) {
+        for replay in pending {
+            manifest.record(replay, false);
+        }
+    }
+
+    fn ext_fs_id(manifest: &SnapshotManifest, path: &Path) -> u64 {
+        manifest.files[&path.to_string_lossy().to_string()].ext_fs_id
+    }
+
+    #[test]
+    fn new_replays_are_pending_once() {
+        let dir = TempDir::new().unwrap();
+        let first = write_replay(&dir, "first.SC2Replay", "first");
+        let second = write_replay(&dir, "second.SC2Replay", "second");
+        let files = vec![first.clone(), second.clone()];
+        let mut manifest = SnapshotManifest::default();
+        let pending = manifest.pending_replays(&files).unwrap();
+        assert_eq!(pending.len(), 2);
+        record_all(&mut manifest, &pending);
+        let first_id = ext_fs_id(&manifest, &first);
+        assert_ne!(first_id, ext_fs_id(&manifest, &second));
+
+        assert!(manifest.pending_replays(&files).unwrap().is_empty());
+        assert_eq!(ext_fs_id(&manifest, &first), first_id);
+        assert!(manifest.dropped_ext_fs_ids.is_empty());
+    }
+
+    #[test]
+    fn touched_replays_keep_their_id() {
+        let dir = TempDir::new().unwrap();
+        let replay = write_replay(&dir, "replay.SC2Replay", "replay");
+        let files = vec![replay.clone()];
+        let mut manifest = SnapshotManifest::default();
+        let pending = manifest.pending_replays(&files).unwrap();
+        record_all(&mut manifest, &pending);
+        let id = ext_fs_id(&manifest, &replay);
+
+        // Same contents with another modification time.
+        manifest
+            .files
+            .get_mut(&replay.to_string_lossy().to_string())
+            .unwrap()
+            .mtime = 0;
+        assert!(manifest.pending_replays(&files).unwrap().is_empty());
+        assert_eq!(ext_fs_id(&manifest, &replay), id);
+        assert_ne!(
+            manifest.files[&replay.to_string_lossy().to_string()].mtime,
+            0
+        );
+        assert!(manifest.dropped_ext_fs_ids.is_empty());
+    }
+
+    #[test]
+    fn changed_replays_drop_their_rows() {
+        let dir = TempDir::new().unwrap();
+        let replay = write_replay(&dir, "replay.SC2Replay", "replay");
+        let files = vec![replay.clone()];
+        let mut manifest = SnapshotManifest::default();
+        let pending = manifest.pending_replays(&files).unwrap();
+        record_all(&mut manifest, &pending);
+        let old_id = ext_fs_id(&manifest, &replay);
+
+        write_replay(&dir, "replay.SC2Replay", "changed replay");
+        let pending = manifest.pending_replays(&files).unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_ne!(pending[0].entry.ext_fs_id, old_id);
+        assert_eq!(manifest.dropped_ext_fs_ids, vec![old_id]);
+        record_all(&mut manifest, &pending);
+        assert_eq!(ext_fs_id(&manifest, &replay), pending[0].entry.ext_fs_id);
+    }
+
+    #[test]
+    fn deleted_replays_drop_their_rows() {
+        let dir = TempDir::new().unwrap();
+        let kept = write_replay(&dir, "kept.SC2Replay", "kept");
+        let deleted = write_replay(&dir, "deleted.SC2Replay", "deleted");
+        let mut manifest = SnapshotManifest::default();
+        let pending = manifest
+            .pending_replays(&[kept.clone(), deleted.clone()])
+            .unwrap();
+        record_all(&mut manifest, &pending);
+        let deleted_id = ext_fs_id(&manifest, &deleted);
+
+        std::fs::remove_file(&deleted).unwrap();
+        assert!(manifest
+            .pending_replays(std::slice::from_ref(&kept))
+            .unwrap()
+            .is_empty());
+        assert_eq!(manifest.dropped_ext_fs_ids, vec![deleted_id]);
+        assert!(!manifest
+            .files
+            .contains_key(&deleted.to_string_lossy().to_string()));
+    }
+
+    #[test]
+    fn forgotten_replays_are_pending_again() {
+        let dir = TempDir::new().unwrap();
+        let replay = write_replay(&dir, "replay.SC2Replay", "replay");
+        let files = vec![replay.clone()];
+        let mut manifest = SnapshotManifest::default();
+        let pending = manifest.pending_replays(&files).unwrap();
+        manifest.forget(&pending[0]);
+        assert!(manifest.files.is_empty());
+
+        let pending = manifest.pending_replays(&files).unwrap();
+        assert_eq!(pending.len(), 1);
+        manifest.record(&pending[0], true);
+        assert!(manifest.files[&replay.to_string_lossy().to_string()].failed);
+        // Failed replays are only retried once they change.
+        assert!(manifest.pending_replays(&files).unwrap().is_empty());
+    }
+
+    #[test]
+    fn dropped_rows_are_not_scanned() {
+        let dir = TempDir::new().unwrap();
+        let mut manifest = SnapshotManifest {
+            parts: 1,
+            dropped_ext_fs_ids: vec![1],
+            ..Default::default()
+        };
+        let mut base = df!("ext_fs_id" => [0u64, 1u64]).unwrap();
+        let mut part = df!("ext_fs_id" => [2u64]).unwrap();
+        let part_dir = SnapshotManifest::part_dir(dir.path(), 1);
+        std::fs::create_dir_all(&part_dir).unwrap();
+        let mut file = std::fs::File::create(dir.path().join(DETAILS_IPC)).unwrap();
+        IpcWriter::new(&mut file).finish(&mut base).unwrap();
+        let mut file = std::fs::File::create(part_dir.join(DETAILS_IPC)).unwrap();
+        IpcWriter::new(&mut file).finish(&mut part).unwrap();
+
+        let ids = |manifest: &SnapshotManifest| -> Vec<Option<u64>> {
+            manifest
+                .scan_ipc(dir.path(), DETAILS_IPC)
+                .unwrap()
+                .unwrap()
+                .collect()
+                .unwrap()
+                .column("ext_fs_id")
+                .unwrap()
+                .u64()
+                .unwrap()
+                .into_iter()
+                .collect()
+        };
+        assert_eq!(ids(&manifest), vec![Some(0), Some(2)]);
+        manifest.dropped_ext_fs_ids.clear();
+        assert_eq!(ids(&manifest), vec![Some(0), Some(1), Some(2)]);
+        assert!(manifest.scan_ipc(dir.path(), STATS_IPC).unwrap().is_none());
+    }
+}
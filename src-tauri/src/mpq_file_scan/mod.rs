@@ -1,31 +1,56 @@
 //! Swarmy Tauri UI - SC2Replay Directory Scan and Export to Arrow IPC Module
 
-use crate::settings::load_app_settings_from_store;
-use s2protocol::arrow_store::ArrowIpcTypes;
+use crate::build_labels::{load_build_label_rules, try_classify_build_orders};
+use crate::jobs::JobRunner;
+use crate::replay_watcher::refresh_replay_watcher;
+use crate::settings::{load_app_settings_from_store, update_active_library};
+use crate::snapshot_registry::SnapshotRegistry;
+use rayon::prelude::*;
+use s2protocol::basic_replay_data::SC2ReplayBasicData;
 use s2protocol::cli::WriteArrowIpcProps;
 use s2protocol::game_events::read_balance_data_from_json_dir;
-use s2protocol::SC2ReplaysDirStats;
-use std::path::PathBuf;
+use s2protocol::{get_matching_files, SC2ReplaysDirStats};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 use swarmy_tauri_common::*;
+use tauri::{Emitter, Manager};
 use tauri_plugin_store::StoreBuilder;
 
+pub mod manifest;
+use manifest::*;
+pub mod snapshot_writer;
+use snapshot_writer::*;
+pub mod snapshot_schema;
+use snapshot_schema::*;
+
 #[tauri::command]
-pub async fn get_current_app_config(app_handle: tauri::AppHandle) -> Result<AppSettings, String> {
-    let store = StoreBuilder::new(&app_handle, "settings.json")
+pub async fn get_current_app_config(app_handle: tauri::AppHandle) -> ApiResponse<AppSettings> {
+    let init_time = std::time::Instant::now();
+    let res = try_get_current_app_config(&app_handle).await;
+    if let Err(e) = &res {
+        log::error!("Error loading app settings: {}", e);
+    }
+    ApiResponse::from_result(res, init_time.elapsed().as_millis() as u64)
+}
+
+pub async fn try_get_current_app_config(
+    app_handle: &tauri::AppHandle,
+) -> Result<AppSettings, SwarmyTauriError> {
+    let store = StoreBuilder::new(app_handle, "settings.json")
         .build()
-        .map_err(|e| {
-            log::error!("Error building store: {}", e);
-            format!("Error building store: {:?}", e)
-        })?;
+        .map_err(|e| SwarmyTauriError::Other(format!("Error building store: {}", e)))?;
 
     // If there are no saved settings yet, this will return an error so we ignore the return value.
     let _ = store.reload();
 
-    let app_settings = load_app_settings_from_store(&store).await.map_err(|e| {
-        log::error!("Error loading app settings: {}", e);
-        format!("Error loading app settings: {:?}", e)
-    })?;
-    Ok(app_settings)
+    load_app_settings_from_store(
+        &store,
+        &app_handle.state::<JobRunner>(),
+        &app_handle.state::<SnapshotRegistry>(),
+    )
+    .await
 }
 
 #[tauri::command(rename_all = "snake_case")]
@@ -33,98 +58,450 @@ pub async fn basic_scan_replay_path(
     app_handle: tauri::AppHandle,
     replay_path: String,
     disable_parallel_scans: bool,
-) -> Result<SC2ReplaysDirStats, String> {
-    let store = StoreBuilder::new(&app_handle, "settings.json")
-        .build()
-        .map_err(|e| {
-            log::error!("Error building store: {}", e);
-            format!("Error building store: {:?}", e)
-        })?;
-
-    // If there are no saved settings yet, this will return an error so we ignore the return value.
-    let _ = store.reload();
-
-    store.set("disable_parallel_scans", disable_parallel_scans);
-    store.set("replay_path", replay_path.clone());
-    // create a thread to scan the directory in the background:
-    let t = std::thread::spawn(move || {
-        log::info!("Scanning replays directory: {}", replay_path);
-        match SC2ReplaysDirStats::from_directory(&replay_path, disable_parallel_scans) {
-            Ok(s) => {
-                log::info!(
-                    "Finished scanning replays directory: {} with res: {:?}",
-                    replay_path,
-                    s
-                );
-                Ok(s)
-            }
-            Err(e) => {
-                log::error!("Error scanning replays directory: {}", e);
-                Err(format!("Error scanning replays directory: {:?}", e))
+) -> ApiResponse<SC2ReplaysDirStats> {
+    match StoreBuilder::new(&app_handle, "settings.json").build() {
+        Ok(store) => {
+            // If there are no saved settings yet, this will return an error so we ignore the return value.
+            let _ = store.reload();
+            store.set("disable_parallel_scans", disable_parallel_scans);
+            update_active_library(&store, |library| library.replay_path = replay_path.clone());
+            if let Err(e) = refresh_replay_watcher(&app_handle, &store) {
+                log::error!("Error refreshing the replay watcher: {}", e);
             }
         }
-    });
-    t.join().unwrap()
+        Err(e) => log::error!("Error building store: {}", e),
+    }
+    let init_time = std::time::Instant::now();
+    log::info!("Scanning replays directory: {}", replay_path);
+    let replay_path_cp = replay_path.clone();
+    let res = app_handle
+        .state::<JobRunner>()
+        .run_background("basic_scan_replay_path", move || {
+            SC2ReplaysDirStats::from_directory(&replay_path_cp, disable_parallel_scans)
+                .map_err(SwarmyTauriError::from)
+        })
+        .await;
+    match &res {
+        Ok(s) => log::info!(
+            "Finished scanning replays directory: {} with res: {:?}",
+            replay_path,
+            s
+        ),
+        Err(e) => log::error!("Error scanning replays directory: {}", e),
+    }
+    ApiResponse::from_result(res, init_time.elapsed().as_millis() as u64)
+}
+
+/// Holds the cancellation flag of the manual optimize job.
+#[derive(Debug, Default)]
+pub struct OptimizeState {
+    /// Set from the time the manual job is requested, so that a cancel while it waits for the
+    /// replay watcher stops it and not the run of the watcher.
+    pub cancel: Mutex<Option<Arc<AtomicBool>>>,
+    /// Held while an optimize job runs, the ones of the replay watcher wait for the manual ones.
+    pub running: tokio::sync::Mutex<()>,
 }
 
 #[tauri::command(rename_all = "snake_case")]
 pub async fn optimize_replay_path(
-    _app_handle: tauri::AppHandle,
+    app_handle: tauri::AppHandle,
     replay_path: String,
     disable_parallel_scans: bool,
-) -> ApiResponse {
-    // create a thread to scan the directory in the background:
-    let t = std::thread::spawn(move || {
-        let init_time = std::time::Instant::now();
-        match try_optimize_replay_path(replay_path, disable_parallel_scans)
-        {
-            Ok(val) => ApiResponse::new(
-            ResponseMetaBuilder::new(true)
-                .duration_ms(init_time.elapsed().as_millis() as u64)
-                .build(),
-            val,
-        ),
-            Err(e) => {
-            log::error!("Error optimizing replays: {}", e);
-        ApiResponse::new(
-            ResponseMetaBuilder::new(true)
-                .duration_ms(init_time.elapsed().as_millis() as u64)
-                .build(),
-            format!("Error optimizing replays: {:?}", e))
-        }}
-    });
-    t.join().unwrap()
+    optimize_settings: OptimizeSettings,
+) -> ApiResponse<String> {
+    let mut build_rules_path = String::new();
+    match StoreBuilder::new(&app_handle, "settings.json").build() {
+        Ok(store) => {
+            // If there are no saved settings yet, this will return an error so we ignore the return value.
+            let _ = store.reload();
+            update_active_library(&store, |library| {
+                library.optimize_settings = optimize_settings.clone()
+            });
+            build_rules_path = store
+                .get("build_rules_path")
+                .and_then(|v| v.as_str().map(|s| s.to_string()))
+                .unwrap_or_default();
+        }
+        Err(e) => log::error!("Error building store: {}", e),
+    }
+    let init_time = std::time::Instant::now();
+    let cancel = Arc::new(AtomicBool::new(false));
+    set_optimize_cancel(&app_handle, Some(cancel.clone()));
+    let res = run_optimize_job(
+        &app_handle,
+        "optimize_replay_path",
+        replay_path,
+        disable_parallel_scans,
+        optimize_settings,
+        build_rules_path,
+        cancel.clone(),
+    )
+    .await;
+    // A newer manual job may have replaced the flag in the meantime.
+    {
+        let optimize_state = app_handle.state::<OptimizeState>();
+        let mut current = optimize_state
+            .cancel
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        if current.as_ref().is_some_and(|c| Arc::ptr_eq(c, &cancel)) {
+            *current = None;
+        }
+    }
+    if let Err(e) = &res {
+        log::error!("Error optimizing replays: {}", e);
+    }
+    ApiResponse::from_result(res, init_time.elapsed().as_millis() as u64)
 }
 
-fn try_optimize_replay_path(
+/// Optimizes `replay_path` on the job runner, reporting the progress with
+/// [`OPTIMIZE_PROGRESS_EVENT`], and classifies the build orders of the new snapshot.
+/// Only one optimize job runs at a time, each one is stopped by its own `cancel` flag.
+pub async fn run_optimize_job(
+    app_handle: &tauri::AppHandle,
+    job_name: &str,
     replay_path: String,
     disable_parallel_scans: bool,
+    optimize_settings: OptimizeSettings,
+    build_rules_path: String,
+    cancel: Arc<AtomicBool>,
 ) -> Result<String, SwarmyTauriError> {
+    let optimize_state = app_handle.state::<OptimizeState>();
+    let _running = optimize_state.running.lock().await;
+    let job_app_handle = app_handle.clone();
+    let snapshot_path = replay_path.clone();
+    let ipc_dir = app_handle.state::<SnapshotRegistry>().ipc_dir(&replay_path);
+    let res = app_handle
+        .state::<JobRunner>()
+        .run_background(job_name, move || {
+            let on_progress = |progress: &OptimizeProgress| {
+                if let Err(e) = job_app_handle.emit(OPTIMIZE_PROGRESS_EVENT, progress) {
+                    log::error!("Error emitting optimize progress: {}", e);
+                }
+            };
+            let res = try_optimize_replay_path(
+                replay_path,
+                &ipc_dir,
+                &optimize_settings,
+                disable_parallel_scans,
+                &cancel,
+                on_progress,
+            );
+            // The build labels are not part of the optimized snapshot, they are recomputed
+            // from the new IPC files.
+            if res.is_ok() {
+                if let Err(e) = load_build_label_rules(&build_rules_path)
+                    .and_then(|rules| try_classify_build_orders(&ipc_dir, &rules))
+                {
+                    log::error!("Error classifying build orders: {}", e);
+                }
+            }
+            res
+        })
+        .await;
+    // The files were rewritten even if the optimize failed half way.
+    app_handle
+        .state::<SnapshotRegistry>()
+        .invalidate(&snapshot_path);
+    res
+}
+
+fn set_optimize_cancel(app_handle: &tauri::AppHandle, cancel: Option<Arc<AtomicBool>>) {
+    let optimize_state = app_handle.state::<OptimizeState>();
+    *optimize_state
+        .cancel
+        .lock()
+        .unwrap_or_else(|e| e.into_inner()) = cancel;
+}
+
+/// Requests the manual optimize job to stop, the current snapshot is kept as is.
+/// The runs of the replay watcher are not affected.
+#[tauri::command]
+pub fn cancel_optimize(state: tauri::State<OptimizeState>) -> ApiResponse<()> {
+    log::info!("Cancelling optimize job");
+    if let Some(cancel) = state
+        .cancel
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_ref()
+    {
+        cancel.store(true, Ordering::SeqCst);
+    }
+    ApiResponse::ok(ResponseMetaBuilder::new(true).build(), ())
+}
+
+/// The number of parsed files between progress updates.
+const PARSE_PROGRESS_INTERVAL: usize = 25;
+
+/// Parses the replays in `replay_path` and writes the Arrow IPC snapshot to `ipc_dir`, see
+/// [`crate::snapshot_registry::snapshot_ipc_dir`].
+/// The files are written to a staging directory first so that a failed or cancelled run leaves the
+/// previous snapshot untouched.
+/// When the settings are incremental and a snapshot exists, only the replays that are new or
+/// changed since the previous run are parsed and their rows are added to the snapshot as a part.
+pub fn try_optimize_replay_path<F>(
+    replay_path: String,
+    ipc_dir: &Path,
+    settings: &OptimizeSettings,
+    disable_parallel_scans: bool,
+    cancel: &AtomicBool,
+    on_progress: F,
+) -> Result<String, SwarmyTauriError>
+where
+    F: Fn(&OptimizeProgress) + Sync,
+{
     let path = PathBuf::from(&replay_path);
-    let destination = path.join("ipcs");
-    if !destination.exists() {
-        std::fs::create_dir_all(&destination)?;
+    let destination = ipc_dir.to_path_buf();
+    let staging = suffixed_dir(&destination, ".tmp");
+    if staging.exists() {
+        std::fs::remove_dir_all(&staging)?;
     }
+    std::fs::create_dir_all(&staging)?;
     log::info!(
-        "Optimizing replays directory: {} and storing into {}",
+        "Optimizing replays directory: {} and storing into {} (incremental: {})",
         path.display(),
-        destination.display()
+        destination.display(),
+        settings.incremental
     );
-    let versioned_abilities = read_balance_data_from_json_dir(&path)?;
-    // TODO: Move from cli on s2protocol and create a leptos view to configure this.
+    let res = write_snapshot(
+        &path,
+        &staging,
+        &destination,
+        settings,
+        disable_parallel_scans,
+        cancel,
+        &on_progress,
+    );
+    match res {
+        Ok(Some(progress)) => {
+            swap_snapshot_dir(&staging, &destination)?;
+            on_progress(&OptimizeProgress {
+                stage: OptimizeStage::Finished,
+                ..progress
+            });
+            Ok(String::from("Optimization completed successfully."))
+        }
+        Ok(None) => {
+            std::fs::remove_dir_all(&staging)?;
+            on_progress(&OptimizeProgress {
+                stage: OptimizeStage::Finished,
+                ..Default::default()
+            });
+            Ok(String::from("Snapshot is up to date."))
+        }
+        Err(e) => {
+            std::fs::remove_dir_all(&staging)?;
+            if matches!(e, SwarmyTauriError::Cancelled) {
+                on_progress(&OptimizeProgress {
+                    stage: OptimizeStage::Cancelled,
+                    ..Default::default()
+                });
+            }
+            Err(e)
+        }
+    }
+}
+
+/// Discovers, parses and writes every IPC file of the snapshot into `output`.
+/// In incremental mode, if the `previous` snapshot has a manifest, only the pending replays are
+/// parsed, their rows are written to a new part next to the IPC files of the `previous` snapshot.
+/// Returns None if there was nothing to process.
+fn write_snapshot<F>(
+    source: &Path,
+    output: &Path,
+    previous: &Path,
+    settings: &OptimizeSettings,
+    disable_parallel_scans: bool,
+    cancel: &AtomicBool,
+    on_progress: &F,
+) -> Result<Option<OptimizeProgress>, SwarmyTauriError>
+where
+    F: Fn(&OptimizeProgress) + Sync,
+{
+    let versioned_abilities = read_balance_data_from_json_dir(source)?;
     let props = WriteArrowIpcProps {
-        scan_max_files: 10000,
-        process_max_files: 10000,
-        traverse_max_depth: 8,
-        min_version: None,
-        max_version: None,
+        scan_max_files: settings.scan_max_files,
+        process_max_files: settings.process_max_files,
+        traverse_max_depth: settings.traverse_max_depth,
+        min_version: settings.min_version,
+        max_version: settings.max_version,
     };
-    ArrowIpcTypes::handle_arrow_ipc_cmd(
-        path,
-        destination,
-        &props,
-        &versioned_abilities,
-        disable_parallel_scans,
+    // Snapshots written by other versions have other columns, they are rewritten in full.
+    let previous_manifest = if settings.incremental && is_current_snapshot(previous) {
+        SnapshotManifest::load(previous)?
+    } else {
+        None
+    };
+    let mut progress = OptimizeProgress {
+        ipcs_total: SNAPSHOT_IPCS.len(),
+        ..Default::default()
+    };
+    on_progress(&progress);
+    let files = get_matching_files(
+        source.to_path_buf(),
+        props.scan_max_files,
+        props.traverse_max_depth,
     )?;
-    Ok(String::from("Optimization completed successfully."))
+    let is_incremental = previous_manifest.is_some();
+    let mut manifest = previous_manifest.unwrap_or_default();
+    let previously_dropped = manifest.dropped_ext_fs_ids.len();
+    let pending = manifest.pending_replays(&files)?;
+    // The rows of the replays that were deleted or changed since the previous run.
+    let has_dropped_rows = manifest.dropped_ext_fs_ids.len() > previously_dropped;
+    log::info!(
+        "Found {} replays, {} of them are new or changed",
+        files.len(),
+        pending.len()
+    );
+    if is_incremental && pending.is_empty() && !has_dropped_rows {
+        return Ok(None);
+    }
+    progress.stage = OptimizeStage::Parsing;
+    progress.files_discovered = pending.len();
+    on_progress(&progress);
+
+    let files_parsed = AtomicUsize::new(0);
+    let files_failed = AtomicUsize::new(0);
+    let failed_ids = Mutex::new(HashSet::new());
+    let parse_file = |replay: &PendingReplay| {
+        if cancel.load(Ordering::SeqCst) {
+            return None;
+        }
+        let res = match SC2ReplayBasicData::new(&replay.path, replay.entry.ext_fs_id) {
+            Ok(data) => {
+                files_parsed.fetch_add(1, Ordering::SeqCst);
+                Some(data)
+            }
+            Err(e) => {
+                log::warn!("Error parsing {}: {}", replay.path.display(), e);
+                files_failed.fetch_add(1, Ordering::SeqCst);
+                if let Ok(mut failed_ids) = failed_ids.lock() {
+                    failed_ids.insert(replay.entry.ext_fs_id);
+                }
+                None
+            }
+        };
+        let parsed = files_parsed.load(Ordering::SeqCst);
+        let failed = files_failed.load(Ordering::SeqCst);
+        if (parsed + failed) % PARSE_PROGRESS_INTERVAL == 0 {
+            on_progress(&OptimizeProgress {
+                files_parsed: parsed,
+                files_failed: failed,
+                ..progress.clone()
+            });
+        }
+        res
+    };
+    let sources: Vec<SC2ReplayBasicData> = if disable_parallel_scans {
+        pending.iter().filter_map(parse_file).collect()
+    } else {
+        pending.par_iter().filter_map(parse_file).collect()
+    };
+    if cancel.load(Ordering::SeqCst) {
+        return Err(SwarmyTauriError::Cancelled);
+    }
+    let sources: Vec<SC2ReplayBasicData> = sources
+        .into_iter()
+        .filter(|source| {
+            props
+                .min_version
+                .is_none_or(|min_version| source.init_data.version >= min_version)
+                && props
+                    .max_version
+                    .is_none_or(|max_version| source.init_data.version <= max_version)
+        })
+        .take(props.process_max_files)
+        .collect();
+    progress.files_parsed = files_parsed.load(Ordering::SeqCst);
+    progress.files_failed = files_failed.load(Ordering::SeqCst);
+    if sources.is_empty() && !is_incremental {
+        return Err(SwarmyTauriError::Other(String::from(
+            "No replays with valid init data found",
+        )));
+    }
+    let processed_ids: HashSet<u64> = sources
+        .iter()
+        .map(|source| source.init_data.ext_fs_id)
+        .collect();
+    let failed_ids = failed_ids
+        .into_inner()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    // Only parse failures are recorded as failed, the replays left out by the limits are not
+    // recorded so that a run with other limits picks them up.
+    for replay in &pending {
+        if processed_ids.contains(&replay.entry.ext_fs_id) {
+            manifest.record(replay, false);
+        } else if failed_ids.contains(&replay.entry.ext_fs_id) {
+            manifest.record(replay, true);
+        } else {
+            manifest.forget(replay);
+        }
+    }
+    // The replays left out by the limits stay pending, there is nothing to write if they are
+    // the only ones.
+    if is_incremental && sources.is_empty() && failed_ids.is_empty() && !has_dropped_rows {
+        return Ok(None);
+    }
+
+    progress.stage = OptimizeStage::Writing;
+    // An incremental run keeps the previous IPC files and their parts as they are and writes the
+    // rows of its replays to a new part, the rows of the deleted or changed replays are left out
+    // when scanning. Past SNAPSHOT_MAX_PARTS the parts are merged into the IPC files instead.
+    let merge_parts = is_incremental && manifest.parts >= SNAPSHOT_MAX_PARTS;
+    let new_rows_dir = if !is_incremental {
+        output.to_path_buf()
+    } else if merge_parts {
+        output.join("new")
+    } else {
+        for part in 1..=manifest.parts {
+            std::fs::create_dir_all(SnapshotManifest::part_dir(output, part))?;
+        }
+        SnapshotManifest::part_dir(output, manifest.parts + 1)
+    };
+    std::fs::create_dir_all(&new_rows_dir)?;
+    for (file_name, ipc_type) in SNAPSHOT_IPCS.iter() {
+        if cancel.load(Ordering::SeqCst) {
+            return Err(SwarmyTauriError::Cancelled);
+        }
+        progress.current_ipc = file_name.to_string();
+        on_progress(&progress);
+        if !sources.is_empty() {
+            write_snapshot_ipc(
+                ipc_type,
+                &sources,
+                new_rows_dir.join(file_name),
+                &versioned_abilities,
+                disable_parallel_scans,
+            )?;
+        }
+        if merge_parts {
+            merge_snapshot_ipc(
+                manifest.scan_ipc(previous, file_name)?,
+                &new_rows_dir.join(file_name),
+                &output.join(file_name),
+            )?;
+        } else if is_incremental {
+            link_snapshot_ipc(&previous.join(file_name), &output.join(file_name))?;
+            for part in 1..=manifest.parts {
+                link_snapshot_ipc(
+                    &SnapshotManifest::part_dir(previous, part).join(file_name),
+                    &SnapshotManifest::part_dir(output, part).join(file_name),
+                )?;
+            }
+        }
+        progress.ipcs_written += 1;
+    }
+    if merge_parts {
+        std::fs::remove_dir_all(&new_rows_dir)?;
+        manifest.parts = 0;
+        manifest.dropped_ext_fs_ids.clear();
+    } else if is_incremental && !sources.is_empty() {
+        manifest.parts += 1;
+    } else if is_incremental {
+        std::fs::remove_dir_all(&new_rows_dir)?;
+    }
+    manifest.save(output)?;
+    SnapshotSchema::from_ipc_dir(output)?.save(output)?;
+    progress.current_ipc = String::new();
+    Ok(Some(progress))
 }
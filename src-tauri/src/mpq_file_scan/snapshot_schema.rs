@@ -0,0 +1,361 @@
+//! Stamps a snapshot with the versions and the schemas it was written with, so that snapshots
+//! written by older versions are reported as outdated instead of failing at query time.
+
+use polars::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use swarmy_tauri_common::*;
+
+use super::manifest::SnapshotManifest;
+use super::snapshot_writer::SNAPSHOT_IPCS;
+
+/// The file stored next to the IPC files with the versions and schemas of the snapshot.
+pub const SNAPSHOT_SCHEMA_FILE: &str = "snapshot.json";
+
+/// The version of s2protocol the snapshots are written with, read from the lock file.
+pub const S2PROTOCOL_VERSION: &str = env!("S2PROTOCOL_VERSION");
+
+/// Bumped when the snapshot writer changes the files or columns it writes.
+pub const SNAPSHOT_FORMAT_VERSION: u32 = 1;
+
+/// The columns the queries rely on, by IPC file.
+const REQUIRED_COLUMNS: [(&str, &[&str]); 3] = [
+    (
+        DETAILS_IPC,
+        &[
+            "ext_fs_id",
+            "ext_datetime",
+            "title",
+            "cache_handles",
+            "player_name",
+            "player_team_id",
+            "player_race",
+            "player_result",
+            "player_toon_region",
+            "player_toon_realm",
+            "player_toon_id",
+        ],
+    ),
+    (INIT_DATA_IPC, &["ext_fs_id", "version"]),
+    (STATS_IPC, &["ext_fs_id", "player_id", "ext_replay_seconds"]),
+];
+
+/// A column of an IPC file and its Polars data type.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SnapshotColumn {
+    pub name: String,
+    pub dtype: String,
+}
+
+/// An IPC file of the snapshot as it was written.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SnapshotIpcSchema {
+    pub file_name: String,
+    pub rows: u64,
+    pub columns: Vec<SnapshotColumn>,
+}
+
+/// The contents of `snapshot.json`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SnapshotSchema {
+    pub format_version: u32,
+    pub s2protocol_version: String,
+    pub app_version: String,
+    pub files: Vec<SnapshotIpcSchema>,
+}
+
+impl SnapshotSchema {
+    /// Reads the row counts and schemas of the IPC files written by optimize in `ipc_dir`, along
+    /// with their parts.
+    pub fn from_ipc_dir(ipc_dir: &Path) -> Result<Self, SwarmyTauriError> {
+        let manifest = SnapshotManifest::load(ipc_dir)?.unwrap_or_default();
+        let mut files = vec![];
+        for (file_name, _) in SNAPSHOT_IPCS.iter() {
+            let Some(mut lf) = manifest.scan_ipc(ipc_dir, file_name)? else {
+                continue;
+            };
+            let columns = schema_columns(&lf.collect_schema()?);
+            let rows = lf
+                .select([len().cast(DataType::UInt64).alias("rows")])
+                .collect()?
+                .column("rows")?
+                .u64()?
+                .get(0);
+            files.push(SnapshotIpcSchema {
+                file_name: file_name.to_string(),
+                rows: rows.unwrap_or_default(),
+                columns,
+            });
+        }
+        Ok(Self {
+            format_version: SNAPSHOT_FORMAT_VERSION,
+            s2protocol_version: S2PROTOCOL_VERSION.to_string(),
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            files,
+        })
+    }
+
+    /// Loads the schema from the snapshot directory, if there is one.
+    pub fn load(ipc_dir: &Path) -> Result<Option<Self>, SwarmyTauriError> {
+        let schema_path = ipc_dir.join(SNAPSHOT_SCHEMA_FILE);
+        if !schema_path.exists() {
+            return Ok(None);
+        }
+        let contents = std::fs::read_to_string(schema_path)?;
+        Ok(Some(serde_json::from_str(&contents)?))
+    }
+
+    /// Writes the schema to the snapshot directory.
+    pub fn save(&self, ipc_dir: &Path) -> Result<(), SwarmyTauriError> {
+        let contents = serde_json::to_string_pretty(self)?;
+        std::fs::write(ipc_dir.join(SNAPSHOT_SCHEMA_FILE), contents)?;
+        Ok(())
+    }
+
+    /// Whether the snapshot was written by the current snapshot writer and s2protocol.
+    fn check_versions(&self) -> Result<(), SwarmyTauriError> {
+        if self.format_version != SNAPSHOT_FORMAT_VERSION {
+            return Err(SwarmyTauriError::SnapshotOutdated(format!(
+                "written with snapshot format {}, expected {}",
+                self.format_version, SNAPSHOT_FORMAT_VERSION
+            )));
+        }
+        if self.s2protocol_version != S2PROTOCOL_VERSION {
+            return Err(SwarmyTauriError::SnapshotOutdated(format!(
+                "written with s2protocol {}, expected {}",
+                self.s2protocol_version, S2PROTOCOL_VERSION
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Checks that the snapshot in `ipc_dir` has a current `snapshot.json` and that the `files` on
+/// disk, by file name, have the schemas it recorded and the columns the queries rely on.
+pub fn validate_snapshot_schema<'a>(
+    ipc_dir: &Path,
+    files: impl IntoIterator<Item = (&'a str, &'a Schema)>,
+) -> Result<(), SwarmyTauriError> {
+    let Some(expected) = SnapshotSchema::load(ipc_dir)? else {
+        return Err(SwarmyTauriError::SnapshotOutdated(format!(
+            "{} has no {}",
+            ipc_dir.display(),
+            SNAPSHOT_SCHEMA_FILE
+        )));
+    };
+    expected.check_versions()?;
+    let files: Vec<(&str, &Schema)> = files.into_iter().collect();
+    for ipc in &expected.files {
+        let Some((_, schema)) = files.iter().find(|(name, _)| *name == ipc.file_name) else {
+            return Err(SwarmyTauriError::SnapshotOutdated(format!(
+                "{} is missing",
+                ipc.file_name
+            )));
+        };
+        if schema_columns(schema) != ipc.columns {
+            return Err(SwarmyTauriError::SnapshotOutdated(format!(
+                "the columns of {} changed since it was written",
+                ipc.file_name
+            )));
+        }
+    }
+    for (file_name, columns) in REQUIRED_COLUMNS.iter() {
+        let Some((_, schema)) = files.iter().find(|(name, _)| name == file_name) else {
+            return Err(SwarmyTauriError::SnapshotOutdated(format!(
+                "{} is missing",
+                file_name
+            )));
+        };
+        if let Some(column) = columns.iter().find(|column| !schema.contains(column)) {
+            return Err(SwarmyTauriError::SnapshotOutdated(format!(
+                "{} has no column {}",
+                file_name, column
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Whether the snapshot in `ipc_dir` was written by the current versions, incremental optimize
+/// runs only extend such snapshots.
+pub fn is_current_snapshot(ipc_dir: &Path) -> bool {
+    matches!(
+        SnapshotSchema::load(ipc_dir).map(|schema| schema.map(|schema| schema.check_versions())),
+        Ok(Some(Ok(())))
+    )
+}
+
+fn schema_columns(schema: &Schema) -> Vec<SnapshotColumn> {
+    schema
+        .iter()
+        .map(|(name, dtype)| SnapshotColumn {
+            name: name.to_string(),
+            dtype: dtype.to_string(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mpq_file_scan::try_optimize_replay_path;
+    use crate::snapshot_registry::SnapshotRegistry;
+    use std::sync::atomic::AtomicBool;
+    use tempfile::TempDir;
+
+    /// The schemas of files with the required columns only.
+    fn required_schemas() -> Vec<(&'static str, Schema)> {
+        REQUIRED_COLUMNS
+            .iter()
+            .map(|(file_name, columns)| {
+                let schema = Schema::from_iter(
+                    columns
+                        .iter()
+                        .map(|column| Field::new((*column).into(), DataType::String)),
+                );
+                (*file_name, schema)
+            })
+            .collect()
+    }
+
+    /// Saves the `snapshot.json` of the `schemas` in a new directory.
+    fn saved_schema(schemas: &[(&str, Schema)]) -> (TempDir, SnapshotSchema) {
+        let ipc_dir = TempDir::new().unwrap();
+        let snapshot_schema = SnapshotSchema {
+            format_version: SNAPSHOT_FORMAT_VERSION,
+            s2protocol_version: S2PROTOCOL_VERSION.to_string(),
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            files: schemas
+                .iter()
+                .map(|(file_name, schema)| SnapshotIpcSchema {
+                    file_name: file_name.to_string(),
+                    rows: 0,
+                    columns: schema_columns(schema),
+                })
+                .collect(),
+        };
+        snapshot_schema.save(ipc_dir.path()).unwrap();
+        (ipc_dir, snapshot_schema)
+    }
+
+    fn validate(ipc_dir: &TempDir, schemas: &[(&str, Schema)]) -> Result<(), SwarmyTauriError> {
+        validate_snapshot_schema(
+            ipc_dir.path(),
+            schemas
+                .iter()
+                .map(|(file_name, schema)| (*file_name, schema)),
+        )
+    }
+
+    #[test]
+    fn optimized_snapshot_opens() {
+        let replay_path = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests")
+            .join("fixtures")
+            .to_string_lossy()
+            .to_string();
+        let snapshot_root = TempDir::new().unwrap();
+        let registry = SnapshotRegistry::new(snapshot_root.path().to_path_buf());
+        try_optimize_replay_path(
+            replay_path.clone(),
+            &registry.ipc_dir(&replay_path),
+            &OptimizeSettings::default(),
+            true,
+            &AtomicBool::new(false),
+            |_| {},
+        )
+        .unwrap();
+        if let Err(e) = registry.open_library(&replay_path) {
+            panic!("The optimized snapshot did not open: {}", e);
+        }
+    }
+
+    #[test]
+    fn current_schema_is_valid() {
+        let schemas = required_schemas();
+        let (ipc_dir, _) = saved_schema(&schemas);
+        assert!(validate(&ipc_dir, &schemas).is_ok());
+        assert!(is_current_snapshot(ipc_dir.path()));
+    }
+
+    #[test]
+    fn missing_schema_file_is_outdated() {
+        let ipc_dir = TempDir::new().unwrap();
+        assert!(matches!(
+            validate(&ipc_dir, &required_schemas()),
+            Err(SwarmyTauriError::SnapshotOutdated(_))
+        ));
+        assert!(!is_current_snapshot(ipc_dir.path()));
+    }
+
+    #[test]
+    fn other_versions_are_outdated() {
+        let schemas = required_schemas();
+        let (ipc_dir, snapshot_schema) = saved_schema(&schemas);
+        SnapshotSchema {
+            format_version: SNAPSHOT_FORMAT_VERSION + 1,
+            ..snapshot_schema.clone()
+        }
+        .save(ipc_dir.path())
+        .unwrap();
+        assert!(matches!(
+            validate(&ipc_dir, &schemas),
+            Err(SwarmyTauriError::SnapshotOutdated(_))
+        ));
+        assert!(!is_current_snapshot(ipc_dir.path()));
+
+        SnapshotSchema {
+            s2protocol_version: String::from("0.0.0"),
+            ..snapshot_schema
+        }
+        .save(ipc_dir.path())
+        .unwrap();
+        assert!(matches!(
+            validate(&ipc_dir, &schemas),
+            Err(SwarmyTauriError::SnapshotOutdated(_))
+        ));
+    }
+
+    #[test]
+    fn changed_columns_are_outdated() {
+        let mut schemas = required_schemas();
+        let (ipc_dir, _) = saved_schema(&schemas);
+        schemas[0].1.with_column("extra".into(), DataType::UInt64);
+        assert!(matches!(
+            validate(&ipc_dir, &schemas),
+            Err(SwarmyTauriError::SnapshotOutdated(_))
+        ));
+
+        let mut schemas = required_schemas();
+        schemas[0]
+            .1
+            .set_dtype("player_race", DataType::UInt8)
+            .unwrap();
+        assert!(matches!(
+            validate(&ipc_dir, &schemas),
+            Err(SwarmyTauriError::SnapshotOutdated(_))
+        ));
+    }
+
+    #[test]
+    fn missing_files_are_outdated() {
+        let schemas = required_schemas();
+        let (ipc_dir, _) = saved_schema(&schemas);
+        assert!(matches!(
+            validate(&ipc_dir, &schemas[1..]),
+            Err(SwarmyTauriError::SnapshotOutdated(_))
+        ));
+    }
+
+    #[test]
+    fn missing_required_column_is_outdated() {
+        let mut schemas = required_schemas();
+        schemas[0].1.remove("player_race").unwrap();
+        // Recorded as written, the schema on disk matches it but lacks a required column.
+        let (ipc_dir, _) = saved_schema(&schemas);
+        assert!(matches!(
+            validate(&ipc_dir, &schemas),
+            Err(SwarmyTauriError::SnapshotOutdated(_))
+        ));
+    }
+}
@@ -0,0 +1,153 @@
+//! Writes the Arrow IPC files that make up a snapshot.
+
+use polars::prelude::*;
+use s2protocol::arrow_store::ArrowIpcTypes;
+use s2protocol::basic_replay_data::SC2ReplayBasicData;
+use s2protocol::game_events::MultiVersionedBalanceUnits;
+use s2protocol::UserInitDataFlatRow;
+use std::path::{Path, PathBuf};
+use swarmy_tauri_common::*;
+
+/// The number of parts an incremental run may add to a snapshot, the run that would exceed it
+/// merges the parts back into the IPC files of the snapshot instead.
+pub const SNAPSHOT_MAX_PARTS: u32 = 16;
+
+/// The IPC files of a snapshot and the s2protocol type that generates them, in write order.
+pub const SNAPSHOT_IPCS: [(&str, ArrowIpcTypes); 8] = [
+    (DETAILS_IPC, ArrowIpcTypes::Details),
+    (INIT_DATA_IPC, ArrowIpcTypes::UserInitData),
+    (STATS_IPC, ArrowIpcTypes::Stats),
+    (UPGRADES_IPC, ArrowIpcTypes::Upgrades),
+    (UNIT_BORN_IPC, ArrowIpcTypes::UnitBorn),
+    (UNIT_DIED_IPC, ArrowIpcTypes::UnitDied),
+    (CMD_TARGET_POINT_IPC, ArrowIpcTypes::CmdTargetPoint),
+    (CMD_TARGET_UNIT_IPC, ArrowIpcTypes::CmdTargetUnit),
+];
+
+/// Writes a single IPC file of the snapshot for the parsed sources.
+pub fn write_snapshot_ipc(
+    ipc_type: &ArrowIpcTypes,
+    sources: &[SC2ReplayBasicData],
+    output: PathBuf,
+    versioned_abilities: &MultiVersionedBalanceUnits,
+    disable_parallel_scans: bool,
+) -> Result<(), SwarmyTauriError> {
+    match ipc_type {
+        ArrowIpcTypes::Details => ipc_type.handle_read_once_write_all(sources, output)?,
+        ArrowIpcTypes::UserInitData => write_init_data_ipc(sources, output)?,
+        ArrowIpcTypes::Stats
+        | ArrowIpcTypes::Upgrades
+        | ArrowIpcTypes::UnitBorn
+        | ArrowIpcTypes::UnitDied => ipc_type.handle_tracker_events(
+            sources,
+            output,
+            versioned_abilities,
+            disable_parallel_scans,
+        )?,
+        ArrowIpcTypes::CmdTargetPoint | ArrowIpcTypes::CmdTargetUnit => ipc_type
+            .handle_game_events(sources, output, versioned_abilities, disable_parallel_scans)?,
+        _ => {
+            return Err(SwarmyTauriError::Other(format!(
+                "Unsupported snapshot IPC type: {:?}",
+                ipc_type
+            )))
+        }
+    }
+    Ok(())
+}
+
+/// Writes the user init data of the sources, one row per user in the lobby, along with the
+/// protocol version of the replay.
+fn write_init_data_ipc(
+    sources: &[SC2ReplayBasicData],
+    output: PathBuf,
+) -> Result<(), SwarmyTauriError> {
+    let (rows, versions): (Vec<UserInitDataFlatRow>, Vec<u32>) = sources
+        .iter()
+        .flat_map(|source| {
+            Vec::<UserInitDataFlatRow>::from(&source.init_data)
+                .into_iter()
+                .map(|row| (row, source.init_data.version))
+        })
+        .unzip();
+    let mut df = df!(
+        "ext_fs_id" => rows.iter().map(|r| r.ext_fs_id).collect::<Vec<u64>>(),
+        "version" => versions,
+        "ext_fs_sha256" => rows.iter().map(|r| r.ext_fs_sha256.clone()).collect::<Vec<String>>(),
+        "ext_fs_file_name" => rows.iter().map(|r| r.ext_fs_file_name.clone()).collect::<Vec<String>>(),
+        "user_index" => rows.iter().map(|r| r.user_index).collect::<Vec<u32>>(),
+        "name" => rows.iter().map(|r| r.name.clone()).collect::<Vec<String>>(),
+        "clan_tag" => rows.iter().map(|r| r.clan_tag.clone()).collect::<Vec<String>>(),
+        "scaled_rating" => rows.iter().map(|r| r.scaled_rating).collect::<Vec<Option<i32>>>(),
+    )?;
+    let mut file = std::fs::File::create(output)?;
+    IpcWriter::new(&mut file).finish(&mut df)?;
+    Ok(())
+}
+
+/// Replaces the snapshot in `destination` with the one written to `staging`.
+/// The previous snapshot is only removed once the new one is in place.
+pub fn swap_snapshot_dir(staging: &Path, destination: &Path) -> Result<(), SwarmyTauriError> {
+    let previous = suffixed_dir(destination, ".old");
+    if previous.exists() {
+        std::fs::remove_dir_all(&previous)?;
+    }
+    if destination.exists() {
+        std::fs::rename(destination, &previous)?;
+    }
+    std::fs::rename(staging, destination)?;
+    if previous.exists() {
+        std::fs::remove_dir_all(&previous)?;
+    }
+    Ok(())
+}
+
+/// `dir` with `suffix` appended to its name, i.e. `replays-1a2b.tmp` for `replays-1a2b`.
+pub fn suffixed_dir(dir: &Path, suffix: &str) -> PathBuf {
+    let mut name = dir.as_os_str().to_os_string();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// Links `previous` to `output`, an untouched IPC file is kept instead of rewritten.
+/// The previous snapshot directory is removed once the new one is in place, the link keeps the
+/// file contents alive.
+pub fn link_snapshot_ipc(previous: &Path, output: &Path) -> Result<(), SwarmyTauriError> {
+    if !previous.exists() {
+        return Ok(());
+    }
+    if std::fs::hard_link(previous, output).is_err() {
+        std::fs::copy(previous, output)?;
+    }
+    Ok(())
+}
+
+/// Writes to `output` the `previous` rows followed by the rows of the `new` IPC file.
+/// The rows are streamed in batches.
+pub fn merge_snapshot_ipc(
+    previous: Option<LazyFrame>,
+    new: &Path,
+    output: &Path,
+) -> Result<(), SwarmyTauriError> {
+    let mut frames: Vec<LazyFrame> = previous.into_iter().collect();
+    if new.exists() {
+        frames.push(LazyFrame::scan_ipc(
+            PlPath::new(&new.to_string_lossy()),
+            Default::default(),
+            Default::default(),
+        )?);
+    }
+    if frames.is_empty() {
+        return Ok(());
+    }
+    // Collecting a sink runs the streaming engine, which writes the rows in batches.
+    concat(frames, UnionArgs::default())?
+        .sink_ipc(
+            SinkTarget::Path(PlPath::new(&output.to_string_lossy())),
+            IpcWriterOptions::default(),
+            None,
+            SinkOptions::default(),
+        )?
+        .collect()?;
+    Ok(())
+}
@@ -0,0 +1,253 @@
+//! Per-player aggregates of the details IPC file.
+
+use crate::build_labels::join_build_labels;
+use crate::jobs::JobRunner;
+use crate::snapshot_registry::SnapshotRegistry;
+use polars::prelude::*;
+use swarmy_tauri_common::*;
+use tauri::Manager;
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn query_player_profile(
+    app_handle: tauri::AppHandle,
+    query: PlayerProfileQuery,
+) -> ApiResponse<PlayerProfile> {
+    let init_time = std::time::Instant::now();
+    let snapshots = app_handle.state::<SnapshotRegistry>().inner().clone();
+    let res = app_handle
+        .state::<JobRunner>()
+        .run("query_player_profile", move || {
+            try_query_player_profile(&snapshots, query)
+        })
+        .await;
+    if let Err(e) = &res {
+        log::error!("Error querying player profile: {}", e);
+    }
+    ApiResponse::from_result(res, init_time.elapsed().as_millis() as u64)
+}
+
+/// Returns the filter that keeps the details rows of a player given its toon handle or its name.
+pub fn player_filter(player: &str) -> Expr {
+    let player = player.trim();
+    if let Some((region, realm, id)) = parse_toon_handle(player) {
+        return col("player_toon_region")
+            .eq(lit(region as u32))
+            .and(col("player_toon_realm").eq(lit(realm)))
+            .and(col("player_toon_id").eq(lit(id)));
+    }
+    player_name_filter(player)
+}
+
+/// Returns the filter that keeps the rows of a player given its name.
+/// A name without clan tag matches the name under any clan tag.
+pub fn player_name_filter(name: &str) -> Expr {
+    let name = name.trim();
+    let filter = col("player_name").eq(lit(name.to_string()));
+    if name.contains("<sp/>") {
+        filter
+    } else {
+        filter.or(col("player_name")
+            .str()
+            .ends_with(lit(format!("<sp/>{}", name))))
+    }
+}
+
+/// The games, wins and losses aggregates of a group of details rows.
+pub fn player_record_aggs() -> Vec<Expr> {
+    vec![
+        col("ext_fs_id")
+            .n_unique()
+            .cast(DataType::UInt64)
+            .alias("games"),
+        col("player_result")
+            .eq(lit("Win"))
+            .sum()
+            .cast(DataType::UInt64)
+            .alias("wins"),
+        col("player_result")
+            .eq(lit("Loss"))
+            .sum()
+            .cast(DataType::UInt64)
+            .alias("losses"),
+    ]
+}
+
+/// Groups the details rows by `key` and returns the record of each group, most games first.
+pub fn player_records_by(
+    rows: LazyFrame,
+    key: &str,
+    limit: Option<usize>,
+) -> Result<Vec<(String, PlayerRecord)>, SwarmyTauriError> {
+    let mut grouped = rows
+        .group_by([col(key)])
+        .agg(player_record_aggs())
+        .sort_by_exprs(
+            [col("games"), col(key)],
+            SortMultipleOptions::default().with_order_descending_multi([true, false]),
+        );
+    if let Some(limit) = limit {
+        grouped = grouped.limit(limit as IdxSize);
+    }
+    let df = grouped.collect()?;
+    let keys = df.column(key)?.str()?;
+    let games = df.column("games")?.u64()?;
+    let wins = df.column("wins")?.u64()?;
+    let losses = df.column("losses")?.u64()?;
+    Ok((0..df.height())
+        .map(|idx| {
+            (
+                keys.get(idx).unwrap_or("Unknown").to_string(),
+                PlayerRecord {
+                    games: games.get(idx).unwrap_or(0),
+                    wins: wins.get(idx).unwrap_or(0),
+                    losses: losses.get(idx).unwrap_or(0),
+                },
+            )
+        })
+        .collect())
+}
+
+/// Computes the profile of a player from the details.ipc file.
+pub fn try_query_player_profile(
+    snapshots: &SnapshotRegistry,
+    query: PlayerProfileQuery,
+) -> Result<PlayerProfile, SwarmyTauriError> {
+    log::info!(
+        "Querying player profile from: {:?} for player: {}",
+        query.replay_paths,
+        query.player
+    );
+    if query.player.trim().is_empty() {
+        return Err(SwarmyTauriError::Other(String::from("Player is empty")));
+    }
+    let snapshot = snapshots.open(&query.replay_paths)?;
+    let details = snapshot.scan(DETAILS_IPC)?;
+    let player_rows = details.clone().filter(player_filter(&query.player)).cache();
+
+    let overall = player_rows.clone().select(player_record_aggs()).collect()?;
+    let record = PlayerRecord {
+        games: overall.column("games")?.u64()?.get(0).unwrap_or(0),
+        wins: overall.column("wins")?.u64()?.get(0).unwrap_or(0),
+        losses: overall.column("losses")?.u64()?.get(0).unwrap_or(0),
+    };
+    if record.games == 0 {
+        return Err(SwarmyTauriError::Other(format!(
+            "No games found for player: {}",
+            query.player
+        )));
+    }
+
+    let names = player_records_by(player_rows.clone(), "player_name", Some(1))?;
+    let (clan, name) = split_clan_tag(names.first().map(|(name, _)| name.as_str()).unwrap_or(""));
+
+    let toons = player_rows
+        .clone()
+        .select([
+            col("player_toon_region"),
+            col("player_toon_realm"),
+            col("player_toon_id"),
+        ])
+        .unique(None, UniqueKeepStrategy::First)
+        .collect()?;
+    let toon_regions = toons.column("player_toon_region")?.u8()?;
+    let toon_realms = toons.column("player_toon_realm")?.u32()?;
+    let toon_ids = toons.column("player_toon_id")?.u64()?;
+    let toon_handles = (0..toons.height())
+        .map(|idx| {
+            format_toon_handle(
+                toon_regions.get(idx).unwrap_or(0),
+                toon_realms.get(idx).unwrap_or(0),
+                toon_ids.get(idx).unwrap_or(0),
+            )
+        })
+        .collect();
+
+    // The races are named in the language of the client that saved each replay.
+    let races = player_records_by(
+        player_rows
+            .clone()
+            .with_column(race_id_expr(col("player_race")).alias("player_race")),
+        "player_race",
+        None,
+    )?
+    .into_iter()
+    .map(|(race, record)| PlayerRaceRecord { race, record })
+    .collect();
+
+    let favourite_maps =
+        player_records_by(player_rows.clone(), "title", Some(PLAYER_PROFILE_TOP_N))?
+            .into_iter()
+            .map(|(title, record)| PlayerMapRecord { title, record })
+            .collect();
+
+    let openings = match snapshot.scan_optional(BUILD_LABELS_IPC) {
+        Some(labels) => player_records_by(
+            join_build_labels(player_rows.clone(), labels),
+            "label",
+            None,
+        )?
+        .into_iter()
+        .map(|(label, record)| PlayerOpeningRecord { label, record })
+        .collect(),
+        None => vec![],
+    };
+
+    // The opponents are the players of the same games in another team, their rows carry the
+    // result of the profile player so that wins and losses are from its side.
+    let opponent_rows = player_rows
+        .clone()
+        .select([
+            col("ext_fs_id"),
+            col("player_team_id").alias("own_team_id"),
+            col("player_result"),
+        ])
+        .inner_join(
+            details.select([col("ext_fs_id"), col("player_name"), col("player_team_id")]),
+            col("ext_fs_id"),
+            col("ext_fs_id"),
+        )
+        .filter(col("player_team_id").neq(col("own_team_id")));
+    let opponents = player_records_by(opponent_rows, "player_name", Some(PLAYER_PROFILE_TOP_N))?
+        .into_iter()
+        .map(|(player_name, record)| {
+            let (clan, name) = split_clan_tag(&player_name);
+            PlayerOpponentRecord { clan, name, record }
+        })
+        .collect();
+
+    let activity = player_rows
+        .group_by([col("ext_datetime").dt().date().alias("date")])
+        .agg([col("ext_fs_id")
+            .n_unique()
+            .cast(DataType::UInt64)
+            .alias("games")])
+        .sort(["date"], Default::default())
+        .select([
+            col("date").dt().to_string("%Y-%m-%d").alias("date"),
+            col("games"),
+        ])
+        .collect()?;
+    let dates = activity.column("date")?.str()?;
+    let games = activity.column("games")?.u64()?;
+    let activity = (0..activity.height())
+        .filter_map(|idx| {
+            let date = chrono::NaiveDate::parse_from_str(dates.get(idx)?, "%Y-%m-%d").ok()?;
+            Some(PlayerActivityDay {
+                date,
+                games: games.get(idx).unwrap_or(0),
+            })
+        })
+        .collect();
+
+    Ok(PlayerProfile {
+        clan,
+        name,
+        toon_handles,
+        record,
+        races,
+        favourite_maps,
+        openings,
+        opponents,
+        activity,
+    })
+}
@@ -0,0 +1,212 @@
+//! Watches the replay folder of the active library and adds the new replays to its snapshot.
+
+use crate::mpq_file_scan::snapshot_schema::is_current_snapshot;
+use crate::mpq_file_scan::{run_optimize_job, try_get_current_app_config};
+use crate::settings::load_libraries_from_store;
+use crate::snapshot_registry::SnapshotRegistry;
+use notify_debouncer_full::notify::{EventKind, RecommendedWatcher, RecursiveMode};
+use notify_debouncer_full::{new_debouncer, DebounceEventResult, Debouncer, RecommendedCache};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::AtomicBool;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use swarmy_tauri_common::*;
+use tauri::{Emitter, Manager};
+use tauri_plugin_store::{Store, StoreBuilder};
+
+/// How long a replay must stay unchanged before it is added, the game writes it in several steps.
+const REPLAY_SETTLE_DELAY: Duration = Duration::from_secs(5);
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn set_replay_watcher(
+    app_handle: tauri::AppHandle,
+    enabled: bool,
+) -> ApiResponse<AppSettings> {
+    let init_time = std::time::Instant::now();
+    let res = match StoreBuilder::new(&app_handle, "settings.json").build() {
+        Ok(store) => {
+            // If there are no saved settings yet, this will return an error so we ignore the return value.
+            let _ = store.reload();
+            store.set("watch_replay_path", enabled);
+            match refresh_replay_watcher(&app_handle, &store) {
+                Ok(()) => try_get_current_app_config(&app_handle).await,
+                Err(e) => {
+                    // The folder cannot be watched, the watcher stays off.
+                    store.set("watch_replay_path", false);
+                    Err(e)
+                }
+            }
+        }
+        Err(e) => Err(SwarmyTauriError::Other(format!(
+            "Error building store: {}",
+            e
+        ))),
+    };
+    if let Err(e) = &res {
+        log::error!("Error setting the replay watcher: {}", e);
+    }
+    ApiResponse::from_result(res, init_time.elapsed().as_millis() as u64)
+}
+
+/// The watcher of the replay folder, kept in the Tauri managed state.
+#[derive(Default)]
+pub struct ReplayWatcher {
+    watched: Mutex<Option<WatchedFolder>>,
+}
+
+struct WatchedFolder {
+    replay_path: String,
+    /// Dropping the debouncer stops the watch and the update task with it.
+    _debouncer: Debouncer<RecommendedWatcher, RecommendedCache>,
+}
+
+/// Whether the replay folder of the active library is watched, off by default.
+pub fn is_replay_watcher_enabled<R: tauri::Runtime>(store: &Store<R>) -> bool {
+    store
+        .get("watch_replay_path")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
+}
+
+/// Watches the replay folder of the active library if the watcher is enabled in the store,
+/// stops watching the previous folder if it changed.
+pub fn refresh_replay_watcher(
+    app_handle: &tauri::AppHandle,
+    store: &Store<tauri::Wry>,
+) -> Result<(), SwarmyTauriError> {
+    let (libraries, selection) = load_libraries_from_store(store);
+    let replay_path = libraries
+        .into_iter()
+        .find(|library| library.name == selection.active)
+        .map(|library| library.replay_path)
+        .filter(|replay_path| is_replay_watcher_enabled(store) && !replay_path.is_empty());
+    let replay_watcher = app_handle.state::<ReplayWatcher>();
+    let mut watched = replay_watcher
+        .watched
+        .lock()
+        .map_err(|_| SwarmyTauriError::Other(String::from("Replay watcher lock poisoned")))?;
+    if watched.as_ref().map(|folder| &folder.replay_path) == replay_path.as_ref() {
+        return Ok(());
+    }
+    if let Some(folder) = watched.take() {
+        log::info!("Stopped watching replay folder: {}", folder.replay_path);
+    }
+    if let Some(replay_path) = replay_path {
+        *watched = Some(watch_replay_path(app_handle.clone(), replay_path)?);
+    }
+    Ok(())
+}
+
+/// Watches `replay_path` recursively, the replays that stopped changing are sent in batches to a
+/// task that adds them to the snapshot one batch at a time.
+fn watch_replay_path(
+    app_handle: tauri::AppHandle,
+    replay_path: String,
+) -> Result<WatchedFolder, SwarmyTauriError> {
+    let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel::<Vec<PathBuf>>();
+    let mut debouncer = new_debouncer(
+        REPLAY_SETTLE_DELAY,
+        None,
+        move |res: DebounceEventResult| match res {
+            Ok(events) => {
+                let replays: Vec<PathBuf> = events
+                    .into_iter()
+                    .filter(|event| {
+                        matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_))
+                    })
+                    .flat_map(|event| event.event.paths)
+                    .filter(|path| is_replay_file(path))
+                    .collect();
+                if !replays.is_empty() {
+                    // The update task stops with the watcher, there is nobody to tell then.
+                    let _ = sender.send(replays);
+                }
+            }
+            Err(errors) => {
+                for e in errors {
+                    log::error!("Replay watcher error: {}", e);
+                }
+            }
+        },
+    )
+    .map_err(|e| SwarmyTauriError::Other(format!("Error creating the replay watcher: {}", e)))?;
+    debouncer
+        .watch(Path::new(&replay_path), RecursiveMode::Recursive)
+        .map_err(|e| SwarmyTauriError::Other(format!("Error watching {}: {}", replay_path, e)))?;
+    log::info!("Watching replay folder: {}", replay_path);
+    let task_replay_path = replay_path.clone();
+    tauri::async_runtime::spawn(async move {
+        while let Some(mut replays) = receiver.recv().await {
+            // The replays saved while the previous update ran are added together.
+            while let Ok(more) = receiver.try_recv() {
+                replays.extend(more);
+            }
+            let replays: HashSet<PathBuf> = replays.into_iter().collect();
+            if let Err(e) = update_snapshot(&app_handle, &task_replay_path, replays.len()).await {
+                log::error!("Error updating the snapshot of {}: {}", task_replay_path, e);
+            }
+        }
+    });
+    Ok(WatchedFolder {
+        replay_path,
+        _debouncer: debouncer,
+    })
+}
+
+fn is_replay_file(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("SC2Replay"))
+}
+
+/// Adds the new `replays` of `replay_path` to its snapshot with an incremental optimize and
+/// emits [`SNAPSHOT_UPDATED_EVENT`] so that the views load it again.
+/// A folder without a current snapshot is left to the Optimize button, a full optimize may take
+/// a long time.
+async fn update_snapshot(
+    app_handle: &tauri::AppHandle,
+    replay_path: &str,
+    replays: usize,
+) -> Result<(), SwarmyTauriError> {
+    let ipc_dir = app_handle.state::<SnapshotRegistry>().ipc_dir(replay_path);
+    if !is_current_snapshot(&ipc_dir) {
+        log::info!(
+            "Not updating {}, it has no current snapshot to add the replays to",
+            replay_path
+        );
+        return Ok(());
+    }
+    let settings = try_get_current_app_config(app_handle).await?;
+    if settings.replay_path != replay_path {
+        // The active library changed, its own watcher takes over.
+        return Ok(());
+    }
+    log::info!(
+        "Adding {} new or changed replays to the snapshot of {}",
+        replays,
+        replay_path
+    );
+    run_optimize_job(
+        app_handle,
+        "update_snapshot",
+        replay_path.to_string(),
+        settings.disable_parallel_scans,
+        OptimizeSettings {
+            incremental: true,
+            ..settings.optimize_settings
+        },
+        settings.build_rules_path,
+        // The cancel button only reaches the manual optimize jobs.
+        Arc::new(AtomicBool::new(false)),
+    )
+    .await?;
+    app_handle
+        .emit(
+            SNAPSHOT_UPDATED_EVENT,
+            SnapshotUpdated {
+                replay_path: replay_path.to_string(),
+                replays,
+            },
+        )
+        .map_err(|e| SwarmyTauriError::Other(format!("Error emitting snapshot update: {}", e)))
+}
@@ -1,34 +1,157 @@
 //! Module for application settings management.
+use std::path::{Path, PathBuf};
 use swarmy_tauri_common::*;
-use tauri_plugin_store::Store;
+use tauri::Manager;
+use tauri_plugin_store::{Store, StoreBuilder};
 
+use crate::jobs::JobRunner;
+use crate::mpq_file_scan::try_get_current_app_config;
+use crate::replay_watcher::is_replay_watcher_enabled;
+use crate::snapshot_registry::{SnapshotRegistry, SNAPSHOTS_DIR};
 use crate::try_get_snapshot_metadata;
 
+/// Stores the snapshots under `snapshot_dir`, empty for the default under the app data dir.
+/// The replay folders must be optimized again, their snapshots are not moved.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn set_snapshot_dir(
+    app_handle: tauri::AppHandle,
+    snapshot_dir: String,
+) -> ApiResponse<AppSettings> {
+    let init_time = std::time::Instant::now();
+    let res = match try_set_snapshot_dir(&app_handle, &snapshot_dir) {
+        Ok(()) => try_get_current_app_config(&app_handle).await,
+        Err(e) => Err(e),
+    };
+    if let Err(e) = &res {
+        log::error!("Error setting the snapshot directory: {}", e);
+    }
+    ApiResponse::from_result(res, init_time.elapsed().as_millis() as u64)
+}
+
+fn try_set_snapshot_dir(
+    app_handle: &tauri::AppHandle,
+    snapshot_dir: &str,
+) -> Result<(), SwarmyTauriError> {
+    let store = StoreBuilder::new(app_handle, "settings.json")
+        .build()
+        .map_err(|e| SwarmyTauriError::Other(format!("Error building store: {}", e)))?;
+    // If there are no saved settings yet, this will return an error so we ignore the return value.
+    let _ = store.reload();
+    let snapshot_root = if snapshot_dir.trim().is_empty() {
+        store.delete("snapshot_dir");
+        default_snapshot_root(app_handle)?
+    } else {
+        store.set("snapshot_dir", snapshot_dir.trim());
+        PathBuf::from(snapshot_dir.trim())
+    };
+    std::fs::create_dir_all(&snapshot_root)?;
+    log::info!("Storing the snapshots in: {}", snapshot_root.display());
+    app_handle
+        .state::<SnapshotRegistry>()
+        .set_snapshot_root(snapshot_root);
+    Ok(())
+}
+
+/// The directory with the snapshots, the one configured in the store or [`SNAPSHOTS_DIR`] under
+/// the app data dir, so that optimize never writes into the replay folders.
+pub fn snapshot_root_from_store<R: tauri::Runtime>(
+    store: &Store<R>,
+    app_handle: &tauri::AppHandle<R>,
+) -> Result<PathBuf, SwarmyTauriError> {
+    match configured_snapshot_dir(|key| store.get(key)) {
+        Some(snapshot_dir) => Ok(snapshot_dir),
+        None => default_snapshot_root(app_handle),
+    }
+}
+
+/// The snapshot directory configured with `set_snapshot_dir`, None for the default.
+/// `get` reads a value of the settings store.
+pub fn configured_snapshot_dir<F>(get: F) -> Option<PathBuf>
+where
+    F: Fn(&str) -> Option<serde_json::Value>,
+{
+    get("snapshot_dir")
+        .and_then(|v| v.as_str().map(|s| s.trim().to_string()))
+        .filter(|snapshot_dir| !snapshot_dir.is_empty())
+        .map(PathBuf::from)
+}
+
+fn default_snapshot_root<R: tauri::Runtime>(
+    app_handle: &tauri::AppHandle<R>,
+) -> Result<PathBuf, SwarmyTauriError> {
+    let app_data_dir = app_handle
+        .path()
+        .app_data_dir()
+        .map_err(|e| SwarmyTauriError::Other(format!("No app data directory: {}", e)))?;
+    Ok(app_data_dir.join(SNAPSHOTS_DIR))
+}
+
+/// Reads the settings store of the app from `app_data_dir` without the app, i.e. from the CLI.
+/// The settings are empty if the app never saved them.
+pub fn read_settings_file(
+    app_data_dir: &Path,
+) -> Result<serde_json::Map<String, serde_json::Value>, SwarmyTauriError> {
+    let settings_path = app_data_dir.join("settings.json");
+    if !settings_path.exists() {
+        return Ok(Default::default());
+    }
+    let contents = std::fs::read_to_string(settings_path)?;
+    Ok(serde_json::from_str(&contents)?)
+}
+
 pub async fn load_app_settings_from_store<R: tauri::Runtime>(
     store: &Store<R>,
+    jobs: &JobRunner,
+    snapshots: &SnapshotRegistry,
 ) -> Result<AppSettings, SwarmyTauriError> {
     let disable_parallel_scans = store
         .get("disable_parallel_scans")
         .and_then(|v| v.as_bool())
         .unwrap_or(false);
 
-    let replay_path = store
-        .get("replay_path")
+    let sql_history = store
+        .get("sql_history")
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default();
+
+    let build_rules_path = store
+        .get("build_rules_path")
         .and_then(|v| v.as_str().map(|s| s.to_string()))
         .unwrap_or_default();
 
+    let (libraries, library_selection) = load_libraries_from_store(store);
+    let active_library = libraries
+        .iter()
+        .find(|library| library.name == library_selection.active)
+        .cloned()
+        .unwrap_or_default();
+    let replay_path = active_library.replay_path;
+    let query_replay_paths = library_selection.replay_paths(&libraries);
+
     // if the ipc directory do basic scan.
-    let ipc_path = std::path::Path::new(&replay_path).join("ipcs");
+    // Opening the snapshot validates its schema, an outdated snapshot is reported to the UI.
+    let ipc_path = snapshots.ipc_dir(&replay_path);
+    let mut snapshot_outdated = None;
     let arrow_ipc_stats = if ipc_path.exists() && ipc_path.is_dir() {
         let replay_path_cp = replay_path.clone();
-        let t = std::thread::spawn(move || match try_get_snapshot_metadata(replay_path_cp) {
+        let snapshots = snapshots.clone();
+        match jobs
+            .run("get_snapshot_metadata", move || {
+                try_get_snapshot_metadata(&snapshots, replay_path_cp)
+            })
+            .await
+        {
             Ok(val) => val,
+            Err(e @ SwarmyTauriError::SnapshotOutdated(_)) => {
+                log::warn!("{}", e);
+                snapshot_outdated = Some(e.to_string());
+                SnapshotStats::default()
+            }
             Err(e) => {
                 log::error!("Error getting snapshot metadata: {}", e);
                 SnapshotStats::default()
             }
-        });
-        t.join().unwrap()
+        }
     } else {
         SnapshotStats::default()
     };
@@ -36,6 +159,100 @@ pub async fn load_app_settings_from_store<R: tauri::Runtime>(
     Ok(AppSettings {
         disable_parallel_scans,
         replay_path,
+        optimize_settings: active_library.optimize_settings,
+        libraries,
+        library_selection,
+        query_replay_paths,
+        sql_history,
+        build_rules_path,
         arrow_ipc_stats,
+        snapshot_outdated,
+        snapshot_dir: snapshots.snapshot_root().display().to_string(),
+        watch_replay_path: is_replay_watcher_enabled(store),
     })
 }
+
+/// Reads the libraries and the library selection from the store.
+pub fn load_libraries_from_store<R: tauri::Runtime>(
+    store: &Store<R>,
+) -> (Vec<ReplayLibrary>, LibrarySelection) {
+    load_libraries(|key| store.get(key))
+}
+
+/// Reads the libraries and the library selection with `get`, which reads a value of the settings
+/// store.
+/// The single replay path of older versions becomes the [`DEFAULT_LIBRARY_NAME`] library.
+pub fn load_libraries<F>(get: F) -> (Vec<ReplayLibrary>, LibrarySelection)
+where
+    F: Fn(&str) -> Option<serde_json::Value>,
+{
+    let mut libraries: Vec<ReplayLibrary> = get("libraries")
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default();
+    if libraries.is_empty() {
+        let replay_path = get("replay_path")
+            .and_then(|v| v.as_str().map(|s| s.to_string()))
+            .unwrap_or_default();
+        if !replay_path.is_empty() {
+            libraries.push(ReplayLibrary {
+                name: String::from(DEFAULT_LIBRARY_NAME),
+                replay_path,
+                optimize_settings: get("optimize_settings")
+                    .and_then(|v| serde_json::from_value(v).ok())
+                    .unwrap_or_default(),
+            });
+        }
+    }
+    let mut selection: LibrarySelection = get("library_selection")
+        .and_then(|v| serde_json::from_value(v).ok())
+        .unwrap_or_default();
+    // The selected libraries may have been removed since.
+    if !libraries
+        .iter()
+        .any(|library| library.name == selection.active)
+    {
+        selection.active = libraries
+            .first()
+            .map(|library| library.name.clone())
+            .unwrap_or_default();
+    }
+    let active = selection.active.clone();
+    selection
+        .union
+        .retain(|name| *name != active && libraries.iter().any(|library| library.name == *name));
+    (libraries, selection)
+}
+
+/// Writes the libraries and the library selection to the store.
+pub fn save_libraries_to_store<R: tauri::Runtime>(
+    store: &Store<R>,
+    libraries: &[ReplayLibrary],
+    selection: &LibrarySelection,
+) {
+    store.set("libraries", serde_json::json!(libraries));
+    store.set("library_selection", serde_json::json!(selection));
+}
+
+/// Updates the active library, the [`DEFAULT_LIBRARY_NAME`] library is created if there are no
+/// libraries yet.
+pub fn update_active_library<R, F>(store: &Store<R>, update: F)
+where
+    R: tauri::Runtime,
+    F: FnOnce(&mut ReplayLibrary),
+{
+    let (mut libraries, mut selection) = load_libraries_from_store(store);
+    if libraries.is_empty() {
+        selection.active = String::from(DEFAULT_LIBRARY_NAME);
+        libraries.push(ReplayLibrary {
+            name: selection.active.clone(),
+            ..Default::default()
+        });
+    }
+    if let Some(library) = libraries
+        .iter_mut()
+        .find(|library| library.name == selection.active)
+    {
+        update(library);
+    }
+    save_libraries_to_store(store, &libraries, &selection);
+}
@@ -0,0 +1,442 @@
+//! Keeps the IPC files of the snapshots open between queries.
+
+use crate::mpq_file_scan::manifest::SnapshotManifest;
+use crate::mpq_file_scan::snapshot_schema::validate_snapshot_schema;
+use polars::prelude::*;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::time::SystemTime;
+use swarmy_tauri_common::*;
+
+/// The directory under the app data dir where the snapshots are stored by default.
+pub const SNAPSHOTS_DIR: &str = "snapshots";
+
+/// The directory of the snapshot of the replay folder `replay_path` under `snapshot_root`.
+/// It is named after the replay folder and a hash of its path, so that replay folders with the
+/// same name do not share a snapshot.
+pub fn snapshot_ipc_dir(snapshot_root: &Path, replay_path: &str) -> PathBuf {
+    let replay_path = replay_path.trim_end_matches(['/', '\\']);
+    let folder_name = Path::new(replay_path)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let hash = sha256::digest(replay_path);
+    snapshot_root.join(format!("{}-{}", folder_name, &hash[..16]))
+}
+
+/// The snapshots opened so far, kept in the Tauri managed state.
+/// A snapshot is opened again when any of its files is added, removed or rewritten.
+/// A query may target several libraries, their snapshots are opened one by one and queried as
+/// their union, which is kept until any of them is opened again.
+#[derive(Clone)]
+pub struct SnapshotRegistry {
+    snapshots: Arc<Mutex<HashMap<PathBuf, Arc<Snapshot>>>>,
+    /// The unions by the sorted snapshot directories of their libraries.
+    unions: Arc<Mutex<HashMap<Vec<PathBuf>, UnionSnapshot>>>,
+    /// The directory with the snapshots of the replay folders, see [`snapshot_ipc_dir`].
+    snapshot_root: Arc<RwLock<PathBuf>>,
+}
+
+impl SnapshotRegistry {
+    pub fn new(snapshot_root: PathBuf) -> Self {
+        Self {
+            snapshots: Default::default(),
+            unions: Default::default(),
+            snapshot_root: Arc::new(RwLock::new(snapshot_root)),
+        }
+    }
+
+    /// The directory with the snapshots of the replay folders.
+    pub fn snapshot_root(&self) -> PathBuf {
+        self.snapshot_root
+            .read()
+            .map(|snapshot_root| snapshot_root.clone())
+            .unwrap_or_default()
+    }
+
+    /// Moves the snapshots to `snapshot_root`, the snapshots opened so far are forgotten.
+    /// The existing snapshots are not moved, the replay folders must be optimized again.
+    pub fn set_snapshot_root(&self, snapshot_root: PathBuf) {
+        if let Ok(mut root) = self.snapshot_root.write() {
+            *root = snapshot_root;
+        }
+        if let Ok(mut snapshots) = self.snapshots.lock() {
+            snapshots.clear();
+        }
+        if let Ok(mut unions) = self.unions.lock() {
+            unions.clear();
+        }
+    }
+
+    /// The directory of the snapshot of the replay folder `replay_path`.
+    pub fn ipc_dir(&self, replay_path: &str) -> PathBuf {
+        snapshot_ipc_dir(&self.snapshot_root(), replay_path)
+    }
+
+    /// Returns the snapshot of the replay folders `replay_paths`, the union of their snapshots
+    /// if there are several, see [`SnapshotRegistry::open_library`].
+    pub fn open(&self, replay_paths: &[String]) -> Result<Arc<Snapshot>, SwarmyTauriError> {
+        let mut replay_paths: Vec<&str> = replay_paths
+            .iter()
+            .map(String::as_str)
+            .filter(|replay_path| !replay_path.is_empty())
+            .collect();
+        // The ext_fs_id of the games depends on the position of their library in the union.
+        replay_paths.sort_unstable();
+        replay_paths.dedup();
+        match replay_paths.as_slice() {
+            [] => Err(SwarmyTauriError::Other(String::from(
+                "No library selected, add a replay folder first",
+            ))),
+            [replay_path] => self.open_library(replay_path),
+            _ => self.open_union(&replay_paths),
+        }
+    }
+
+    /// Returns the snapshot of the replay folder `replay_path`, opening it if it is not cached
+    /// yet or if its files changed since.
+    /// Fails with [`SwarmyTauriError::SnapshotOutdated`] if the snapshot must be optimized again.
+    pub fn open_library(&self, replay_path: &str) -> Result<Arc<Snapshot>, SwarmyTauriError> {
+        let ipc_path = self.ipc_dir(replay_path);
+        if let Some(snapshot) = self.cached(&ipc_path) {
+            if !snapshot.is_stale() {
+                return Ok(snapshot);
+            }
+            log::info!("Snapshot changed, opening it again: {}", ipc_path.display());
+        }
+        let snapshot = Arc::new(Snapshot::open(&ipc_path)?);
+        if let Ok(mut snapshots) = self.snapshots.lock() {
+            snapshots.insert(ipc_path, snapshot.clone());
+        }
+        Ok(snapshot)
+    }
+
+    /// The union of the sorted `replay_paths`, built again when the snapshot of any of them was
+    /// opened again.
+    fn open_union(&self, replay_paths: &[&str]) -> Result<Arc<Snapshot>, SwarmyTauriError> {
+        let libraries = replay_paths
+            .iter()
+            .map(|replay_path| self.open_library(replay_path))
+            .collect::<Result<Vec<_>, _>>()?;
+        let ipc_dirs: Vec<PathBuf> = libraries
+            .iter()
+            .map(|library| library.ipc_path.clone())
+            .collect();
+        if let Some(union) = self
+            .unions
+            .lock()
+            .ok()
+            .and_then(|unions| unions.get(&ipc_dirs).cloned())
+        {
+            let is_current = union.libraries.len() == libraries.len()
+                && union
+                    .libraries
+                    .iter()
+                    .zip(&libraries)
+                    .all(|(cached, library)| Arc::ptr_eq(cached, library));
+            if is_current {
+                return Ok(union.snapshot);
+            }
+        }
+        let snapshot = Arc::new(Snapshot::union(&libraries));
+        if let Ok(mut unions) = self.unions.lock() {
+            unions.insert(
+                ipc_dirs,
+                UnionSnapshot {
+                    libraries,
+                    snapshot: snapshot.clone(),
+                },
+            );
+        }
+        Ok(snapshot)
+    }
+
+    /// Forgets the snapshot of the replay folder `replay_path` and the unions it is part of, the
+    /// next query opens them again.
+    pub fn invalidate(&self, replay_path: &str) {
+        let ipc_dir = self.ipc_dir(replay_path);
+        if let Ok(mut snapshots) = self.snapshots.lock() {
+            snapshots.remove(&ipc_dir);
+        }
+        if let Ok(mut unions) = self.unions.lock() {
+            unions.retain(|ipc_dirs, _| !ipc_dirs.contains(&ipc_dir));
+        }
+    }
+
+    fn cached(&self, ipc_path: &Path) -> Option<Arc<Snapshot>> {
+        self.snapshots
+            .lock()
+            .ok()
+            .and_then(|snapshots| snapshots.get(ipc_path).cloned())
+    }
+}
+
+/// A union of snapshots along with the snapshots of the libraries it was built from.
+#[derive(Clone)]
+struct UnionSnapshot {
+    libraries: Vec<Arc<Snapshot>>,
+    snapshot: Arc<Snapshot>,
+}
+
+/// An IPC file of a snapshot, with the modification time and length it was opened with.
+/// Its frame also has the rows of its parts, see [`SnapshotManifest::scan_ipc`].
+struct IpcFile {
+    path: PathBuf,
+    modified: SystemTime,
+    len: u64,
+    /// The length of the parts of the file.
+    parts_len: u64,
+    frame: LazyFrame,
+    schema: SchemaRef,
+}
+
+/// The number of bits the ext_fs_id of the games of each library is shifted by in a union, the
+/// ids within a library stay below it.
+const LIBRARY_EXT_FS_ID_SHIFT: u32 = 40;
+
+/// The IPC files of a snapshot directory, scanned once, and the small tables derived from them.
+pub struct Snapshot {
+    ipc_path: PathBuf,
+    /// The modification time of the directory, which changes when files are added or removed.
+    modified: SystemTime,
+    /// The IPC files by file name, i.e. `details.ipc`.
+    files: BTreeMap<String, IpcFile>,
+    map_titles: OnceLock<Vec<String>>,
+    player_names: OnceLock<Vec<String>>,
+}
+
+/// Returns the values of the cache, computing them on first use.
+fn cached_values<F>(cache: &OnceLock<Vec<String>>, values: F) -> Result<&[String], SwarmyTauriError>
+where
+    F: FnOnce() -> Result<Vec<String>, SwarmyTauriError>,
+{
+    if let Some(values) = cache.get() {
+        return Ok(values);
+    }
+    let values = values()?;
+    // Another query may have filled the cache meanwhile, both have the same values.
+    Ok(cache.get_or_init(|| values))
+}
+
+impl Snapshot {
+    fn open(ipc_path: &Path) -> Result<Self, SwarmyTauriError> {
+        log::info!("Opening snapshot: {}", ipc_path.display());
+        let modified = std::fs::metadata(ipc_path)?.modified()?;
+        let manifest = SnapshotManifest::load(ipc_path)?.unwrap_or_default();
+        let mut files = BTreeMap::new();
+        for entry in std::fs::read_dir(ipc_path)? {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("ipc") {
+                continue;
+            }
+            let Some(file_name) = path.file_name().and_then(|name| name.to_str()) else {
+                continue;
+            };
+            let metadata = std::fs::metadata(&path)?;
+            let Some(mut frame) = manifest.scan_ipc(ipc_path, file_name)? else {
+                continue;
+            };
+            let parts_len = (1..=manifest.parts)
+                .filter_map(|part| {
+                    std::fs::metadata(SnapshotManifest::part_dir(ipc_path, part).join(file_name))
+                        .ok()
+                })
+                .map(|metadata| metadata.len())
+                .sum();
+            // Reading the schema once also keeps it resolved in the cloned frames.
+            let schema = frame.collect_schema()?;
+            files.insert(
+                file_name.to_string(),
+                IpcFile {
+                    modified: metadata.modified()?,
+                    len: metadata.len(),
+                    parts_len,
+                    path,
+                    frame,
+                    schema,
+                },
+            );
+        }
+        validate_snapshot_schema(
+            ipc_path,
+            files
+                .iter()
+                .map(|(file_name, file)| (file_name.as_str(), file.schema.as_ref())),
+        )?;
+        Ok(Self {
+            ipc_path: ipc_path.to_path_buf(),
+            modified,
+            files,
+            map_titles: OnceLock::new(),
+            player_names: OnceLock::new(),
+        })
+    }
+
+    /// The union of the snapshots of several `libraries`, with the files all of them have.
+    /// The ext_fs_id of the games of the n-th library is offset by n << 40, so that the games of
+    /// different libraries keep distinct ids.
+    fn union(libraries: &[Arc<Snapshot>]) -> Self {
+        let mut files = BTreeMap::new();
+        let first = &libraries[0];
+        for (file_name, first_file) in &first.files {
+            let library_files: Vec<&IpcFile> = libraries
+                .iter()
+                .filter_map(|library| library.files.get(file_name))
+                .collect();
+            if library_files.len() != libraries.len() {
+                continue;
+            }
+            let has_ext_fs_id = first_file.schema.contains("ext_fs_id");
+            let frames: Vec<LazyFrame> = library_files
+                .iter()
+                .enumerate()
+                .map(|(index, file)| {
+                    let offset = (index as u64) << LIBRARY_EXT_FS_ID_SHIFT;
+                    if has_ext_fs_id && offset > 0 {
+                        file.frame
+                            .clone()
+                            .with_column((col("ext_fs_id") + lit(offset)).alias("ext_fs_id"))
+                    } else {
+                        file.frame.clone()
+                    }
+                })
+                .collect();
+            let Ok(frame) = concat(frames, UnionArgs::default()) else {
+                continue;
+            };
+            files.insert(
+                file_name.clone(),
+                IpcFile {
+                    path: first_file.path.clone(),
+                    modified: library_files
+                        .iter()
+                        .map(|file| file.modified)
+                        .max()
+                        .unwrap_or(first_file.modified),
+                    len: library_files.iter().map(|file| file.len).sum(),
+                    parts_len: library_files.iter().map(|file| file.parts_len).sum(),
+                    frame,
+                    schema: first_file.schema.clone(),
+                },
+            );
+        }
+        let ipc_paths: Vec<String> = libraries
+            .iter()
+            .map(|library| library.ipc_path.display().to_string())
+            .collect();
+        Self {
+            ipc_path: PathBuf::from(ipc_paths.join(", ")),
+            modified: libraries
+                .iter()
+                .map(|library| library.modified)
+                .max()
+                .unwrap_or(first.modified),
+            files,
+            map_titles: OnceLock::new(),
+            player_names: OnceLock::new(),
+        }
+    }
+
+    /// Whether files were added, removed or rewritten since the snapshot was opened.
+    fn is_stale(&self) -> bool {
+        let modified = std::fs::metadata(&self.ipc_path).and_then(|metadata| metadata.modified());
+        if modified.ok() != Some(self.modified) {
+            return true;
+        }
+        self.files.values().any(|file| {
+            std::fs::metadata(&file.path)
+                .and_then(|metadata| Ok((metadata.modified()?, metadata.len())))
+                .map(|(modified, len)| modified != file.modified || len != file.len)
+                .unwrap_or(true)
+        })
+    }
+
+    pub fn ipc_path(&self) -> &Path {
+        &self.ipc_path
+    }
+
+    /// The size of the IPC files of the snapshot.
+    pub fn directory_size(&self) -> u64 {
+        self.files
+            .values()
+            .map(|file| file.len + file.parts_len)
+            .sum()
+    }
+
+    /// The modification time of `file_name`, which must be in the snapshot.
+    pub fn modified(&self, file_name: &str) -> Result<SystemTime, SwarmyTauriError> {
+        Ok(self.file(file_name)?.modified)
+    }
+
+    /// Scans `file_name`, which must be in the snapshot.
+    pub fn scan(&self, file_name: &str) -> Result<LazyFrame, SwarmyTauriError> {
+        Ok(self.file(file_name)?.frame.clone())
+    }
+
+    /// Scans `file_name`, None when the snapshot does not have it, i.e. build_labels.ipc before
+    /// the build orders are classified.
+    pub fn scan_optional(&self, file_name: &str) -> Option<LazyFrame> {
+        self.files.get(file_name).map(|file| file.frame.clone())
+    }
+
+    /// The schema of `file_name`, None when the snapshot does not have it.
+    pub fn schema(&self, file_name: &str) -> Option<SchemaRef> {
+        self.files.get(file_name).map(|file| file.schema.clone())
+    }
+
+    /// The files of the snapshot as tables named after the file, i.e. `details.ipc` is the
+    /// `details` table.
+    pub fn tables(&self) -> impl Iterator<Item = (&str, LazyFrame)> {
+        self.files
+            .iter()
+            .map(|(file_name, file)| (file_name.trim_end_matches(".ipc"), file.frame.clone()))
+    }
+
+    /// The distinct map titles of the snapshot, sorted.
+    pub fn map_titles(&self) -> Result<&[String], SwarmyTauriError> {
+        cached_values(&self.map_titles, || self.distinct_details_values("title"))
+    }
+
+    /// The distinct player names of the snapshot without their clan tag, sorted. A name without
+    /// clan tag matches the player under any clan tag, see [`crate::player_profile::player_filter`].
+    pub fn player_names(&self) -> Result<&[String], SwarmyTauriError> {
+        cached_values(&self.player_names, || {
+            let mut names: Vec<String> = self
+                .distinct_details_values("player_name")?
+                .iter()
+                .map(|name| split_clan_tag(name).1)
+                .collect();
+            names.sort();
+            names.dedup();
+            Ok(names)
+        })
+    }
+
+    fn distinct_details_values(&self, column: &str) -> Result<Vec<String>, SwarmyTauriError> {
+        let res = self
+            .scan(DETAILS_IPC)?
+            .select([col(column)])
+            .filter(col(column).is_not_null())
+            .unique(None, UniqueKeepStrategy::Any)
+            .sort([column], Default::default())
+            .collect()?;
+        Ok(res
+            .column(column)?
+            .str()?
+            .into_iter()
+            .flatten()
+            .map(|value| value.to_string())
+            .collect())
+    }
+
+    fn file(&self, file_name: &str) -> Result<&IpcFile, SwarmyTauriError> {
+        self.files.get(file_name).ok_or_else(|| {
+            SwarmyTauriError::Other(format!(
+                "The snapshot at {} has no {}, optimize the replays first",
+                self.ipc_path.display(),
+                file_name
+            ))
+        })
+    }
+}
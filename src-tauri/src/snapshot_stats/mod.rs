@@ -1,56 +1,44 @@
 //! Provides information about the analyzed game collection.
+use crate::jobs::JobRunner;
+use crate::snapshot_registry::{Snapshot, SnapshotRegistry};
 use polars::prelude::*;
 use swarmy_tauri_common::*;
+use tauri::Manager;
 
 #[tauri::command(rename_all = "snake_case")]
-pub async fn get_snapshot_metadata(replay_path: String) -> ApiResponse {
-    // create a thread to get the metadata in the background:
-    let t = std::thread::spawn(move || {
-        let init_time = std::time::Instant::now();
-        match try_get_snapshot_metadata(replay_path) {
-            Ok(val) => ApiResponse::new(
-                ResponseMetaBuilder::new(true)
-                    .duration_ms(init_time.elapsed().as_millis() as u64)
-                    .build(),
-                serde_json::to_string(&val).unwrap_or_default(),
-            ),
-            Err(e) => {
-                log::error!("Error getting snapshot metadata: {}", e);
-                ApiResponse::new(
-                    ResponseMetaBuilder::new(false)
-                        .duration_ms(init_time.elapsed().as_millis() as u64)
-                        .build(),
-                    format!("Error getting snapshot metadata: {:?}", e),
-                )
-            }
-        }
-    });
-    t.join().unwrap()
+pub async fn get_snapshot_metadata(
+    app_handle: tauri::AppHandle,
+    replay_path: String,
+) -> ApiResponse<SnapshotStats> {
+    let init_time = std::time::Instant::now();
+    let snapshots = app_handle.state::<SnapshotRegistry>().inner().clone();
+    let res = app_handle
+        .state::<JobRunner>()
+        .run("get_snapshot_metadata", move || {
+            try_get_snapshot_metadata(&snapshots, replay_path)
+        })
+        .await;
+    if let Err(e) = &res {
+        log::error!("Error getting snapshot metadata: {}", e);
+    }
+    ApiResponse::from_result(res, init_time.elapsed().as_millis() as u64)
 }
 
 /// Gets the list of maps from the details.ipc file
-pub fn try_get_snapshot_metadata(replay_path: String) -> Result<SnapshotStats, SwarmyTauriError> {
-    let replay_path = format!("{}/ipcs/", replay_path);
-    log::info!("Getting snapshot metadata from: {}", replay_path);
-    // Add the size of all the files in state.source_dir
-    let mut directory_size = 0;
-    for entry in std::fs::read_dir(&replay_path)? {
-        let entry = entry?;
-        let path = entry.path();
-        let metadata = std::fs::metadata(path)?;
-        let size = metadata.len();
-        directory_size += size;
-    }
-    // get the date_modified of the details.ipc file
-    let details_ipc_filename = format!("{}/{}", replay_path, DETAILS_IPC);
-    let date_modified = std::fs::metadata(details_ipc_filename)?.modified()?;
-    let details_query = LazyFrame::scan_ipc(
-        PlPath::new(&format!("{}/{}", replay_path, DETAILS_IPC)),
-        Default::default(),
-        Default::default(),
-    )?;
+pub fn try_get_snapshot_metadata(
+    snapshots: &SnapshotRegistry,
+    replay_path: String,
+) -> Result<SnapshotStats, SwarmyTauriError> {
+    let snapshot = snapshots.open_library(&replay_path)?;
+    log::info!(
+        "Getting snapshot metadata from: {}",
+        snapshot.ipc_path().display()
+    );
+    let directory_size = snapshot.directory_size();
+    let date_modified = snapshot.modified(DETAILS_IPC)?;
+    let details_query = snapshot.scan(DETAILS_IPC)?;
 
-    // Date range from the details.ipc file
+    // Date range and distinct counts from the details.ipc file, each row is a player in a game.
     let res = details_query
         .select([
             col("ext_datetime")
@@ -63,7 +51,26 @@ pub fn try_get_snapshot_metadata(replay_path: String) -> Result<SnapshotStats, S
                 .dt()
                 .to_string("%Y-%m-%d")
                 .alias("max_date"),
-            col("ext_fs_id").max().alias("num_games"),
+            col("ext_fs_id")
+                .n_unique()
+                .cast(DataType::UInt64)
+                .alias("num_games"),
+            col("title")
+                .n_unique()
+                .cast(DataType::UInt32)
+                .alias("num_maps"),
+            col("cache_handles")
+                .n_unique()
+                .cast(DataType::UInt32)
+                .alias("num_map_versions"),
+            as_struct(vec![
+                col("player_toon_region"),
+                col("player_toon_realm"),
+                col("player_toon_id"),
+            ])
+            .n_unique()
+            .cast(DataType::UInt64)
+            .alias("num_players"),
         ])
         .collect()?;
 
@@ -77,11 +84,15 @@ pub fn try_get_snapshot_metadata(replay_path: String) -> Result<SnapshotStats, S
         .str()?
         .get(0)
         .unwrap_or("1970-01-01");
-    let num_games = res.column("num_games")?.u64()?.get(0).unwrap_or(0) + 1;
+    let num_games = res.column("num_games")?.u64()?.get(0).unwrap_or(0);
+    let num_maps = res.column("num_maps")?.u32()?.get(0).unwrap_or(0);
+    let num_map_versions = res.column("num_map_versions")?.u32()?.get(0).unwrap_or(0);
+    let num_players = res.column("num_players")?.u64()?.get(0).unwrap_or(0);
     let min_date = chrono::NaiveDate::parse_from_str(min_date_str, "%Y-%m-%d")
         .unwrap_or(chrono::NaiveDate::from_ymd_opt(1970, 1, 1).unwrap());
     let max_date = chrono::NaiveDate::parse_from_str(max_date_str, "%Y-%m-%d")
         .unwrap_or(chrono::NaiveDate::from_ymd_opt(1970, 1, 1).unwrap());
+    let num_game_versions = count_game_versions(&snapshot)?;
     // let data_str = crate::common::convert_df_to_json_data(&res)?;
 
     Ok(SnapshotStats {
@@ -90,6 +101,30 @@ pub fn try_get_snapshot_metadata(replay_path: String) -> Result<SnapshotStats, S
         max_date,
         min_date,
         num_games,
-        num_maps: 0,
+        num_maps,
+        num_map_versions,
+        num_players,
+        num_game_versions,
     })
 }
+
+/// Counts the distinct protocol versions in the init_data.ipc file.
+/// Snapshots written before the version column was added report 0.
+fn count_game_versions(snapshot: &Snapshot) -> Result<u32, SwarmyTauriError> {
+    let (Some(init_data_query), Some(schema)) = (
+        snapshot.scan_optional(INIT_DATA_IPC),
+        snapshot.schema(INIT_DATA_IPC),
+    ) else {
+        return Ok(0);
+    };
+    if schema.get("version").is_none() {
+        return Ok(0);
+    }
+    let res = init_data_query
+        .select([col("version")
+            .n_unique()
+            .cast(DataType::UInt32)
+            .alias("num_game_versions")])
+        .collect()?;
+    Ok(res.column("num_game_versions")?.u32()?.get(0).unwrap_or(0))
+}
@@ -0,0 +1,315 @@
+//! Ad-hoc SQL queries over the Arrow IPC snapshot.
+
+use polars::prelude::*;
+use polars::sql::SQLContext;
+use sqlparser::dialect::GenericDialect;
+use sqlparser::tokenizer::{Token, Tokenizer};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::time::Duration;
+use swarmy_tauri_common::*;
+use tauri::Manager;
+use tauri_plugin_store::StoreBuilder;
+
+use crate::common::convert_df_to_json_data;
+use crate::jobs::JobRunner;
+use crate::snapshot_registry::{Snapshot, SnapshotRegistry};
+
+#[tauri::command(rename_all = "snake_case")]
+pub async fn run_sql_query(
+    app_handle: tauri::AppHandle,
+    query: SqlQuery,
+) -> ApiResponse<SqlQueryResult> {
+    let init_time = std::time::Instant::now();
+    let snapshots = app_handle.state::<SnapshotRegistry>().inner().clone();
+    let abandoned_queries = app_handle.state::<AbandonedQueries>().inner().clone();
+    // Only the first page of a query is a new entry in the history.
+    let history_sql = (query.page == 0).then(|| query.sql.clone());
+    // The timeout starts when the job does, not while it waits for the other jobs.
+    let res = app_handle
+        .state::<JobRunner>()
+        .run("run_sql_query", move || {
+            try_run_sql_query(&snapshots, &abandoned_queries, query)
+        })
+        .await;
+    match &res {
+        Ok(_) => {
+            if let Some(sql) = history_sql {
+                save_sql_history(&app_handle, sql);
+            }
+        }
+        Err(e) => log::error!("Error running SQL query: {}", e),
+    }
+    ApiResponse::from_result(res, init_time.elapsed().as_millis() as u64)
+}
+
+/// Puts `sql` first in the history of the queries that ran, without duplicates.
+fn save_sql_history(app_handle: &tauri::AppHandle, sql: String) {
+    match StoreBuilder::new(app_handle, "settings.json").build() {
+        Ok(store) => {
+            // If there are no saved settings yet, this will return an error so we ignore the return value.
+            let _ = store.reload();
+            let mut history: Vec<String> = store
+                .get("sql_history")
+                .and_then(|v| serde_json::from_value(v).ok())
+                .unwrap_or_default();
+            history.retain(|entry| entry != &sql);
+            history.insert(0, sql);
+            history.truncate(SQL_QUERY_HISTORY_SIZE);
+            store.set("sql_history", serde_json::json!(history));
+        }
+        Err(e) => log::error!("Error building store: {}", e),
+    }
+}
+
+/// The Polars SQL table functions, they read any file on disk.
+const SQL_TABLE_FUNCTIONS: [&str; 4] = ["read_csv", "read_ipc", "read_json", "read_parquet"];
+
+/// Plans `sql` with every IPC file of the snapshot registered as a table named after the file,
+/// i.e. `details.ipc` is the `details` table.
+/// Only these tables can be queried, the SQL that calls a table function is refused.
+pub fn sql_frame(snapshot: &Snapshot, sql: &str) -> Result<LazyFrame, SwarmyTauriError> {
+    check_sql_table_functions(sql)?;
+    let mut ctx = SQLContext::new();
+    for (table_name, lf) in snapshot.tables() {
+        ctx.register(table_name, lf);
+    }
+    Ok(ctx.execute(sql)?)
+}
+
+/// Refuses `sql` if it calls one of the [`SQL_TABLE_FUNCTIONS`], in any position and case.
+fn check_sql_table_functions(sql: &str) -> Result<(), SwarmyTauriError> {
+    let tokens = Tokenizer::new(&GenericDialect {}, sql)
+        .tokenize()
+        .map_err(|e| SwarmyTauriError::Other(format!("Invalid SQL: {}", e)))?;
+    // The comments are whitespace tokens too.
+    let mut tokens = tokens
+        .iter()
+        .filter(|token| !matches!(token, Token::Whitespace(_)))
+        .peekable();
+    while let Some(token) = tokens.next() {
+        let Token::Word(word) = token else {
+            continue;
+        };
+        let is_table_function = SQL_TABLE_FUNCTIONS
+            .iter()
+            .any(|function| function.eq_ignore_ascii_case(&word.value));
+        if is_table_function && matches!(tokens.peek(), Some(Token::LParen)) {
+            return Err(SwarmyTauriError::Other(format!(
+                "{} is not allowed, only the tables of the snapshot can be queried",
+                word.value
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Counts the queries that timed out while their computation still runs, Polars cannot stop a
+/// computation once started.
+#[derive(Debug, Default, Clone)]
+pub struct AbandonedQueries {
+    running: Arc<AtomicUsize>,
+}
+
+impl AbandonedQueries {
+    /// Starts tracking a query, refused while [`SQL_QUERY_MAX_ABANDONED`] abandoned queries still
+    /// run.
+    pub fn start(&self) -> Result<(QueryTimeout, QueryComputation), SwarmyTauriError> {
+        let running = self.running.load(Ordering::SeqCst);
+        if running >= SQL_QUERY_MAX_ABANDONED {
+            return Err(SwarmyTauriError::Other(format!(
+                "{} timed out queries are still running, try again later",
+                running
+            )));
+        }
+        let abandoned = Arc::new(AtomicBool::new(false));
+        Ok((
+            QueryTimeout {
+                abandoned: abandoned.clone(),
+                running: self.running.clone(),
+            },
+            QueryComputation {
+                abandoned,
+                running: self.running.clone(),
+            },
+        ))
+    }
+}
+
+/// Held by the caller that waits for a query.
+pub struct QueryTimeout {
+    abandoned: Arc<AtomicBool>,
+    running: Arc<AtomicUsize>,
+}
+
+impl QueryTimeout {
+    /// Stops waiting for the query, it counts as abandoned until its computation ends.
+    pub fn abandon(self) {
+        if !self.abandoned.swap(true, Ordering::SeqCst) {
+            self.running.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+}
+
+/// Moved into the computation of a query, dropped when it ends.
+pub struct QueryComputation {
+    abandoned: Arc<AtomicBool>,
+    running: Arc<AtomicUsize>,
+}
+
+impl Drop for QueryComputation {
+    fn drop(&mut self) {
+        // The flag is already set if the caller abandoned the query.
+        if self.abandoned.swap(true, Ordering::SeqCst) {
+            self.running.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+}
+
+/// Runs the SQL of the query over the snapshot and returns the requested page.
+/// A query that exceeds its timeout is abandoned, its computation finishes in the background and
+/// counts against [`SQL_QUERY_MAX_ABANDONED`] until then.
+pub fn try_run_sql_query(
+    snapshots: &SnapshotRegistry,
+    abandoned_queries: &AbandonedQueries,
+    query: SqlQuery,
+) -> Result<SqlQueryResult, SwarmyTauriError> {
+    let (timeout, computation) = abandoned_queries.start()?;
+    let timeout_ms = sql_query_timeout_ms(&query);
+    let (lf, row_limit) = sql_query_frame(snapshots, &query)?;
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let _computation = computation;
+        // The receiver is gone if the query timed out.
+        let _ = tx.send(lf.collect());
+    });
+    let res = match rx.recv_timeout(Duration::from_millis(timeout_ms)) {
+        Ok(res) => res?,
+        Err(mpsc::RecvTimeoutError::Timeout) => {
+            timeout.abandon();
+            return Err(query_timed_out(timeout_ms));
+        }
+        Err(mpsc::RecvTimeoutError::Disconnected) => {
+            return Err(SwarmyTauriError::Other(String::from(
+                "Query stopped before returning a result",
+            )));
+        }
+    };
+    sql_query_page(res, row_limit, &query)
+}
+
+/// Plans the SQL of the query over the snapshot, along with the row limit it is computed with.
+/// One extra row is computed to tell whether the result was cut at the limit.
+pub fn sql_query_frame(
+    snapshots: &SnapshotRegistry,
+    query: &SqlQuery,
+) -> Result<(LazyFrame, usize), SwarmyTauriError> {
+    let snapshot = snapshots.open(&query.replay_paths)?;
+    log::info!(
+        "Running SQL query over: {}: {}",
+        snapshot.ipc_path().display(),
+        query.sql
+    );
+    let row_limit = if query.row_limit == 0 {
+        SQL_QUERY_DEFAULT_ROW_LIMIT
+    } else {
+        query.row_limit
+    };
+    let lf = sql_frame(&snapshot, &query.sql)?.limit(row_limit as IdxSize + 1);
+    Ok((lf, row_limit))
+}
+
+fn sql_query_timeout_ms(query: &SqlQuery) -> u64 {
+    if query.timeout_ms == 0 {
+        SQL_QUERY_DEFAULT_TIMEOUT_MS
+    } else {
+        query.timeout_ms
+    }
+}
+
+fn query_timed_out(timeout_ms: u64) -> SwarmyTauriError {
+    SwarmyTauriError::Other(format!("Query timed out after {} ms", timeout_ms))
+}
+
+/// Returns the requested page of the computed rows `res`.
+pub fn sql_query_page(
+    mut res: DataFrame,
+    row_limit: usize,
+    query: &SqlQuery,
+) -> Result<SqlQueryResult, SwarmyTauriError> {
+    let truncated = res.height() > row_limit;
+    if truncated {
+        res = res.slice(0, row_limit);
+    }
+    let total = res.height();
+    let per_page = if query.per_page == 0 {
+        SQL_QUERY_DEFAULT_PER_PAGE
+    } else {
+        query.per_page
+    };
+    let start = query.page.saturating_mul(per_page).min(total);
+    let end = start.saturating_add(per_page).min(total);
+    let page_df = res.slice(start as i64, end - start);
+
+    let columns: Vec<String> = page_df
+        .get_column_names()
+        .iter()
+        .map(|name| name.to_string())
+        .collect();
+    let dtypes = page_df
+        .dtypes()
+        .iter()
+        .map(|dtype| dtype.to_string())
+        .collect();
+    // The JSON writer outputs one object per row, the values are returned in column order.
+    let json_rows: Vec<serde_json::Map<String, serde_json::Value>> =
+        serde_json::from_str(&convert_df_to_json_data(&page_df)?)?;
+    let rows = json_rows
+        .into_iter()
+        .map(|mut row| {
+            columns
+                .iter()
+                .map(|column| row.remove(column).unwrap_or(serde_json::Value::Null))
+                .collect()
+        })
+        .collect();
+
+    Ok(SqlQueryResult {
+        columns,
+        dtypes,
+        rows,
+        total,
+        truncated,
+        start,
+        end,
+        page: query.page,
+        per_page,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn table_functions_are_refused() {
+        for sql in [
+            "SELECT * FROM read_csv('/etc/passwd')",
+            "SELECT * FROM READ_PARQUET ('a.parquet')",
+            "SELECT * FROM details JOIN \"read_json\"('a.json') USING (ext_fs_id)",
+            "SELECT * FROM read_ipc -- comment\n('a.ipc')",
+        ] {
+            assert!(check_sql_table_functions(sql).is_err(), "{}", sql);
+        }
+    }
+
+    #[test]
+    fn snapshot_tables_are_allowed() {
+        for sql in [
+            "SELECT title, COUNT(*) FROM details GROUP BY title",
+            "SELECT 'read_csv(x)' AS read_csv FROM stats",
+        ] {
+            assert!(check_sql_table_functions(sql).is_ok(), "{}", sql);
+        }
+    }
+}
@@ -1,14 +1,57 @@
 //! Swarmy Tauri Application
 
 use leptos::ev::MouseEvent;
+use leptos::leptos_dom::logging::console_log;
 use leptos::prelude::*;
-use phosphor_leptos::{Icon, IconData, IconWeight, BARCODE, HOUSE, MAP_TRIFOLD};
+use leptos::task::spawn_local;
+use phosphor_leptos::{
+    Icon, IconData, IconWeight, BARCODE, CHART_LINE, GIT_COMMIT, HOURGLASS, HOUSE, LIST_CHECKS,
+    MAP_TRIFOLD, SWORD, TERMINAL, USER,
+};
+use swarmy_tauri_common::{SnapshotUpdated, SNAPSHOT_UPDATED_EVENT};
+use swarmy_tauri_ui::build_order::view::BuildOrderPage;
+use swarmy_tauri_ui::economy::view::EconomyPage;
+use swarmy_tauri_ui::jobs::view::JobsPage;
+use swarmy_tauri_ui::library::view::LibrarySwitcher;
+use swarmy_tauri_ui::map_stats::head_to_head::HeadToHeadReport;
+use swarmy_tauri_ui::map_stats::versions::MapVersionsReport;
 use swarmy_tauri_ui::map_stats::view::StatsByMap;
+use swarmy_tauri_ui::player_profile::view::PlayerProfilePage;
 use swarmy_tauri_ui::scan::view::ScanDirectory;
+use swarmy_tauri_ui::sql_query::view::QueryConsole;
+use swarmy_tauri_ui::{listen_event, Navigation};
 
 #[component]
 pub fn Main() -> impl IntoView {
     let (active_page, set_active_page) = signal("Stats By Map".to_string());
+    let navigation = Navigation {
+        set_active_page,
+        profile_player: RwSignal::new(String::new()),
+        build_order_comparison: RwSignal::new(Default::default()),
+        map_versions_title: RwSignal::new(String::new()),
+        economy_game: RwSignal::new(0),
+        library_selection: RwSignal::new(Default::default()),
+        snapshot_updated: RwSignal::new(None),
+    };
+    provide_context(navigation);
+    let unlisten_snapshot_updated = StoredValue::new_local(None::<js_sys::Function>);
+
+    spawn_local(async move {
+        let unlisten = listen_event(SNAPSHOT_UPDATED_EVENT, move |update: SnapshotUpdated| {
+            console_log(&format!(
+                "Snapshot of {} updated with {} replays",
+                update.replay_path, update.replays
+            ));
+            navigation.snapshot_updated.set(Some(update));
+        })
+        .await;
+        unlisten_snapshot_updated.set_value(Some(unlisten));
+    });
+    on_cleanup(move || {
+        if let Some(unlisten) = unlisten_snapshot_updated.get_value() {
+            let _ = unlisten.call0(&wasm_bindgen::JsValue::NULL);
+        }
+    });
 
     view! {
         <div class="flex flex-row h-screen bg-gray-800">
@@ -48,6 +91,44 @@ pub fn Main() -> impl IntoView {
                         active_page=active_page
                         set_active_page=set_active_page
                     />
+                    <SidebarMenuItem
+                        name="Map Versions"
+                        active_page=active_page
+                        set_active_page=set_active_page
+                    />
+                    <SidebarMenuItem
+                        name="Player"
+                        active_page=active_page
+                        set_active_page=set_active_page
+                    />
+                    <SidebarMenuItem
+                        name="Head To Head"
+                        active_page=active_page
+                        set_active_page=set_active_page
+                    />
+                    <SidebarMenuItem
+                        name="Build Order"
+                        active_page=active_page
+                        set_active_page=set_active_page
+                    />
+                    <SidebarMenuItem
+                        name="Economy"
+                        active_page=active_page
+                        set_active_page=set_active_page
+                    />
+                    <SidebarMenuItem
+                        name="Query"
+                        active_page=active_page
+                        set_active_page=set_active_page
+                    />
+                    <SidebarMenuItem
+                        name="Jobs"
+                        active_page=active_page
+                        set_active_page=set_active_page
+                    />
+                </div>
+                <div class="flex flex-col items-center mt-2 border-t border-purple-700">
+                    <LibrarySwitcher />
                 </div>
                 <div class="flex flex-col items-center mt-2 border-t border-purple-700">
                     <a
@@ -91,16 +172,58 @@ pub fn Main() -> impl IntoView {
                     </a>
                 </div>
             </div>
-            <div class="flex-grow p-2 overflow-auto">
-                <Show when=move || active_page.get() == "Scan">
-                    <ScanDirectory />
-                </Show>
-            </div>
-            <div class="flex-grow p-2 overflow-auto">
-                <Show when=move || active_page.get() == "Stats By Map">
-                    <StatsByMap />
-                </Show>
-            </div>
+            // The pages load the libraries they query when mounted, they are mounted again when
+            // another library is selected. The replay watcher updates only make them fetch again.
+            {move || {
+                navigation.library_selection.track();
+                view! {
+                    <div class="flex-grow p-2 overflow-auto">
+                        <Show when=move || active_page.get() == "Scan">
+                            <ScanDirectory />
+                        </Show>
+                    </div>
+                    <div class="flex-grow p-2 overflow-auto">
+                        <Show when=move || active_page.get() == "Stats By Map">
+                            <StatsByMap />
+                        </Show>
+                    </div>
+                    <div class="flex-grow p-2 overflow-auto">
+                        <Show when=move || active_page.get() == "Map Versions">
+                            <MapVersionsReport />
+                        </Show>
+                    </div>
+                    <div class="flex-grow p-2 overflow-auto">
+                        <Show when=move || active_page.get() == "Player">
+                            <PlayerProfilePage />
+                        </Show>
+                    </div>
+                    <div class="flex-grow p-2 overflow-auto">
+                        <Show when=move || active_page.get() == "Head To Head">
+                            <HeadToHeadReport />
+                        </Show>
+                    </div>
+                    <div class="flex-grow p-2 overflow-auto">
+                        <Show when=move || active_page.get() == "Build Order">
+                            <BuildOrderPage />
+                        </Show>
+                    </div>
+                    <div class="flex-grow p-2 overflow-auto">
+                        <Show when=move || active_page.get() == "Economy">
+                            <EconomyPage />
+                        </Show>
+                    </div>
+                    <div class="flex-grow p-2 overflow-auto">
+                        <Show when=move || active_page.get() == "Query">
+                            <QueryConsole />
+                        </Show>
+                    </div>
+                    <div class="flex-grow p-2 overflow-auto">
+                        <Show when=move || active_page.get() == "Jobs">
+                            <JobsPage />
+                        </Show>
+                    </div>
+                }
+            }}
         </div>
     }
 }
@@ -117,10 +240,24 @@ fn SidebarMenuItem(
     let house_icon_data: IconData = HOUSE;
     let barcode_icon_data: IconData = BARCODE;
     let map_trifold_icon_data: IconData = MAP_TRIFOLD;
+    let terminal_icon_data: IconData = TERMINAL;
+    let user_icon_data: IconData = USER;
+    let sword_icon_data: IconData = SWORD;
+    let list_checks_icon_data: IconData = LIST_CHECKS;
+    let git_commit_icon_data: IconData = GIT_COMMIT;
+    let chart_line_icon_data: IconData = CHART_LINE;
+    let hourglass_icon_data: IconData = HOURGLASS;
     let icon_data = match name {
         "Home" => house_icon_data,
         "Scan" => barcode_icon_data,
         "Stats By Map" => map_trifold_icon_data,
+        "Map Versions" => git_commit_icon_data,
+        "Player" => user_icon_data,
+        "Head To Head" => sword_icon_data,
+        "Build Order" => list_checks_icon_data,
+        "Economy" => chart_line_icon_data,
+        "Query" => terminal_icon_data,
+        "Jobs" => hourglass_icon_data,
         _ => house_icon_data,
     };
 
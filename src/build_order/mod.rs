@@ -0,0 +1,3 @@
+//! Build order module.
+
+pub mod view;
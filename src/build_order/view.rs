@@ -0,0 +1,469 @@
+//! Leptos view for build orders and their comparison.
+use crate::*;
+use leptos::leptos_dom::logging::console_log;
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use phosphor_leptos::{Icon, IconWeight, ARROWS_CLOCKWISE, LIST_CHECKS, X_CIRCLE};
+use serde::{Deserialize, Serialize};
+use swarmy_tauri_common::*;
+
+/// The arguments of the compare_build_orders command.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CompareBuildOrdersArgs {
+    query: BuildOrderComparisonQuery,
+}
+
+/// The arguments of the query_build_order command.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct QueryBuildOrderArgs {
+    query: BuildOrderQuery,
+}
+
+/// Fetches the build order of A, and compares it with B when B has a player.
+fn trigger_fetch_build_orders(
+    data: WriteSignal<BuildOrderComparison>,
+    query: BuildOrderComparisonQuery,
+    backend_response: WriteSignal<ResponseStatus>,
+) {
+    if query.a.replay_paths.is_empty() || query.a.player_name.is_empty() {
+        console_log("Replay path or player is empty.");
+        return;
+    }
+    spawn_local(async move {
+        console_log(&format!(
+            "Invoking build order commands with query: {:?}",
+            query
+        ));
+        let res = if query.b.player_name.is_empty() {
+            let res = invoke_command::<_, BuildOrder>(
+                "query_build_order",
+                &QueryBuildOrderArgs { query: query.a },
+            )
+            .await;
+            backend_response.set(ResponseStatus::from(&res));
+            res.into_result().map(|a| BuildOrderComparison {
+                a,
+                ..Default::default()
+            })
+        } else {
+            let res = invoke_command::<_, BuildOrderComparison>(
+                "compare_build_orders",
+                &CompareBuildOrdersArgs { query },
+            )
+            .await;
+            backend_response.set(ResponseStatus::from(&res));
+            res.into_result()
+        };
+        match res {
+            Ok(res) => data.set(res),
+            Err(e) => {
+                console_log(&format!("Error fetching build orders: {}", e));
+                data.set(BuildOrderComparison::default());
+            }
+        }
+    });
+}
+
+#[component]
+pub fn BuildOrderPage() -> impl IntoView {
+    let navigation = expect_context::<Navigation>();
+    let (query, set_query) = signal(navigation.build_order_comparison.get_untracked());
+    let (comparison, set_comparison) = signal(BuildOrderComparison::default());
+    let (backend_response, set_backend_response) = signal(ResponseStatus::incomplete());
+    // The build orders shown, the inputs may have changed since.
+    let loaded = StoredValue::new(None::<BuildOrderComparisonQuery>);
+    let load = move |query: BuildOrderComparisonQuery| {
+        loaded.set_value(Some(query.clone()));
+        trigger_fetch_build_orders(set_comparison, query, set_backend_response);
+    };
+
+    spawn_local(async move {
+        // The snapshot location is part of the app settings.
+        match invoke_command_without_args::<AppSettings>("get_current_app_config")
+            .await
+            .into_result()
+        {
+            Ok(config) => set_query.update(|query| {
+                query.a.replay_paths = config.query_replay_paths.clone();
+                query.b.replay_paths = config.query_replay_paths;
+            }),
+            Err(e) => {
+                console_log(&format!("Error invoking get_current_app_config: {:?}", e));
+            }
+        }
+    });
+    // Fetches the build orders whenever they are opened from another page.
+    Effect::new(move |_| {
+        let opened = navigation.build_order_comparison.get();
+        set_query.set(opened.clone());
+        load(opened);
+    });
+    navigation.on_snapshot_updated(
+        move || query.get_untracked().a.replay_paths,
+        move || {
+            if let Some(query) = loaded.get_value() {
+                load(query);
+            }
+        },
+    );
+
+    view! {
+        <BuildLabelsPanel />
+        <div class="grid grid-cols-8 gap-1">
+            <BuildOrderQueryInputs
+                label="A"
+                query=Signal::derive(move || query.get().a)
+                on_change=Callback::new(move |a| set_query.update(|query| query.a = a))
+            />
+            <BuildOrderQueryInputs
+                label="B"
+                query=Signal::derive(move || query.get().b)
+                on_change=Callback::new(move |b| set_query.update(|query| query.b = b))
+            />
+            <div class="col-span-8 flex items-center gap-2">
+                <label class="input input-sm">
+                    <span class="label">"Until loop"</span>
+                    <input
+                        type="number"
+                        min="0"
+                        class="my-0 mx-0 w-24"
+                        placeholder=BUILD_ORDER_DEFAULT_MAX_LOOP.to_string()
+                        prop:value=move || query.get().a.max_loop
+                        on:change=move |ev| {
+                            let max_loop = event_target_value(&ev).parse().unwrap_or(0);
+                            set_query
+                                .update(|query| {
+                                    query.a.max_loop = max_loop;
+                                    query.b.max_loop = max_loop;
+                                });
+                        }
+                    />
+                </label>
+                <button
+                    class="btn btn-sm btn-primary"
+                    on:click=move |_| load(query.get_untracked())
+                >
+                    <Icon icon=LIST_CHECKS weight=IconWeight::Bold prop:class="stroke-current" />
+                    "Load"
+                </button>
+            </div>
+            <Show when=move || {
+                !backend_response.get().meta.success && backend_response.get().meta.is_complete
+            }>
+                <div role="alert" class="alert alert-error shadow-lg m-1 p-1 col-span-8">
+                    <Icon icon=X_CIRCLE weight=IconWeight::Bold prop:class="stroke-current" />
+                    <span>{backend_response.get().message.clone()}</span>
+                </div>
+            </Show>
+            <Show when=move || {
+                !comparison.get().a.steps.is_empty() && comparison.get().b.steps.is_empty()
+            }>
+                <div class="col-span-8">
+                    <BuildOrderTable build_order=Signal::derive(move || comparison.get().a) />
+                </div>
+            </Show>
+            <Show when=move || {
+                !comparison.get().a.steps.is_empty() && !comparison.get().b.steps.is_empty()
+            }>
+                <div class="col-span-8">
+                    <BuildOrderComparisonTable comparison />
+                </div>
+            </Show>
+        </div>
+    }
+}
+
+#[component]
+fn BuildOrderQueryInputs(
+    label: &'static str,
+    query: Signal<BuildOrderQuery>,
+    on_change: Callback<BuildOrderQuery>,
+) -> impl IntoView {
+    view! {
+        <div class="col-span-2">
+            <label class="input input-sm w-full">
+                <span class="label">{format!("Game {}", label)}</span>
+                <input
+                    type="number"
+                    min="0"
+                    class="my-0 mx-0"
+                    prop:value=move || query.get().ext_fs_id
+                    on:change=move |ev| {
+                        let mut changed = query.get_untracked();
+                        changed.ext_fs_id = event_target_value(&ev).parse().unwrap_or(0);
+                        on_change.run(changed);
+                    }
+                />
+            </label>
+        </div>
+        <div class="col-span-2">
+            <label class="input input-sm w-full">
+                <span class="label">{format!("Player {}", label)}</span>
+                <input
+                    type="text"
+                    class="my-0 mx-0"
+                    prop:value=move || query.get().player_name
+                    on:change=move |ev| {
+                        let mut changed = query.get_untracked();
+                        changed.player_name = event_target_value(&ev);
+                        on_change.run(changed);
+                    }
+                />
+            </label>
+        </div>
+    }
+}
+
+#[component]
+pub fn BuildOrderTable(build_order: Signal<BuildOrder>) -> impl IntoView {
+    view! {
+        <h3 class="text-neutral-content bg-gray-800">
+            {move || split_clan_tag(&build_order.get().player_name).1}
+        </h3>
+        <table class="table bg-gray-500 table-xs table-zebra rounded-box">
+            <thead class="bg-gray-700">
+                <tr>
+                    <th></th>
+                    <th>Time</th>
+                    <th>Supply</th>
+                    <th>Unit</th>
+                </tr>
+            </thead>
+            <tbody>
+                {move || {
+                    build_order
+                        .get()
+                        .steps
+                        .into_iter()
+                        .enumerate()
+                        .map(|(idx, step)| {
+                            view! {
+                                <tr>
+                                    <th>{idx + 1}</th>
+                                    <td>{format_game_seconds(step.game_seconds)}</td>
+                                    <td>{format!("{}/{}", step.supply_used, step.supply_made)}</td>
+                                    <td>{step.name}</td>
+                                </tr>
+                            }
+                        })
+                        .collect_view()
+                }}
+            </tbody>
+        </table>
+    }
+}
+
+#[component]
+pub fn BuildOrderComparisonTable(comparison: ReadSignal<BuildOrderComparison>) -> impl IntoView {
+    let divergence = move || {
+        let comparison = comparison.get();
+        match comparison.diverged_at {
+            Some(idx) => format!(
+                "Diverged at step {}: {} vs {}",
+                idx + 1,
+                comparison.a.steps[idx].name,
+                comparison.b.steps[idx].name,
+            ),
+            None => String::from("The build orders do not diverge."),
+        }
+    };
+    view! {
+        <div class="text-neutral-content m-1">{divergence}</div>
+        <table class="table bg-gray-500 table-xs table-zebra rounded-box">
+            <thead class="bg-gray-700">
+                <tr>
+                    <th>Unit</th>
+                    <th>{move || split_clan_tag(&comparison.get().a.player_name).1}</th>
+                    <th>{move || split_clan_tag(&comparison.get().b.player_name).1}</th>
+                    <th>Difference</th>
+                </tr>
+            </thead>
+            <tbody>
+                {move || {
+                    comparison
+                        .get()
+                        .rows
+                        .into_iter()
+                        .map(|row| {
+                            let delta = row.delta_seconds();
+                            let delta_class = match delta {
+                                Some(delta) if delta > 0 => "text-success",
+                                Some(delta) if delta < 0 => "text-error",
+                                Some(_) => "",
+                                None => "text-warning",
+                            };
+                            view! {
+                                <tr>
+                                    <td>{format!("{} #{}", row.name, row.occurrence)}</td>
+                                    <td>{row.seconds_a.map(format_game_seconds).unwrap_or_default()}</td>
+                                    <td>{row.seconds_b.map(format_game_seconds).unwrap_or_default()}</td>
+                                    <td class=delta_class>
+                                        {match delta {
+                                            Some(delta) => format!("{:+} s", delta),
+                                            None => String::from("missing"),
+                                        }}
+                                    </td>
+                                </tr>
+                            }
+                        })
+                        .collect_view()
+                }}
+            </tbody>
+        </table>
+    }
+}
+
+/// The arguments of the classify_build_orders and get_build_labels commands.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BuildLabelsArgs {
+    pub replay_paths: Vec<String>,
+    pub rules_path: String,
+}
+
+/// Invokes `cmd`, one of the build label commands, and sets the summary it returns.
+pub fn trigger_build_labels(
+    cmd: &'static str,
+    summary: WriteSignal<BuildLabelSummary>,
+    args: BuildLabelsArgs,
+    backend_response: WriteSignal<ResponseStatus>,
+) {
+    if args.replay_paths.is_empty() {
+        console_log("Replay path is empty.");
+        return;
+    }
+    spawn_local(async move {
+        console_log(&format!("Invoking {} with args: {:?}", cmd, args));
+        let res = invoke_command::<_, BuildLabelSummary>(cmd, &args).await;
+        backend_response.set(ResponseStatus::from(&res));
+        match res.into_result() {
+            Ok(res) => summary.set(res),
+            Err(e) => console_log(&format!("Error invoking {}: {}", cmd, e)),
+        }
+    });
+}
+
+/// The opening label rules and how many build orders of the snapshot have each label.
+#[component]
+pub fn BuildLabelsPanel() -> impl IntoView {
+    let navigation = expect_context::<Navigation>();
+    let (replay_paths, set_replay_paths) = signal(Vec::<String>::new());
+    let (rules_path, set_rules_path) = signal(String::new());
+    let (summary, set_summary) = signal(BuildLabelSummary::default());
+    let (backend_response, set_backend_response) = signal(ResponseStatus::default());
+    let build_labels_args = move || BuildLabelsArgs {
+        replay_paths: replay_paths.get_untracked(),
+        rules_path: rules_path.get_untracked(),
+    };
+
+    spawn_local(async move {
+        // The snapshot location and the rules file are part of the app settings.
+        match invoke_command_without_args::<AppSettings>("get_current_app_config")
+            .await
+            .into_result()
+        {
+            Ok(config) => {
+                set_replay_paths.set(config.query_replay_paths);
+                set_rules_path.set(config.build_rules_path);
+                trigger_build_labels(
+                    "get_build_labels",
+                    set_summary,
+                    build_labels_args(),
+                    set_backend_response,
+                );
+            }
+            Err(e) => {
+                console_log(&format!("Error invoking get_current_app_config: {:?}", e));
+            }
+        }
+    });
+    // The optimize job labels the games it adds.
+    navigation.on_snapshot_updated(
+        move || replay_paths.get_untracked(),
+        move || {
+            trigger_build_labels(
+                "get_build_labels",
+                set_summary,
+                build_labels_args(),
+                set_backend_response,
+            )
+        },
+    );
+
+    view! {
+        <div class="collapse collapse-arrow bg-gray-900 m-1">
+            <input type="checkbox" />
+            <div class="collapse-title text-sm text-neutral-content">
+                {move || {
+                    format!(
+                        "Opening labels: {} rules, {} build orders",
+                        summary.get().num_rules,
+                        summary.get().num_build_orders,
+                    )
+                }}
+            </div>
+            <div class="collapse-content">
+                <div class="flex items-center gap-2">
+                    <label class="input input-sm grow" title="TOML or JSON rules file">
+                        <span class="label">"Rules file"</span>
+                        <input
+                            type="text"
+                            class="my-0 mx-0"
+                            placeholder="Built-in rules"
+                            prop:value=move || rules_path.get()
+                            on:change=move |ev| set_rules_path.set(event_target_value(&ev))
+                        />
+                    </label>
+                    <button
+                        class="btn btn-sm btn-primary"
+                        title="Reload the rules and label the snapshot"
+                        on:click=move |_| {
+                            trigger_build_labels(
+                                "classify_build_orders",
+                                set_summary,
+                                build_labels_args(),
+                                set_backend_response,
+                            )
+                        }
+                    >
+                        <Icon
+                            icon=ARROWS_CLOCKWISE
+                            weight=IconWeight::Bold
+                            prop:class="stroke-current"
+                        />
+                        "Reload rules"
+                    </button>
+                </div>
+                <Show when=move || {
+                    !backend_response.get().meta.success && backend_response.get().meta.is_complete
+                }>
+                    <div role="alert" class="alert alert-error shadow-lg m-1 p-1">
+                        <Icon icon=X_CIRCLE weight=IconWeight::Bold prop:class="stroke-current" />
+                        <span>{backend_response.get().message.clone()}</span>
+                    </div>
+                </Show>
+                <table class="table bg-gray-500 table-xs table-zebra rounded-box mt-1">
+                    <thead class="bg-gray-700">
+                        <tr>
+                            <th>Opening</th>
+                            <th>Build Orders</th>
+                        </tr>
+                    </thead>
+                    <tbody>
+                        <For
+                            each=move || summary.get().labels
+                            key=|label| label.label.clone()
+                            children=|label| {
+                                view! {
+                                    <tr>
+                                        <td>{label.label}</td>
+                                        <td>{label.count}</td>
+                                    </tr>
+                                }
+                            }
+                        />
+                    </tbody>
+                </table>
+            </div>
+        </div>
+    }
+}
@@ -0,0 +1,259 @@
+//! SVG line charts of values over game time.
+use crate::format_game_seconds;
+use leptos::prelude::*;
+use swarmy_tauri_common::BenchmarkSeries;
+
+/// The colors of the lines of a chart, in the order of the series.
+pub const CHART_COLORS: [&str; 4] = ["#60a5fa", "#f87171", "#4ade80", "#facc15"];
+
+const CHART_WIDTH: f64 = 600.0;
+const CHART_HEIGHT: f64 = 200.0;
+const CHART_MARGIN: f64 = 32.0;
+
+/// A line of a chart, the points are game seconds and values.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ChartSeries {
+    pub label: String,
+    pub points: Vec<(f64, f64)>,
+}
+
+impl ChartSeries {
+    pub fn new(label: String, points: Vec<(f64, f64)>) -> Self {
+        Self { label, points }
+    }
+}
+
+/// Maps game seconds and values to the coordinates of the chart area.
+#[derive(Debug, Clone, Copy)]
+struct ChartScale {
+    max_x: f64,
+    max_y: f64,
+}
+
+impl ChartScale {
+    fn new<'a>(points: impl Iterator<Item = &'a (f64, f64)>) -> Self {
+        let (max_x, max_y) = points.fold((0.0_f64, 0.0_f64), |(max_x, max_y), (x, y)| {
+            (max_x.max(*x), max_y.max(*y))
+        });
+        Self {
+            max_x: max_x.max(1.0),
+            max_y: max_y.max(1.0),
+        }
+    }
+
+    fn x(&self, x: f64) -> f64 {
+        CHART_MARGIN + x / self.max_x * (CHART_WIDTH - 2.0 * CHART_MARGIN)
+    }
+
+    fn y(&self, y: f64) -> f64 {
+        CHART_HEIGHT - CHART_MARGIN + y.max(0.0) / self.max_y * (2.0 * CHART_MARGIN - CHART_HEIGHT)
+    }
+
+    fn path(&self, points: &[(f64, f64)]) -> String {
+        points
+            .iter()
+            .enumerate()
+            .map(|(idx, (x, y))| {
+                let command = if idx == 0 { 'M' } else { 'L' };
+                format!("{}{:.1},{:.1}", command, self.x(*x), self.y(*y))
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// Draws each series as a line, with the game time on the x axis.
+#[component]
+pub fn LineChart(title: &'static str, series: Signal<Vec<ChartSeries>>) -> impl IntoView {
+    view! {
+        <div class="bg-gray-700 rounded-box p-1">
+            <h3 class="text-neutral-content flex justify-center">{title}</h3>
+            {move || {
+                let series = series.get();
+                let scale = ChartScale::new(series.iter().flat_map(|series| series.points.iter()));
+                let ticks = (0..=4)
+                    .map(|tick| {
+                        let seconds = scale.max_x * tick as f64 / 4.0;
+                        view! {
+                            <text
+                                x=scale.x(seconds)
+                                y=CHART_HEIGHT - CHART_MARGIN / 3.0
+                                text-anchor="middle"
+                                font-size="10"
+                                fill="#d1d5db"
+                            >
+                                {format_game_seconds(seconds as u32)}
+                            </text>
+                        }
+                    })
+                    .collect_view();
+                let lines = series
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, series)| {
+                        view! {
+                            <path
+                                d=scale.path(&series.points)
+                                fill="none"
+                                stroke=CHART_COLORS[idx % CHART_COLORS.len()]
+                                stroke-width="1.5"
+                            >
+                                <title>{series.label.clone()}</title>
+                            </path>
+                        }
+                    })
+                    .collect_view();
+                let legend = series
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, series)| {
+                        view! {
+                            <span
+                                class="text-xs mx-2"
+                                style=format!("color: {}", CHART_COLORS[idx % CHART_COLORS.len()])
+                            >
+                                {series.label.clone()}
+                            </span>
+                        }
+                    })
+                    .collect_view();
+                view! {
+                    <svg
+                        viewBox=format!("0 0 {} {}", CHART_WIDTH, CHART_HEIGHT)
+                        class="w-full"
+                        xmlns="http://www.w3.org/2000/svg"
+                    >
+                        <line
+                            x1=CHART_MARGIN
+                            y1=CHART_HEIGHT - CHART_MARGIN
+                            x2=CHART_WIDTH - CHART_MARGIN
+                            y2=CHART_HEIGHT - CHART_MARGIN
+                            stroke="#9ca3af"
+                        />
+                        <line
+                            x1=CHART_MARGIN
+                            y1=CHART_MARGIN
+                            x2=CHART_MARGIN
+                            y2=CHART_HEIGHT - CHART_MARGIN
+                            stroke="#9ca3af"
+                        />
+                        <text
+                            x=CHART_MARGIN - 4.0
+                            y=CHART_MARGIN
+                            text-anchor="end"
+                            font-size="10"
+                            fill="#d1d5db"
+                        >
+                            {format!("{:.0}", scale.max_y)}
+                        </text>
+                        {ticks}
+                        {lines}
+                    </svg>
+                    <div class="flex justify-center">{legend}</div>
+                }
+            }}
+        </div>
+    }
+}
+
+/// Draws the value of a game over the median and interquartile band of its benchmark games.
+#[component]
+pub fn BenchmarkChart(series: Signal<BenchmarkSeries>) -> impl IntoView {
+    view! {
+        <div class="bg-gray-700 rounded-box p-1">
+            <h3 class="text-neutral-content flex justify-center">
+                {move || {
+                    let series = series.get();
+                    format!(
+                        "{} against {} games in {}",
+                        series.metric,
+                        series.num_games,
+                        series.description,
+                    )
+                }}
+            </h3>
+            {move || {
+                let series = series.get();
+                let points: Vec<(f64, f64)> = series
+                    .points
+                    .iter()
+                    .flat_map(|point| {
+                        let x = point.game_seconds as f64;
+                        [(x, point.q3), (x, point.value.unwrap_or(0.0))]
+                    })
+                    .collect();
+                let scale = ChartScale::new(points.iter());
+                let q1: Vec<(f64, f64)> = series
+                    .points
+                    .iter()
+                    .map(|point| (point.game_seconds as f64, point.q1))
+                    .collect();
+                let q3: Vec<(f64, f64)> = series
+                    .points
+                    .iter()
+                    .rev()
+                    .map(|point| (point.game_seconds as f64, point.q3))
+                    .collect();
+                let band = format!("{} {} Z", scale.path(&q1), scale.path(&q3).replacen('M', "L", 1));
+                let median: Vec<(f64, f64)> = series
+                    .points
+                    .iter()
+                    .map(|point| (point.game_seconds as f64, point.median))
+                    .collect();
+                let game: Vec<(f64, f64)> = series
+                    .points
+                    .iter()
+                    .filter_map(|point| point.value.map(|value| (point.game_seconds as f64, value)))
+                    .collect();
+                view! {
+                    <svg
+                        viewBox=format!("0 0 {} {}", CHART_WIDTH, CHART_HEIGHT)
+                        class="w-full"
+                        xmlns="http://www.w3.org/2000/svg"
+                    >
+                        <line
+                            x1=CHART_MARGIN
+                            y1=CHART_HEIGHT - CHART_MARGIN
+                            x2=CHART_WIDTH - CHART_MARGIN
+                            y2=CHART_HEIGHT - CHART_MARGIN
+                            stroke="#9ca3af"
+                        />
+                        <text
+                            x=CHART_MARGIN - 4.0
+                            y=CHART_MARGIN
+                            text-anchor="end"
+                            font-size="10"
+                            fill="#d1d5db"
+                        >
+                            {format!("{:.0}", scale.max_y)}
+                        </text>
+                        <text
+                            x=CHART_WIDTH - CHART_MARGIN
+                            y=CHART_HEIGHT - CHART_MARGIN / 3.0
+                            text-anchor="end"
+                            font-size="10"
+                            fill="#d1d5db"
+                        >
+                            {format_game_seconds(scale.max_x as u32)}
+                        </text>
+                        <path d=band fill="#9ca3af" fill-opacity="0.3" stroke="none">
+                            <title>"25th to 75th percentile"</title>
+                        </path>
+                        <path
+                            d=scale.path(&median)
+                            fill="none"
+                            stroke="#d1d5db"
+                            stroke-width="1"
+                            stroke-dasharray="4 2"
+                        >
+                            <title>"Median"</title>
+                        </path>
+                        <path d=scale.path(&game) fill="none" stroke=CHART_COLORS[0] stroke-width="1.5">
+                            <title>"This game"</title>
+                        </path>
+                    </svg>
+                }
+            }}
+        </div>
+    }
+}
@@ -0,0 +1,4 @@
+//! Economy module.
+
+pub mod chart;
+pub mod view;
@@ -0,0 +1,315 @@
+//! Leptos view for the economy timeline of a game.
+use crate::economy::chart::{BenchmarkChart, ChartSeries, LineChart};
+use crate::*;
+use leptos::leptos_dom::logging::console_log;
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use phosphor_leptos::{Icon, IconWeight, CHART_LINE, CHART_LINE_UP, X_CIRCLE};
+use serde::{Deserialize, Serialize};
+use swarmy_tauri_common::*;
+
+/// The arguments of the query_economy_timeline command.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct QueryEconomyTimelineArgs {
+    query: EconomyQuery,
+}
+
+fn trigger_fetch_economy_timeline(
+    timeline: WriteSignal<EconomyTimeline>,
+    query: EconomyQuery,
+    backend_response: WriteSignal<ResponseStatus>,
+) {
+    if query.replay_paths.is_empty() {
+        console_log("Replay path is empty.");
+        return;
+    }
+    spawn_local(async move {
+        console_log(&format!(
+            "Invoking query_economy_timeline with query: {:?}",
+            query
+        ));
+        let res = invoke_command::<_, EconomyTimeline>(
+            "query_economy_timeline",
+            &QueryEconomyTimelineArgs { query },
+        )
+        .await;
+        backend_response.set(ResponseStatus::from(&res));
+        match res.into_result() {
+            Ok(res) => timeline.set(res),
+            Err(e) => {
+                console_log(&format!("Error invoking query_economy_timeline: {}", e));
+                timeline.set(EconomyTimeline::default());
+            }
+        }
+    });
+}
+
+/// The arguments of the query_benchmark command.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct QueryBenchmarkArgs {
+    query: BenchmarkQuery,
+}
+
+fn trigger_fetch_benchmark(
+    benchmark: WriteSignal<Benchmark>,
+    query: BenchmarkQuery,
+    backend_response: WriteSignal<ResponseStatus>,
+) {
+    if query.replay_paths.is_empty() || query.player_name.is_empty() {
+        console_log("Replay path or player is empty.");
+        return;
+    }
+    spawn_local(async move {
+        console_log(&format!("Invoking query_benchmark with query: {:?}", query));
+        let res =
+            invoke_command::<_, Benchmark>("query_benchmark", &QueryBenchmarkArgs { query }).await;
+        backend_response.set(ResponseStatus::from(&res));
+        match res.into_result() {
+            Ok(res) => benchmark.set(res),
+            Err(e) => {
+                console_log(&format!("Error invoking query_benchmark: {}", e));
+                benchmark.set(Benchmark::default());
+            }
+        }
+    });
+}
+
+/// One series per player of a value of the stats samples.
+fn player_series(
+    timeline: ReadSignal<EconomyTimeline>,
+    value: fn(&EconomySample) -> i32,
+) -> Signal<Vec<ChartSeries>> {
+    Signal::derive(move || {
+        timeline
+            .get()
+            .players
+            .iter()
+            .map(|player| {
+                ChartSeries::new(
+                    split_clan_tag(&player.player_name).1,
+                    player
+                        .samples
+                        .iter()
+                        .map(|sample| (sample.game_seconds as f64, value(sample) as f64))
+                        .collect(),
+                )
+            })
+            .collect()
+    })
+}
+
+#[component]
+pub fn EconomyPage() -> impl IntoView {
+    let navigation = expect_context::<Navigation>();
+    let (replay_paths, set_replay_paths) = signal(Vec::<String>::new());
+    let (game_input, set_game_input) = signal(navigation.economy_game.get_untracked());
+    let (timeline, set_timeline) = signal(EconomyTimeline::default());
+    let (backend_response, set_backend_response) = signal(ResponseStatus::incomplete());
+
+    spawn_local(async move {
+        // The snapshot location is part of the app settings.
+        match invoke_command_without_args::<AppSettings>("get_current_app_config")
+            .await
+            .into_result()
+        {
+            Ok(config) => set_replay_paths.set(config.query_replay_paths),
+            Err(e) => {
+                console_log(&format!("Error invoking get_current_app_config: {:?}", e));
+            }
+        }
+    });
+    navigation.on_snapshot_updated(
+        move || replay_paths.get_untracked(),
+        move || {
+            trigger_fetch_economy_timeline(
+                set_timeline,
+                EconomyQuery {
+                    replay_paths: replay_paths.get_untracked(),
+                    ext_fs_id: navigation.economy_game.get_untracked(),
+                },
+                set_backend_response,
+            )
+        },
+    );
+    // Fetches the timeline whenever another game is opened, from this page or any other.
+    Effect::new(move |_| {
+        let ext_fs_id = navigation.economy_game.get();
+        set_game_input.set(ext_fs_id);
+        trigger_fetch_economy_timeline(
+            set_timeline,
+            EconomyQuery {
+                replay_paths: replay_paths.get(),
+                ext_fs_id,
+            },
+            set_backend_response,
+        );
+    });
+
+    view! {
+        <div class="grid grid-cols-8 gap-1">
+            <div class="col-span-2">
+                <label class="input input-sm w-full">
+                    <span class="label">"Game"</span>
+                    <input
+                        type="number"
+                        min="0"
+                        class="my-0 mx-0"
+                        prop:value=move || game_input.get()
+                        on:change=move |ev| {
+                            set_game_input.set(event_target_value(&ev).parse().unwrap_or(0))
+                        }
+                    />
+                </label>
+            </div>
+            <div class="col-span-6">
+                <button
+                    class="btn btn-sm btn-primary"
+                    on:click=move |_| navigation.economy_game.set(game_input.get_untracked())
+                >
+                    <Icon icon=CHART_LINE weight=IconWeight::Bold prop:class="stroke-current" />
+                    "Load"
+                </button>
+            </div>
+            <Show when=move || {
+                !backend_response.get().meta.success && backend_response.get().meta.is_complete
+            }>
+                <div role="alert" class="alert alert-error shadow-lg m-1 p-1 col-span-8">
+                    <Icon icon=X_CIRCLE weight=IconWeight::Bold prop:class="stroke-current" />
+                    <span>{backend_response.get().message.clone()}</span>
+                </div>
+            </Show>
+            <Show when=move || { !timeline.get().players.is_empty() }>
+                <div class="col-span-8 grid grid-cols-2 gap-2">
+                    <LineChart
+                        title="Minerals Collection Rate"
+                        series=player_series(timeline, |sample| sample.minerals_collection_rate)
+                    />
+                    <LineChart
+                        title="Vespene Collection Rate"
+                        series=player_series(timeline, |sample| sample.vespene_collection_rate)
+                    />
+                    <LineChart
+                        title="Workers"
+                        series=player_series(timeline, |sample| sample.workers)
+                    />
+                    <LineChart
+                        title="Army Value"
+                        series=player_series(timeline, |sample| sample.army_value)
+                    />
+                    <LineChart
+                        title="Supply Used"
+                        series=player_series(timeline, |sample| sample.supply_used)
+                    />
+                    <LineChart
+                        title="Supply Cap"
+                        series=player_series(timeline, |sample| sample.supply_made)
+                    />
+                </div>
+                <div class="col-span-8">
+                    <BenchmarkPanel replay_paths timeline />
+                </div>
+            </Show>
+        </div>
+    }
+}
+
+/// Compares a player of the game against their games in the same matchup or the games on the
+/// same map.
+#[component]
+fn BenchmarkPanel(
+    replay_paths: ReadSignal<Vec<String>>,
+    timeline: ReadSignal<EconomyTimeline>,
+) -> impl IntoView {
+    let (player_name, set_player_name) = signal(String::new());
+    let (scope, set_scope) = signal(BenchmarkScope::default());
+    let (benchmark, set_benchmark) = signal(Benchmark::default());
+    let (backend_response, set_backend_response) = signal(ResponseStatus::incomplete());
+    // Another game clears the benchmark of the previous one, the same game fetched again after
+    // a snapshot update keeps it.
+    Effect::new(move |previous_game: Option<u64>| {
+        let timeline = timeline.get();
+        if previous_game != Some(timeline.ext_fs_id) {
+            set_player_name.set(
+                timeline
+                    .players
+                    .first()
+                    .map(|player| player.player_name.clone())
+                    .unwrap_or_default(),
+            );
+            set_benchmark.set(Benchmark::default());
+        }
+        timeline.ext_fs_id
+    });
+
+    view! {
+        <div class="flex items-center gap-2 mt-2">
+            <label class="select select-sm">
+                <span class="label">"Player"</span>
+                <select on:change=move |ev| set_player_name.set(event_target_value(&ev))>
+                    <For
+                        each=move || timeline.get().players
+                        key=|player| player.player_id
+                        children=move |player| {
+                            let value = player.player_name.clone();
+                            view! {
+                                <option
+                                    value=player.player_name.clone()
+                                    selected=move || player_name.get() == value
+                                >
+                                    {split_clan_tag(&player.player_name).1}
+                                </option>
+                            }
+                        }
+                    />
+                </select>
+            </label>
+            <label class="select select-sm">
+                <span class="label">"Against"</span>
+                <select on:change=move |ev| {
+                    set_scope
+                        .set(
+                            match event_target_value(&ev).as_str() {
+                                "Map" => BenchmarkScope::Map,
+                                _ => BenchmarkScope::Matchup,
+                            },
+                        )
+                }>
+                    <option value="Matchup">"Their games in the matchup"</option>
+                    <option value="Map">"All games on the map"</option>
+                </select>
+            </label>
+            <button
+                class="btn btn-sm btn-primary"
+                on:click=move |_| {
+                    trigger_fetch_benchmark(
+                        set_benchmark,
+                        BenchmarkQuery {
+                            replay_paths: replay_paths.get_untracked(),
+                            ext_fs_id: timeline.get_untracked().ext_fs_id,
+                            player_name: player_name.get_untracked(),
+                            scope: scope.get_untracked(),
+                        },
+                        set_backend_response,
+                    )
+                }
+            >
+                <Icon icon=CHART_LINE_UP weight=IconWeight::Bold prop:class="stroke-current" />
+                "Benchmark"
+            </button>
+        </div>
+        <Show when=move || {
+            !backend_response.get().meta.success && backend_response.get().meta.is_complete
+        }>
+            <div role="alert" class="alert alert-error shadow-lg m-1 p-1">
+                <Icon icon=X_CIRCLE weight=IconWeight::Bold prop:class="stroke-current" />
+                <span>{backend_response.get().message.clone()}</span>
+            </div>
+        </Show>
+        <Show when=move || { !benchmark.get().workers.points.is_empty() }>
+            <div class="grid grid-cols-2 gap-2 mt-2">
+                <BenchmarkChart series=Signal::derive(move || benchmark.get().workers) />
+                <BenchmarkChart series=Signal::derive(move || benchmark.get().army_value) />
+            </div>
+        </Show>
+    }
+}
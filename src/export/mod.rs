@@ -0,0 +1,3 @@
+//! Export module.
+
+pub mod view;
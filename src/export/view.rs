@@ -0,0 +1,127 @@
+//! Leptos view to export the result of a query to a file.
+use crate::*;
+use leptos::leptos_dom::logging::console_log;
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use phosphor_leptos::{Icon, IconWeight, EXPORT, X_CIRCLE};
+use serde::{Deserialize, Serialize};
+use si_scale::helpers::bibytes2;
+use swarmy_tauri_common::*;
+
+/// The arguments of the export_query command.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ExportQueryArgs {
+    query: ExportQuery,
+}
+
+fn trigger_export_query(
+    query: ExportQuery,
+    exported: WriteSignal<Option<ExportResult>>,
+    exporting: WriteSignal<bool>,
+    backend_response: WriteSignal<ResponseStatus>,
+) {
+    backend_response.set(ResponseStatus::incomplete());
+    exported.set(None);
+    exporting.set(true);
+    spawn_local(async move {
+        console_log(&format!("Invoking export_query with query: {:?}", query));
+        let res =
+            invoke_command::<_, Option<ExportResult>>("export_query", &ExportQueryArgs { query })
+                .await;
+        exporting.set(false);
+        backend_response.set(ResponseStatus::from(&res));
+        match (res.data, res.error) {
+            (_, Some(e)) => console_log(&format!("Error invoking export_query: {}", e)),
+            // The data is empty when the save dialog is closed.
+            (data, None) => exported.set(data.flatten()),
+        }
+    });
+}
+
+/// Exports all the rows of `source` to a file picked in a save dialog.
+#[component]
+pub fn ExportMenu(#[prop(into)] source: Signal<ExportSource>) -> impl IntoView {
+    let (format, set_format) = signal(ExportFormat::default());
+    let (compression, set_compression) = signal(false);
+    let (exported, set_exported) = signal(None::<ExportResult>);
+    let (backend_response, set_backend_response) = signal(ResponseStatus::default());
+    let (exporting, set_exporting) = signal(false);
+
+    view! {
+        <div class="flex items-center gap-1">
+            <select
+                class="select select-sm w-32"
+                title="Export format"
+                on:change=move |ev| {
+                    let index: usize = event_target_value(&ev).parse().unwrap_or(0);
+                    set_format.set(ExportFormat::ALL.get(index).copied().unwrap_or_default());
+                }
+            >
+                {ExportFormat::ALL
+                    .iter()
+                    .enumerate()
+                    .map(|(index, option)| {
+                        let option = *option;
+                        view! {
+                            <option value=index.to_string() selected=move || format.get() == option>
+                                {option.name()}
+                            </option>
+                        }
+                    })
+                    .collect_view()}
+            </select>
+            <label class="label text-xs" title="Gzip CSV and JSON Lines, Zstd for Parquet">
+                <input
+                    type="checkbox"
+                    class="toggle toggle-xs toggle-success"
+                    checked=move || compression.get()
+                    on:change=move |ev| set_compression.set(event_target_checked(&ev))
+                />
+                "Compress"
+            </label>
+            <button
+                class="btn btn-sm btn-ghost"
+                title="Export all rows"
+                disabled=move || exporting.get()
+                on:click=move |_| {
+                    trigger_export_query(
+                        ExportQuery {
+                            source: source.get_untracked(),
+                            format: format.get_untracked(),
+                            compression: compression.get_untracked(),
+                        },
+                        set_exported,
+                        set_exporting,
+                        set_backend_response,
+                    )
+                }
+            >
+                <Icon icon=EXPORT weight=IconWeight::Bold prop:class="stroke-current" />
+                "Export"
+            </button>
+            <Show when=move || exported.get().is_some()>
+                <span class="text-neutral-content text-xs truncate">
+                    {move || {
+                        exported
+                            .get()
+                            .map(|exported| {
+                                format!(
+                                    "Saved {} to {}",
+                                    bibytes2(exported.file_size as u32),
+                                    exported.path,
+                                )
+                            })
+                    }}
+                </span>
+            </Show>
+        </div>
+        <Show when=move || {
+            !backend_response.get().meta.success && backend_response.get().meta.is_complete
+        }>
+            <div role="alert" class="alert alert-error shadow-lg m-1 p-1">
+                <Icon icon=X_CIRCLE weight=IconWeight::Bold prop:class="stroke-current" />
+                <span>{backend_response.get().message.clone()}</span>
+            </div>
+        </Show>
+    }
+}
@@ -0,0 +1,3 @@
+//! Jobs module.
+
+pub mod view;
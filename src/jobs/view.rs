@@ -0,0 +1,124 @@
+//! Leptos view for the background jobs of the backend.
+use crate::*;
+use leptos::leptos_dom::logging::console_log;
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use phosphor_leptos::{Icon, IconWeight, ARROWS_CLOCKWISE, X_CIRCLE};
+use std::time::Duration;
+use swarmy_tauri_common::*;
+
+/// How often the jobs are listed again while the page is shown.
+const JOBS_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Lists the running and most recent jobs.
+fn trigger_list_jobs(
+    jobs: WriteSignal<Vec<JobInfo>>,
+    backend_response: WriteSignal<ResponseStatus>,
+) {
+    spawn_local(async move {
+        let res = invoke_command_without_args::<Vec<JobInfo>>("list_jobs").await;
+        backend_response.set(ResponseStatus::from(&res));
+        match res.into_result() {
+            Ok(res) => jobs.set(res),
+            Err(e) => console_log(&format!("Error invoking list_jobs: {}", e)),
+        }
+    });
+}
+
+/// Formats the time a job was submitted in the local time zone.
+fn format_queued_at(queued_at_ms: u64) -> String {
+    js_sys::Date::new(&(queued_at_ms as f64).into())
+        .to_locale_time_string("default")
+        .into()
+}
+
+#[component]
+pub fn JobsPage() -> impl IntoView {
+    let (jobs, set_jobs) = signal(Vec::<JobInfo>::new());
+    let (backend_response, set_backend_response) = signal(ResponseStatus::default());
+
+    trigger_list_jobs(set_jobs, set_backend_response);
+    match set_interval_with_handle(
+        move || trigger_list_jobs(set_jobs, set_backend_response),
+        JOBS_POLL_INTERVAL,
+    ) {
+        Ok(handle) => on_cleanup(move || handle.clear()),
+        Err(e) => console_log(&format!("Error polling the jobs: {:?}", e)),
+    }
+
+    view! {
+        <div class="flex items-center gap-2 m-1">
+            <span class="text-sm text-neutral-content">
+                {move || {
+                    let jobs = jobs.get();
+                    let running = jobs
+                        .iter()
+                        .filter(|info| info.status == JobStatus::Running)
+                        .count();
+                    let queued = jobs.iter().filter(|info| info.status == JobStatus::Queued).count();
+                    format!("{} running, {} queued", running, queued)
+                }}
+            </span>
+            <button
+                class="btn btn-sm btn-primary"
+                on:click=move |_| trigger_list_jobs(set_jobs, set_backend_response)
+            >
+                <Icon icon=ARROWS_CLOCKWISE weight=IconWeight::Bold prop:class="stroke-current" />
+                "Refresh"
+            </button>
+        </div>
+        <Show when=move || {
+            !backend_response.get().meta.success && backend_response.get().meta.is_complete
+        }>
+            <div role="alert" class="alert alert-error shadow-lg m-1 p-1">
+                <Icon icon=X_CIRCLE weight=IconWeight::Bold prop:class="stroke-current" />
+                <span>{backend_response.get().message.clone()}</span>
+            </div>
+        </Show>
+        <table class="table bg-gray-500 table-xs table-zebra rounded-box">
+            <thead class="bg-gray-700">
+                <tr>
+                    <th>Id</th>
+                    <th>Command</th>
+                    <th>Status</th>
+                    <th>Queued At</th>
+                    <th>Duration</th>
+                    <th>Error</th>
+                </tr>
+            </thead>
+            <tbody>
+                <For
+                    each=move || jobs.get()
+                    key=|info| (info.id, info.status, info.duration_ms)
+                    children=|info| {
+                        let status_class = match info.status {
+                            JobStatus::Queued => "badge badge-sm badge-ghost",
+                            JobStatus::Running => "badge badge-sm badge-info",
+                            JobStatus::Finished => "badge badge-sm badge-success",
+                            JobStatus::Failed => "badge badge-sm badge-error",
+                        };
+                        view! {
+                            <tr>
+                                <th>{info.id}</th>
+                                <td>{info.name}</td>
+                                <td>
+                                    <span class=status_class>{format!("{:?}", info.status)}</span>
+                                </td>
+                                <td>{format_queued_at(info.queued_at_ms)}</td>
+                                <td>
+                                    {match info.status {
+                                        JobStatus::Finished | JobStatus::Failed => {
+                                            format!("{} ms", info.duration_ms)
+                                        }
+                                        _ => String::new(),
+                                    }}
+                                </td>
+                                <td>{info.error.unwrap_or_default()}</td>
+                            </tr>
+                        }
+                    }
+                />
+            </tbody>
+        </table>
+    }
+}
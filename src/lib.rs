@@ -1,6 +1,21 @@
 /// Swarmy Tauri Library
 pub mod scan;
 pub mod map_stats;
+pub mod sql_query;
+pub mod player_profile;
+pub mod build_order;
+pub mod economy;
+pub mod jobs;
+pub mod request;
+pub mod export;
+pub mod library;
+use leptos::leptos_dom::logging::console_log;
+use leptos::prelude::{Effect, Get, RwSignal, Set, WriteSignal};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use swarmy_tauri_common::{
+    ApiResponse, BuildOrderComparisonQuery, LibrarySelection, ResponseMetaBuilder, SnapshotUpdated,
+};
 use wasm_bindgen::prelude::*;
 
 #[wasm_bindgen]
@@ -11,4 +26,135 @@ extern "C" {
     // invoke with arguments (default)
     #[wasm_bindgen(js_namespace = ["window", "__TAURI__", "core"])]
     pub async fn invoke(cmd: &str, args: JsValue) -> JsValue;
+    // listen to backend events, resolves to the function that removes the listener.
+    #[wasm_bindgen(js_namespace = ["window", "__TAURI__", "event"])]
+    async fn listen(event: &str, handler: &Closure<dyn FnMut(JsValue)>) -> JsValue;
+}
+
+/// Invokes a backend command with `args` and decodes its typed response.
+pub async fn invoke_command<A, T>(cmd: &str, args: &A) -> ApiResponse<T>
+where
+    A: Serialize,
+    T: DeserializeOwned,
+{
+    let args = match serde_wasm_bindgen::to_value(args) {
+        Ok(args) => args,
+        Err(e) => return ApiResponse::err(ResponseMetaBuilder::new(false).build(), e.into()),
+    };
+    decode_response(cmd, invoke(cmd, args).await)
+}
+
+/// Invokes a backend command that takes no arguments and decodes its typed response.
+pub async fn invoke_command_without_args<T>(cmd: &str) -> ApiResponse<T>
+where
+    T: DeserializeOwned,
+{
+    decode_response(cmd, invoke_without_args(cmd).await)
+}
+
+fn decode_response<T>(cmd: &str, response: JsValue) -> ApiResponse<T>
+where
+    T: DeserializeOwned,
+{
+    serde_wasm_bindgen::from_value::<ApiResponse<T>>(response).unwrap_or_else(|e| {
+        console_log(&format!("Error decoding {} response: {:?}", cmd, e));
+        ApiResponse::err(ResponseMetaBuilder::new(false).build(), e.into())
+    })
+}
+
+/// The envelope Tauri wraps around the payload of the emitted events.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TauriEvent<T> {
+    pub payload: T,
+}
+
+/// Calls `handler` with the payload of every `event` emitted by the backend.
+/// Returns the function that removes the listener.
+pub async fn listen_event<T, F>(event: &str, handler: F) -> js_sys::Function
+where
+    T: DeserializeOwned + 'static,
+    F: Fn(T) + 'static,
+{
+    let event_name = event.to_string();
+    let closure = Closure::<dyn FnMut(JsValue)>::new(move |value: JsValue| {
+        match serde_wasm_bindgen::from_value::<TauriEvent<T>>(value) {
+            Ok(event) => handler(event.payload),
+            Err(e) => console_log(&format!("Error decoding event {}: {:?}", event_name, e)),
+        }
+    });
+    let unlisten = listen(event, &closure).await;
+    // The listener outlives this function, it is kept until unlisten is called.
+    closure.forget();
+    unlisten.unchecked_into()
+}
+
+/// Lets nested components switch the page shown by the app, provided as context by the app.
+#[derive(Debug, Clone, Copy)]
+pub struct Navigation {
+    pub set_active_page: WriteSignal<String>,
+    /// The player shown by the Player page.
+    pub profile_player: RwSignal<String>,
+    /// The build orders shown by the Build Order page.
+    pub build_order_comparison: RwSignal<BuildOrderComparisonQuery>,
+    /// The map title shown by the Map Versions page.
+    pub map_versions_title: RwSignal<String>,
+    /// The game shown by the Economy page.
+    pub economy_game: RwSignal<u64>,
+    /// The libraries the pages query, the pages are mounted again when it changes.
+    pub library_selection: RwSignal<LibrarySelection>,
+    /// The last snapshot updated by the replay watcher, see [`Navigation::on_snapshot_updated`].
+    pub snapshot_updated: RwSignal<Option<SnapshotUpdated>>,
+}
+
+impl Navigation {
+    /// Opens the Player page with the profile of `player`, a name or a toon handle.
+    pub fn open_player_profile(&self, player: String) {
+        self.profile_player.set(player);
+        self.set_active_page.set(String::from("Player"));
+    }
+
+    /// Opens the Build Order page with the build orders of `query`.
+    pub fn open_build_order_comparison(&self, query: BuildOrderComparisonQuery) {
+        self.build_order_comparison.set(query);
+        self.set_active_page.set(String::from("Build Order"));
+    }
+
+    /// Opens the Map Versions page with the versions of the map `title`.
+    pub fn open_map_versions(&self, title: String) {
+        self.map_versions_title.set(title);
+        self.set_active_page.set(String::from("Map Versions"));
+    }
+
+    /// Opens the Economy page with the timeline of the game `ext_fs_id`.
+    pub fn open_economy_timeline(&self, ext_fs_id: u64) {
+        self.economy_game.set(ext_fs_id);
+        self.set_active_page.set(String::from("Economy"));
+    }
+
+    /// Calls `refetch` when the replay watcher updated the snapshot of one of the `replay_paths`
+    /// a page queries, the page keeps its inputs and fetches its data again.
+    pub fn on_snapshot_updated<P, F>(&self, replay_paths: P, refetch: F)
+    where
+        P: Fn() -> Vec<String> + 'static,
+        F: Fn() + 'static,
+    {
+        let snapshot_updated = self.snapshot_updated;
+        Effect::watch(
+            move || snapshot_updated.get(),
+            move |update, _, _| {
+                if update
+                    .as_ref()
+                    .is_some_and(|update| replay_paths().contains(&update.replay_path))
+                {
+                    refetch();
+                }
+            },
+            false,
+        );
+    }
+}
+
+/// Formats a number of seconds of game time as `mm:ss`.
+pub fn format_game_seconds(seconds: u32) -> String {
+    format!("{}:{:02}", seconds / 60, seconds % 60)
 }
@@ -0,0 +1,3 @@
+//! Library module.
+
+pub mod view;
@@ -0,0 +1,276 @@
+//! Leptos view to switch between the replay libraries.
+use crate::*;
+use leptos::leptos_dom::logging::console_log;
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use phosphor_leptos::{Icon, IconWeight, BOOKS, PLUS, TRASH, X_CIRCLE};
+use serde::{Deserialize, Serialize};
+use swarmy_tauri_common::*;
+
+/// The arguments of the save_library command.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SaveLibraryArgs {
+    library: ReplayLibrary,
+}
+
+/// The arguments of the remove_library command.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RemoveLibraryArgs {
+    name: String,
+}
+
+/// The arguments of the select_libraries command.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SelectLibrariesArgs {
+    selection: LibrarySelection,
+}
+
+/// Invokes a command that updates the libraries and applies the settings it returns.
+fn trigger_update_libraries<A>(
+    cmd: &'static str,
+    args: A,
+    navigation: Navigation,
+    settings: WriteSignal<AppSettings>,
+    backend_response: WriteSignal<ResponseStatus>,
+) where
+    A: Serialize + 'static,
+{
+    backend_response.set(ResponseStatus::incomplete());
+    spawn_local(async move {
+        let res = invoke_command::<_, AppSettings>(cmd, &args).await;
+        backend_response.set(ResponseStatus::from(&res));
+        match res.into_result() {
+            Ok(config) => {
+                navigation
+                    .library_selection
+                    .set(config.library_selection.clone());
+                settings.set(config);
+            }
+            Err(e) => console_log(&format!("Error invoking {}: {}", cmd, e)),
+        }
+    });
+}
+
+/// The sidebar button that opens the list of libraries, to add, remove and select the libraries
+/// the pages query.
+#[component]
+pub fn LibrarySwitcher() -> impl IntoView {
+    let navigation = expect_context::<Navigation>();
+    let (settings, set_settings) = signal(AppSettings::default());
+    let (is_open, set_is_open) = signal(false);
+    let (new_library, set_new_library) = signal(ReplayLibrary::default());
+    let (backend_response, set_backend_response) = signal(ResponseStatus::default());
+
+    spawn_local(async move {
+        match invoke_command_without_args::<AppSettings>("get_current_app_config")
+            .await
+            .into_result()
+        {
+            // The pages load the same settings, they are only mounted again on changes.
+            Ok(config) => set_settings.set(config),
+            Err(e) => {
+                console_log(&format!("Error invoking get_current_app_config: {:?}", e));
+            }
+        }
+    });
+    let select = move |selection: LibrarySelection| {
+        trigger_update_libraries(
+            "select_libraries",
+            SelectLibrariesArgs { selection },
+            navigation,
+            set_settings,
+            set_backend_response,
+        )
+    };
+
+    view! {
+        <a
+            href="#"
+            class="flex items-center justify-center w-12 h-12 mt-2 rounded hover:bg-gray-700 hover:text-gray-300"
+            title=move || {
+                let selection = settings.get().library_selection;
+                if selection.active.is_empty() {
+                    String::from("Libraries")
+                } else {
+                    std::iter::once(selection.active)
+                        .chain(selection.union)
+                        .collect::<Vec<_>>()
+                        .join(" + ")
+                }
+            }
+            on:click=move |ev| {
+                ev.prevent_default();
+                set_is_open.set(true);
+            }
+        >
+            <Icon icon=BOOKS weight=IconWeight::Bold size="24px" />
+        </a>
+        <Show when=move || is_open.get()>
+            <div class="modal modal-open">
+                <div class="modal-box max-w-3xl text-neutral-content">
+                    <h3 class="text-lg font-bold">"Libraries"</h3>
+                    <p class="text-xs text-gray-400">
+                        "The active library is scanned and optimized. The pages query it together with the other checked libraries."
+                    </p>
+                    <table class="table table-xs">
+                        <thead>
+                            <tr>
+                                <th>"Active"</th>
+                                <th>"Query"</th>
+                                <th>"Name"</th>
+                                <th>"Path"</th>
+                                <th></th>
+                            </tr>
+                        </thead>
+                        <tbody>
+                            <For
+                                each=move || settings.get().libraries
+                                key=|library| (library.name.clone(), library.replay_path.clone())
+                                children=move |library| {
+                                    let name = library.name.clone();
+                                    let is_active = {
+                                        let name = name.clone();
+                                        move || settings.get().library_selection.active == name
+                                    };
+                                    let is_queried = {
+                                        let name = name.clone();
+                                        move || {
+                                            let selection = settings.get().library_selection;
+                                            selection.active == name || selection.union.contains(&name)
+                                        }
+                                    };
+                                    let name_active = name.clone();
+                                    let name_union = name.clone();
+                                    let name_remove = name.clone();
+                                    view! {
+                                        <tr>
+                                            <td>
+                                                <input
+                                                    type="radio"
+                                                    name="active-library"
+                                                    class="radio radio-xs"
+                                                    prop:checked=is_active.clone()
+                                                    on:change=move |_| {
+                                                        let mut selection = settings
+                                                            .get_untracked()
+                                                            .library_selection;
+                                                        selection.active = name_active.clone();
+                                                        select(selection);
+                                                    }
+                                                />
+                                            </td>
+                                            <td>
+                                                <input
+                                                    type="checkbox"
+                                                    class="checkbox checkbox-xs"
+                                                    prop:checked=is_queried
+                                                    disabled=is_active
+                                                    on:change=move |ev| {
+                                                        let mut selection = settings
+                                                            .get_untracked()
+                                                            .library_selection;
+                                                        selection.union.retain(|union_name| *union_name != name_union);
+                                                        if event_target_checked(&ev) {
+                                                            selection.union.push(name_union.clone());
+                                                        }
+                                                        select(selection);
+                                                    }
+                                                />
+                                            </td>
+                                            <td>{library.name}</td>
+                                            <td class="truncate max-w-xs" title=library.replay_path.clone()>
+                                                {library.replay_path.clone()}
+                                            </td>
+                                            <td>
+                                                <button
+                                                    class="btn btn-xs btn-ghost"
+                                                    title="Remove the library, its snapshot is kept on disk"
+                                                    on:click=move |_| {
+                                                        trigger_update_libraries(
+                                                            "remove_library",
+                                                            RemoveLibraryArgs {
+                                                                name: name_remove.clone(),
+                                                            },
+                                                            navigation,
+                                                            set_settings,
+                                                            set_backend_response,
+                                                        )
+                                                    }
+                                                >
+                                                    <Icon icon=TRASH weight=IconWeight::Bold prop:class="stroke-current" />
+                                                </button>
+                                            </td>
+                                        </tr>
+                                    }
+                                }
+                            />
+                        </tbody>
+                    </table>
+                    <div class="flex items-center gap-1 mt-2">
+                        <label class="input input-sm w-40">
+                            <span class="label">"Name"</span>
+                            <input
+                                class="input input-sm my-0 mx-0"
+                                placeholder="Ladder"
+                                prop:value=move || new_library.get().name
+                                on:input=move |ev| {
+                                    set_new_library.update(|library| library.name = event_target_value(&ev));
+                                }
+                                type="text"
+                            />
+                        </label>
+                        <label class="input input-sm flex-grow">
+                            <span class="label">"Path"</span>
+                            <input
+                                class="input input-sm my-0 mx-0"
+                                prop:value=move || new_library.get().replay_path
+                                on:input=move |ev| {
+                                    set_new_library
+                                        .update(|library| library.replay_path = event_target_value(&ev));
+                                }
+                                type="text"
+                            />
+                        </label>
+                        <button
+                            class="btn btn-sm btn-primary"
+                            title="Add the library, or update the library with the same name"
+                            disabled=move || {
+                                new_library.get().name.trim().is_empty()
+                                    || new_library.get().replay_path.is_empty()
+                            }
+                            on:click=move |_| {
+                                trigger_update_libraries(
+                                    "save_library",
+                                    SaveLibraryArgs {
+                                        library: new_library.get_untracked(),
+                                    },
+                                    navigation,
+                                    set_settings,
+                                    set_backend_response,
+                                );
+                                set_new_library.set(ReplayLibrary::default());
+                            }
+                        >
+                            <Icon icon=PLUS weight=IconWeight::Bold prop:class="stroke-current" />
+                            "Add"
+                        </button>
+                    </div>
+                    <Show when=move || {
+                        !backend_response.get().meta.success
+                            && backend_response.get().meta.is_complete
+                    }>
+                        <div role="alert" class="alert alert-error shadow-lg m-1 p-1">
+                            <Icon icon=X_CIRCLE weight=IconWeight::Bold prop:class="stroke-current" />
+                            <span>{backend_response.get().message.clone()}</span>
+                        </div>
+                    </Show>
+                    <div class="modal-action">
+                        <button class="btn btn-sm" on:click=move |_| set_is_open.set(false)>
+                            "Close"
+                        </button>
+                    </div>
+                </div>
+            </div>
+        </Show>
+    }
+}
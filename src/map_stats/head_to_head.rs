@@ -0,0 +1,288 @@
+//! Leptos view for the head-to-head report between two players.
+use crate::player_profile::view::PlayerRecordTable;
+use crate::*;
+use leptos::leptos_dom::logging::console_log;
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use phosphor_leptos::{Icon, IconWeight, CHART_LINE, LIST_CHECKS, SWORD, X_CIRCLE};
+use serde::{Deserialize, Serialize};
+use swarmy_tauri_common::*;
+
+/// The arguments of the query_head_to_head command.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct QueryHeadToHeadArgs {
+    query: HeadToHeadQuery,
+}
+
+fn trigger_fetch_head_to_head(
+    data: WriteSignal<HeadToHead>,
+    query_cp: HeadToHeadQuery,
+    backend_response: WriteSignal<ResponseStatus>,
+) {
+    if query_cp.replay_paths.is_empty() {
+        console_log("Replay path is empty.");
+        return;
+    }
+    spawn_local(async move {
+        console_log(&format!(
+            "Invoking query_head_to_head with query: {:?}",
+            query_cp
+        ));
+        let res = invoke_command::<_, HeadToHead>(
+            "query_head_to_head",
+            &QueryHeadToHeadArgs { query: query_cp },
+        )
+        .await;
+        backend_response.set(ResponseStatus::from(&res));
+        match res.into_result() {
+            Ok(res) => data.set(res),
+            Err(e) => {
+                console_log(&format!("Error invoking query_head_to_head: {}", e));
+                data.set(HeadToHead::default());
+            }
+        }
+    });
+}
+
+#[component]
+pub fn HeadToHeadReport() -> impl IntoView {
+    let navigation = expect_context::<Navigation>();
+    let (query, set_query) = signal(HeadToHeadQuery::default());
+    let (head_to_head, set_head_to_head) = signal(HeadToHead::default());
+    let (backend_response, set_backend_response) = signal(ResponseStatus::incomplete());
+    // The players of the report shown, the inputs may have changed since.
+    let compared = StoredValue::new(None::<HeadToHeadQuery>);
+    let compare = move |query: HeadToHeadQuery| {
+        compared.set_value(Some(query.clone()));
+        trigger_fetch_head_to_head(set_head_to_head, query, set_backend_response);
+    };
+
+    spawn_local(async move {
+        // The snapshot location is part of the app settings.
+        match invoke_command_without_args::<AppSettings>("get_current_app_config")
+            .await
+            .into_result()
+        {
+            Ok(config) => set_query.update(|query| query.replay_paths = config.query_replay_paths),
+            Err(e) => {
+                console_log(&format!("Error invoking get_current_app_config: {:?}", e));
+            }
+        }
+    });
+    navigation.on_snapshot_updated(
+        move || query.get_untracked().replay_paths,
+        move || {
+            if let Some(query) = compared.get_value() {
+                compare(query);
+            }
+        },
+    );
+
+    view! {
+        <div class="grid grid-cols-8 gap-1">
+            <div class="col-span-3">
+                <label class="input input-sm w-full">
+                    <span class="label">"Player A"</span>
+                    <input
+                        class="input input-sm my-0 mx-0"
+                        placeholder="Name or toon handle"
+                        prop:value=move || query.get().player_a
+                        on:input=move |ev| {
+                            set_query.update(|query| query.player_a = event_target_value(&ev));
+                        }
+                        type="text"
+                    />
+                </label>
+            </div>
+            <div class="col-span-3">
+                <label class="input input-sm w-full">
+                    <span class="label">"Player B"</span>
+                    <input
+                        class="input input-sm my-0 mx-0"
+                        placeholder="Name or toon handle"
+                        prop:value=move || query.get().player_b
+                        on:input=move |ev| {
+                            set_query.update(|query| query.player_b = event_target_value(&ev));
+                        }
+                        type="text"
+                    />
+                </label>
+            </div>
+            <div class="col-span-2">
+                <button
+                    class="btn btn-sm btn-primary"
+                    disabled=move || {
+                        query.get().player_a.trim().is_empty()
+                            || query.get().player_b.trim().is_empty()
+                    }
+                    on:click=move |_| compare(query.get_untracked())
+                >
+                    <Icon icon=SWORD weight=IconWeight::Bold prop:class="stroke-current" />
+                    "Compare"
+                </button>
+            </div>
+            <Show when=move || {
+                !backend_response.get().meta.success && backend_response.get().meta.is_complete
+            }>
+                <div role="alert" class="alert alert-error shadow-lg m-1 p-1 col-span-8">
+                    <Icon icon=X_CIRCLE weight=IconWeight::Bold prop:class="stroke-current" />
+                    <span>{backend_response.get().message.clone()}</span>
+                </div>
+            </Show>
+            <Show when=move || {
+                backend_response.get().meta.success && head_to_head.get().record.games == 0
+            }>
+                <div class="col-span-8 text-neutral-content">
+                    "These players have not played against each other."
+                </div>
+            </Show>
+            <Show when=move || { head_to_head.get().record.games > 0 }>
+                <div class="col-span-8">
+                    <HeadToHeadView head_to_head query />
+                </div>
+            </Show>
+        </div>
+    }
+}
+
+#[component]
+pub fn HeadToHeadView(
+    head_to_head: ReadSignal<HeadToHead>,
+    query: ReadSignal<HeadToHeadQuery>,
+) -> impl IntoView {
+    let navigation = expect_context::<Navigation>();
+    view! {
+        <div class="stats shadow m-1">
+            <div class="stat">
+                <div class="stat-title">"Games"</div>
+                <div class="stat-value">{move || head_to_head.get().record.games}</div>
+            </div>
+            <div class="stat">
+                <div class="stat-title">{move || head_to_head.get().player_a}</div>
+                <div class="stat-value text-success">{move || head_to_head.get().record.wins}</div>
+            </div>
+            <div class="stat">
+                <div class="stat-title">{move || head_to_head.get().player_b}</div>
+                <div class="stat-value text-error">{move || head_to_head.get().record.losses}</div>
+            </div>
+            <div class="stat">
+                <div class="stat-title">"Average Length"</div>
+                <div class="stat-value">
+                    {move || format_game_seconds(head_to_head.get().avg_game_seconds as u32)}
+                </div>
+            </div>
+        </div>
+        <div class="flex gap-4">
+            <div class="flex-item grow">
+                <h3 class="text-neutral-content flex justify-center bg-gray-800">"By Map"</h3>
+                <PlayerRecordTable
+                    label="Map Title"
+                    rows=Signal::derive(move || {
+                        head_to_head
+                            .get()
+                            .maps
+                            .into_iter()
+                            .map(|map| (map.title, map.record))
+                            .collect()
+                    })
+                />
+            </div>
+            <div class="flex-item grow">
+                <h3 class="text-neutral-content flex justify-center bg-gray-800">
+                    "By Race Pairing"
+                </h3>
+                <PlayerRecordTable
+                    label="Races"
+                    rows=Signal::derive(move || {
+                        head_to_head
+                            .get()
+                            .races
+                            .into_iter()
+                            .map(|races| (format!("{} vs {}", races.race_a, races.race_b), races.record))
+                            .collect()
+                    })
+                />
+            </div>
+            <div class="flex-item grow">
+                <h3 class="text-neutral-content flex justify-center bg-gray-800">"Over Time"</h3>
+                <PlayerRecordTable
+                    label="Month"
+                    rows=Signal::derive(move || {
+                        head_to_head
+                            .get()
+                            .months
+                            .into_iter()
+                            .map(|month| (month.month, month.record))
+                            .collect()
+                    })
+                />
+            </div>
+        </div>
+        <h3 class="text-neutral-content flex justify-center bg-gray-800 mt-2">"Games"</h3>
+        <table class="table bg-gray-500 table-xs table-zebra rounded-box">
+            <thead class="bg-gray-700">
+                <tr>
+                    <th>Date</th>
+                    <th>Map Title</th>
+                    <th>Races</th>
+                    <th>Result</th>
+                    <th>Length</th>
+                    <th></th>
+                </tr>
+            </thead>
+            <tbody>
+                <For
+                    each=move || head_to_head.get().games
+                    key=|game| game.ext_fs_id
+                    children=move |game| {
+                        let replay_paths = query.get_untracked().replay_paths;
+                        let build_orders = BuildOrderComparisonQuery {
+                            a: BuildOrderQuery {
+                                replay_paths: replay_paths.clone(),
+                                ext_fs_id: game.ext_fs_id,
+                                player_name: game.player_name_a.clone(),
+                                ..Default::default()
+                            },
+                            b: BuildOrderQuery {
+                                replay_paths,
+                                ext_fs_id: game.ext_fs_id,
+                                player_name: game.player_name_b.clone(),
+                                ..Default::default()
+                            },
+                        };
+                        view! {
+                            <tr>
+                                <td>{game.date.format("%Y-%m-%d %H:%M").to_string()}</td>
+                                <td>{game.title}</td>
+                                <td>{format!("{} vs {}", game.race_a, game.race_b)}</td>
+                                <td>{game.result_a}</td>
+                                <td>{format_game_seconds(game.game_seconds)}</td>
+                                <td>
+                                    <button
+                                        class="btn btn-xs btn-ghost"
+                                        title="Compare build orders"
+                                        on:click=move |_| {
+                                            navigation
+                                                .open_build_order_comparison(build_orders.clone())
+                                        }
+                                    >
+                                        <Icon icon=LIST_CHECKS weight=IconWeight::Bold />
+                                    </button>
+                                    <button
+                                        class="btn btn-xs btn-ghost"
+                                        title="Show the economy timeline"
+                                        on:click=move |_| {
+                                            navigation.open_economy_timeline(game.ext_fs_id)
+                                        }
+                                    >
+                                        <Icon icon=CHART_LINE weight=IconWeight::Bold />
+                                    </button>
+                                </td>
+                            </tr>
+                        }
+                    }
+                />
+            </tbody>
+        </table>
+    }
+}
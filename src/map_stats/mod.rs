@@ -1,8 +1,12 @@
 //! Map stats module.
 
+pub mod head_to_head;
+pub mod versions;
 pub mod view;
 
+use reactive_stores::Store;
 use serde::{Deserialize, Serialize};
+use swarmy_tauri_common::{MapStatsDataFrame, MatchupWinRate};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MapListEntry {
@@ -10,3 +14,64 @@ pub struct MapListEntry {
     pub count: usize,
     pub cache_stats: String,
 }
+
+#[derive(Store, Debug, Default, Clone, Serialize, Deserialize)]
+pub struct MapStatsTable {
+    pub total: usize,
+    #[store(key: String = |row| row.key.clone())]
+    pub rows: Vec<MapStatsEntry>,
+    pub start: usize,
+    pub end: usize,
+    pub page: usize,
+    pub per_page: usize,
+    /// The matchups of any of the rows, the columns of the win rate matrix.
+    pub matchup_columns: Vec<String>,
+}
+
+#[derive(Store, Debug, Clone, Serialize, Deserialize)]
+pub struct MapStatsEntry {
+    /// The title and cache_handles, unique per row.
+    pub key: String,
+    pub idx: usize,
+    pub title: String,
+    pub cache_handles: String,
+    pub num_games: u64,
+    pub min_date: String,
+    pub max_date: String,
+    pub matchups: Vec<MatchupWinRate>,
+}
+
+impl From<MapStatsDataFrame> for MapStatsTable {
+    fn from(data: MapStatsDataFrame) -> Self {
+        let mut matchup_columns: Vec<String> = data
+            .res
+            .iter()
+            .flat_map(|map| map.matchups.iter().map(|matchup| matchup.matchup.clone()))
+            .collect();
+        matchup_columns.sort();
+        matchup_columns.dedup();
+        Self {
+            total: data.total,
+            rows: data
+                .res
+                .into_iter()
+                .enumerate()
+                .map(|(idx, map)| MapStatsEntry {
+                    key: format!("{}-{}", map.title, map.cache_handles),
+                    idx: data.start + idx + 1,
+                    title: map.title,
+                    cache_handles: map.cache_handles,
+                    num_games: map.num_games,
+                    min_date: map.min_date.format("%Y-%m-%d").to_string(),
+                    max_date: map.max_date.format("%Y-%m-%d").to_string(),
+                    matchups: map.matchups,
+                })
+                .collect(),
+            start: data.start,
+            end: data.end,
+            page: data.page,
+            per_page: data.per_page,
+            matchup_columns,
+        }
+    }
+}
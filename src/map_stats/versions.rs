@@ -0,0 +1,226 @@
+//! Leptos view for the versions of a map.
+use crate::map_stats::view::MatchupWinRateCell;
+use crate::*;
+use leptos::leptos_dom::logging::console_log;
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use phosphor_leptos::{Icon, IconWeight, MAGNIFYING_GLASS, X_CIRCLE};
+use serde::{Deserialize, Serialize};
+use swarmy_tauri_common::*;
+
+/// The arguments of the query_map_versions command.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct QueryMapVersionsArgs {
+    query: MapVersionsQuery,
+}
+
+fn trigger_fetch_map_versions(
+    versions: WriteSignal<MapVersions>,
+    query: MapVersionsQuery,
+    backend_response: WriteSignal<ResponseStatus>,
+) {
+    if query.replay_paths.is_empty() || query.map_title.is_empty() {
+        console_log("Replay path or map title is empty.");
+        return;
+    }
+    spawn_local(async move {
+        console_log(&format!(
+            "Invoking query_map_versions with query: {:?}",
+            query
+        ));
+        let res =
+            invoke_command::<_, MapVersions>("query_map_versions", &QueryMapVersionsArgs { query })
+                .await;
+        backend_response.set(ResponseStatus::from(&res));
+        match res.into_result() {
+            Ok(res) => versions.set(res),
+            Err(e) => {
+                console_log(&format!("Error invoking query_map_versions: {}", e));
+                versions.set(MapVersions::default());
+            }
+        }
+    });
+}
+
+#[component]
+pub fn MapVersionsReport() -> impl IntoView {
+    let navigation = expect_context::<Navigation>();
+    let (replay_paths, set_replay_paths) = signal(Vec::<String>::new());
+    let (title_input, set_title_input) = signal(navigation.map_versions_title.get_untracked());
+    let (versions, set_versions) = signal(MapVersions::default());
+    let (backend_response, set_backend_response) = signal(ResponseStatus::incomplete());
+
+    spawn_local(async move {
+        // The snapshot location is part of the app settings.
+        match invoke_command_without_args::<AppSettings>("get_current_app_config")
+            .await
+            .into_result()
+        {
+            Ok(config) => set_replay_paths.set(config.query_replay_paths),
+            Err(e) => {
+                console_log(&format!("Error invoking get_current_app_config: {:?}", e));
+            }
+        }
+    });
+    navigation.on_snapshot_updated(
+        move || replay_paths.get_untracked(),
+        move || {
+            trigger_fetch_map_versions(
+                set_versions,
+                MapVersionsQuery {
+                    replay_paths: replay_paths.get_untracked(),
+                    map_title: navigation.map_versions_title.get_untracked(),
+                },
+                set_backend_response,
+            )
+        },
+    );
+    // Fetches the versions whenever another map is opened, from this page or the map stats.
+    Effect::new(move |_| {
+        let map_title = navigation.map_versions_title.get();
+        set_title_input.set(map_title.clone());
+        trigger_fetch_map_versions(
+            set_versions,
+            MapVersionsQuery {
+                replay_paths: replay_paths.get(),
+                map_title,
+            },
+            set_backend_response,
+        );
+    });
+
+    view! {
+        <div class="grid grid-cols-8 gap-1">
+            <div class="col-span-4">
+                <label class="input input-sm w-full">
+                    <span class="label">"Map"</span>
+                    <input
+                        class="input input-sm my-0 mx-0"
+                        placeholder="Exact map title"
+                        prop:value=move || title_input.get()
+                        on:input=move |ev| set_title_input.set(event_target_value(&ev))
+                        on:keydown=move |ev| {
+                            if ev.key() == "Enter" {
+                                navigation.map_versions_title.set(title_input.get_untracked());
+                            }
+                        }
+                        type="text"
+                    />
+                </label>
+            </div>
+            <div class="col-span-1">
+                <button
+                    class="btn btn-sm btn-ghost"
+                    title="Search"
+                    on:click=move |_| {
+                        navigation.map_versions_title.set(title_input.get_untracked())
+                    }
+                >
+                    <Icon icon=MAGNIFYING_GLASS weight=IconWeight::Bold prop:class="stroke-current" />
+                </button>
+            </div>
+            <div class="col-span-3"></div>
+            <Show when=move || {
+                !backend_response.get().meta.success && backend_response.get().meta.is_complete
+            }>
+                <div role="alert" class="alert alert-error shadow-lg m-1 p-1 col-span-8">
+                    <Icon icon=X_CIRCLE weight=IconWeight::Bold prop:class="stroke-current" />
+                    <span>{backend_response.get().message.clone()}</span>
+                </div>
+            </Show>
+            <Show when=move || {
+                backend_response.get().meta.success && versions.get().versions.is_empty()
+            }>
+                <div class="col-span-8 text-neutral-content">"No games were played in this map."</div>
+            </Show>
+            <Show when=move || { !versions.get().versions.is_empty() }>
+                <div class="col-span-8">
+                    <MapVersionsTable versions />
+                </div>
+            </Show>
+        </div>
+    }
+}
+
+/// The versions of a map, one row each, with the win rate of every matchup and its change from
+/// the row above.
+#[component]
+pub fn MapVersionsTable(versions: ReadSignal<MapVersions>) -> impl IntoView {
+    let matchup_columns = move || {
+        let mut columns: Vec<String> = versions
+            .get()
+            .versions
+            .iter()
+            .flat_map(|version| {
+                version
+                    .matchups
+                    .iter()
+                    .map(|matchup| matchup.matchup.clone())
+            })
+            .collect();
+        columns.sort();
+        columns.dedup();
+        columns
+    };
+    view! {
+        <table class="table bg-gray-500 table-xs table-zebra rounded-box">
+            <thead class="bg-gray-700">
+                <tr>
+                    <th>Version</th>
+                    <th>Cache Handles</th>
+                    <th>Total Games</th>
+                    <th>First Played</th>
+                    <th>Last Played</th>
+                    <For
+                        each=matchup_columns
+                        key=|matchup| matchup.clone()
+                        children=|matchup| {
+                            view! { <th title="Win rate, 1v1 games">{matchup}</th> }
+                        }
+                    />
+                </tr>
+            </thead>
+            <tbody>
+                {move || {
+                    let columns = matchup_columns();
+                    versions
+                        .get()
+                        .versions
+                        .into_iter()
+                        .enumerate()
+                        .map(|(idx, version)| {
+                            let cells = columns
+                                .iter()
+                                .map(|column| {
+                                    let matchup = version
+                                        .matchups
+                                        .iter()
+                                        .find(|matchup| &matchup.matchup == column)
+                                        .cloned();
+                                    let shift = version
+                                        .shifts
+                                        .iter()
+                                        .find(|shift| &shift.matchup == column)
+                                        .cloned();
+                                    view! { <MatchupWinRateCell matchup shift /> }
+                                })
+                                .collect_view();
+                            view! {
+                                <tr>
+                                    <th>{idx + 1}</th>
+                                    <td class="truncate max-w-xs" title=version.cache_handles.clone()>
+                                        {version.cache_handles.clone()}
+                                    </td>
+                                    <td>{version.num_games}</td>
+                                    <td>{version.min_date.format("%Y-%m-%d").to_string()}</td>
+                                    <td>{version.max_date.format("%Y-%m-%d").to_string()}</td>
+                                    {cells}
+                                </tr>
+                            }
+                        })
+                        .collect_view()
+                }}
+            </tbody>
+        </table>
+    }
+}
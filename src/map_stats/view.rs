@@ -1,59 +1,155 @@
 //! Leptos view for map stats.
 use leptos::prelude::*;
 use reactive_stores::Store;
+use reactive_graph::traits::Read;
 use swarmy_tauri_common::*;
 use crate::*;
+use crate::map_stats::*;
+use crate::build_order::view::{trigger_build_labels, BuildLabelsArgs};
+use crate::export::view::ExportMenu;
+use crate::request::{Debouncer, RequestSequence, INPUT_DEBOUNCE};
 use leptos::task::spawn_local;
-use serde::{Deserialize, Serialize};
 use leptos::leptos_dom::logging::console_log;
-use phosphor_leptos::{Icon, IconWeight, X_CIRCLE};
-
-use s2protocol::details::PlayerLobbyDetails;
+use phosphor_leptos::{Icon, IconWeight, CARET_LEFT, CARET_RIGHT, X_CIRCLE};
+use serde::{Deserialize, Serialize};
 
-#[derive(Store, Debug, Default, Clone, Serialize, Deserialize)]
-pub struct MapStatsDataFrame {
-    pub total: usize,
-    #[store(key: String = |row| row.title.clone())]
-    pub res: Vec<PlayerLobbyDetails>,
-    pub start: usize,
-    pub end: usize,
-    pub page: usize,
-    pub per_page: usize,
+/// The arguments of the query_map_stats command.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct QueryMapStatsArgs {
+    query: MapStatsQuery,
 }
 
+/// The arguments of the autocomplete commands.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct AutocompleteArgs {
+    query: AutocompleteQuery,
+}
 
-async fn fetch_query_map_stats(query: MapStatsQuery) -> Result<MapStatsDataFrame, SwarmyTauriError> {
-    let args = serde_wasm_bindgen::to_value(&query).unwrap();
-    console_log(&format!(
-        "Invoking fetch_query_map_stats with args: {:?}",
-        args
-    ));
-    // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
-    let response = serde_wasm_bindgen::from_value::<ApiResponse>(
-        invoke("query_map_stats", args).await,
-    )?;
-            Ok(serde_json::from_str(&response.message)?)
+/// Fetches the map stats of `query`, the response is dropped if another request of the
+/// `sequence` was started meanwhile.
+fn trigger_fetch_query_map_stats(
+    data: Store<MapStatsTable>,
+    query: MapStatsQuery,
+    sequence: RequestSequence,
+    backend_response: WriteSignal<ResponseStatus>,
+) {
+    if query.replay_paths.is_empty() {
+        console_log("Replay path is empty.");
+        return;
+    }
+    let request_id = sequence.next();
+    spawn_local(async move {
+        console_log(&format!("Invoking query_map_stats {} with query: {:?}", request_id, query));
+        // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
+        let res = invoke_command::<_, MapStatsDataFrame>(
+            "query_map_stats",
+            &QueryMapStatsArgs { query },
+        )
+        .await;
+        if !sequence.is_latest(request_id) {
+            console_log(&format!("Dropping stale query_map_stats response {}", request_id));
+            return;
+        }
+        backend_response.set(ResponseStatus::from(&res));
+        match res.into_result() {
+            Ok(res) => {
+                *data.write() = res.into();
+            }
+            Err(e) => {
+                console_log(&format!("Error invoking query_map_stats: {}", e));
+                *data.write() = MapStatsTable::default();
+            }
+        }
+    });
 }
 
-fn trigger_fetch_query_map_stats(data: Store<MapStatsDataFrame>, query: ReadSignal<MapStatsQuery>) {
-    let query_cp = query.get_untracked();
+/// Fetches the suggestions of `cmd`, one of the autocomplete commands, the response is dropped
+/// if another request of the `sequence` was started meanwhile.
+fn trigger_autocomplete(
+    cmd: &'static str,
+    suggestions: WriteSignal<Vec<String>>,
+    query: AutocompleteQuery,
+    sequence: RequestSequence,
+) {
+    let request_id = sequence.next();
+    if query.replay_paths.is_empty() || query.term.trim().is_empty() {
+        suggestions.set(vec![]);
+        return;
+    }
     spawn_local(async move {
-        *data.write() = fetch_query_map_stats(query_cp).await.unwrap_or_default();
+        let res = invoke_command::<_, Vec<String>>(cmd, &AutocompleteArgs { query }).await;
+        if !sequence.is_latest(request_id) {
+            return;
+        }
+        match res.into_result() {
+            Ok(res) => suggestions.set(res),
+            Err(e) => console_log(&format!("Error invoking {}: {}", cmd, e)),
+        }
     });
 }
 
 #[component]
 pub fn StatsByMap() -> impl IntoView {
-
+    let navigation = expect_context::<Navigation>();
     let (query, set_query) = signal(MapStatsQuery::default());
-    let (player_name, set_player_name) = signal(String::new());
-    let (map_title, set_map_title) = signal(String::new());
-    let (map_stats, set_map_stats) = signal(MapStatsDataFrame::default());
-    let (backend_response, set_backend_response) = signal(ApiResponse {
-        meta: ResponseMeta::incomplete(),
-        message: String::new(),
+    let (map_title_suggestions, set_map_title_suggestions) = signal(Vec::<String>::new());
+    let (player_name_suggestions, set_player_name_suggestions) = signal(Vec::<String>::new());
+    let (backend_response, set_backend_response) = signal(ResponseStatus::incomplete());
+    let (build_labels, set_build_labels) = signal(BuildLabelSummary::default());
+    let (build_labels_response, set_build_labels_response) = signal(ResponseStatus::default());
+    let map_stats_data = Store::new(MapStatsTable::default());
+    let map_stats_sequence = RequestSequence::new();
+    let map_title_sequence = RequestSequence::new();
+    let player_name_sequence = RequestSequence::new();
+    let map_title_debouncer = Debouncer::new(INPUT_DEBOUNCE);
+    let player_name_debouncer = Debouncer::new(INPUT_DEBOUNCE);
+    let fetch_map_stats = move || {
+        trigger_fetch_query_map_stats(
+            map_stats_data,
+            query.get_untracked(),
+            map_stats_sequence,
+            set_backend_response,
+        )
+    };
+    let autocomplete_query = move |term: String| AutocompleteQuery {
+        replay_paths: query.get_untracked().replay_paths,
+        term,
+        limit: 0,
+    };
+
+    spawn_local(async move {
+        // The snapshot location is part of the app settings.
+        match invoke_command_without_args::<AppSettings>("get_current_app_config")
+            .await
+            .into_result()
+        {
+            Ok(config) => {
+                // The openings of the snapshot are offered as a filter.
+                trigger_build_labels(
+                    "get_build_labels",
+                    set_build_labels,
+                    BuildLabelsArgs {
+                        replay_paths: config.query_replay_paths.clone(),
+                        rules_path: config.build_rules_path,
+                    },
+                    set_build_labels_response,
+                );
+                set_query.update(|query| query.replay_paths = config.query_replay_paths);
+                fetch_map_stats();
+            }
+            Err(e) => {
+                console_log(&format!("Error invoking get_current_app_config: {:?}", e));
+            }
+        }
     });
-    let map_stats_data = Store::new(MapStatsDataFrame::default());
+    navigation.on_snapshot_updated(move || query.get_untracked().replay_paths, fetch_map_stats);
+    let change_page = move |delta: isize| {
+        set_query.update(|query| {
+            query.page = query.page.saturating_add_signed(delta);
+        });
+        fetch_map_stats();
+    };
+
     view! {
         <div class="grid grid-cols-8 grid-rows-1 gap-1">
             <div class="col-span-3">
@@ -61,13 +157,38 @@ pub fn StatsByMap() -> impl IntoView {
                     <span class="label">"Map"</span>
                     <input
                         class="input input-sm my-0 mx-0"
-                        value=move || map_title.get()
-                        on:input=move |_| {
-                            trigger_fetch_query_map_stats(map_stats_data, query);
+                        list="map-title-suggestions"
+                        prop:value=move || query.get().map_title
+                        on:input=move |ev| {
+                            let map_title = event_target_value(&ev);
+                            set_query
+                                .update(|query| {
+                                    query.map_title = map_title.clone();
+                                    query.page = 0;
+                                });
+                            // The responses of the requests in flight are for older text.
+                            map_stats_sequence.next();
+                            map_title_debouncer
+                                .call(move || {
+                                    fetch_map_stats();
+                                    trigger_autocomplete(
+                                        "autocomplete_map_titles",
+                                        set_map_title_suggestions,
+                                        autocomplete_query(map_title),
+                                        map_title_sequence,
+                                    );
+                                });
                         }
                         type="text"
                     />
                 </label>
+                <datalist id="map-title-suggestions">
+                    <For
+                        each=move || map_title_suggestions.get()
+                        key=|title| title.clone()
+                        children=|title| view! { <option value=title /> }
+                    />
+                </datalist>
             </div>
             <div class="col-span-1"></div>
             <div class="col-span-3">
@@ -75,31 +196,249 @@ pub fn StatsByMap() -> impl IntoView {
                     <span class="label">"Player"</span>
                     <input
                         class="input input-sm my-0 mx-0"
-                        value=move || player_name.get()
-                        on:input=move |_| {
-                            trigger_fetch_query_map_stats(map_stats_data, query);
+                        list="player-name-suggestions"
+                        prop:value=move || query.get().player_name
+                        on:input=move |ev| {
+                            let player_name = event_target_value(&ev);
+                            set_query
+                                .update(|query| {
+                                    query.player_name = player_name.clone();
+                                    query.page = 0;
+                                });
+                            // The responses of the requests in flight are for older text.
+                            map_stats_sequence.next();
+                            player_name_debouncer
+                                .call(move || {
+                                    fetch_map_stats();
+                                    trigger_autocomplete(
+                                        "autocomplete_player_names",
+                                        set_player_name_suggestions,
+                                        autocomplete_query(player_name),
+                                        player_name_sequence,
+                                    );
+                                });
                         }
                         type="text"
                     />
                 </label>
+                <datalist id="player-name-suggestions">
+                    <For
+                        each=move || player_name_suggestions.get()
+                        key=|name| name.clone()
+                        children=|name| view! { <option value=name /> }
+                    />
+                </datalist>
+            </div>
+            <div class="col-span-3">
+                <Show when=move || {
+                    build_labels_response.get().meta.success
+                        && !build_labels.get().labels.is_empty()
+                }>
+                    <label class="select select-sm">
+                        <span class="label">"Opening"</span>
+                        <select on:change=move |ev| {
+                            set_query
+                                .update(|query| {
+                                    query.opening = event_target_value(&ev);
+                                    query.page = 0;
+                                });
+                            fetch_map_stats();
+                        }>
+                            <option value="" selected=move || query.get().opening.is_empty()>
+                                "Any"
+                            </option>
+                            <For
+                                each=move || build_labels.get().labels
+                                key=|label| label.label.clone()
+                                children=move |label| {
+                                    let value = label.label.clone();
+                                    view! {
+                                        <option
+                                            value=label.label.clone()
+                                            selected=move || query.get().opening == value
+                                        >
+                                            {format!("{} ({})", label.label, label.count)}
+                                        </option>
+                                    }
+                                }
+                            />
+                        </select>
+                    </label>
+                </Show>
             </div>
-            <div class="col-span-3"></div>
             <Show when=move || {
                 !backend_response.get().meta.success && backend_response.get().meta.is_complete
             }>
-                <div role="alert" class="alert alert-error shadow-lg m-1 p-1">
+                <div role="alert" class="alert alert-error shadow-lg m-1 p-1 col-span-8">
                     <Icon icon=X_CIRCLE weight=IconWeight::Bold prop:class="stroke-current" />
                     <span>{backend_response.get().message.clone()}</span>
                 </div>
             </Show>
-            <Show when=move || { map_stats.get().total > 0 }>
-                <MapStatsDataTable map_stats_data />
+            <Show when=move || { map_stats_data.total().get() > 0 }>
+                <div class="col-span-8">
+                    <MapStatsDataTable map_stats_data />
+                    <div class="flex justify-center items-center gap-2 m-1">
+                        <button
+                            class="btn btn-sm btn-ghost"
+                            disabled=move || map_stats_data.start().get() == 0
+                            on:click=move |_| change_page(-1)
+                            title="Previous page"
+                        >
+                            <Icon icon=CARET_LEFT weight=IconWeight::Bold prop:class="stroke-current" />
+                        </button>
+                        <span class="text-neutral-content text-sm">
+                            {move || {
+                                format!(
+                                    "{}-{} of {}",
+                                    map_stats_data.start().get() + 1,
+                                    map_stats_data.end().get(),
+                                    map_stats_data.total().get(),
+                                )
+                            }}
+                        </span>
+                        <button
+                            class="btn btn-sm btn-ghost"
+                            disabled=move || {
+                                map_stats_data.end().get() >= map_stats_data.total().get()
+                            }
+                            on:click=move |_| change_page(1)
+                            title="Next page"
+                        >
+                            <Icon icon=CARET_RIGHT weight=IconWeight::Bold prop:class="stroke-current" />
+                        </button>
+                    </div>
+                    <ExportMenu source=Signal::derive(move || ExportSource::MapStats(query.get())) />
+                </div>
             </Show>
         </div>
     }
 }
 
 #[component]
-pub fn MapStatsDataTable(map_stats_data: Store<MapStatsDataFrame>) -> impl IntoView {
-    view! {}
+pub fn MapStatsDataTable(map_stats_data: Store<MapStatsTable>) -> impl IntoView {
+    view! {
+        <table class="table bg-gray-500 table-xs table-zebra rounded-box">
+            <thead class="bg-gray-700">
+                <tr>
+                    <th></th>
+                    <th>Map Title</th>
+                    <th>Cache Handles</th>
+                    <th>Total Games</th>
+                    <th>First Played</th>
+                    <th>Last Played</th>
+                    <For
+                        each=move || map_stats_data.matchup_columns().get()
+                        key=|matchup| matchup.clone()
+                        children=|matchup| {
+                            view! { <th title="Win rate, 1v1 games">{matchup}</th> }
+                        }
+                    />
+                </tr>
+            </thead>
+            <tbody>
+                <For
+                    each=move || map_stats_data.rows()
+                    key=|row| row.read().key.clone()
+                    children=move |child| {
+                        let navigation = expect_context::<Navigation>();
+                        let idx = child.clone().idx();
+                        let title = child.clone().title();
+                        let title_link = title.clone();
+                        let cache_handles = child.clone().cache_handles();
+                        let cache_handles_title = cache_handles.clone();
+                        let num_games = child.clone().num_games();
+                        let min_date = child.clone().min_date();
+                        let max_date = child.clone().max_date();
+                        let matchups = child.clone().matchups();
+                        view! {
+                            <tr>
+                                <th>{move || idx.get()}</th>
+                                <td>
+                                    <a
+                                        class="link link-hover"
+                                        title="Show the versions of this map"
+                                        on:click=move |_| navigation.open_map_versions(title_link.get())
+                                    >
+                                        {move || title.get()}
+                                    </a>
+                                </td>
+                                <td class="truncate max-w-xs" title=move || cache_handles_title.get()>
+                                    {move || cache_handles.get()}
+                                </td>
+                                <td>{move || num_games.get()}</td>
+                                <td>{move || min_date.get()}</td>
+                                <td>{move || max_date.get()}</td>
+                                {move || {
+                                    let matchups = matchups.get();
+                                    map_stats_data
+                                        .matchup_columns()
+                                        .get()
+                                        .into_iter()
+                                        .map(|column| {
+                                            let matchup = matchups
+                                                .iter()
+                                                .find(|matchup| matchup.matchup == column)
+                                                .cloned();
+                                            view! { <MatchupWinRateCell matchup /> }
+                                        })
+                                        .collect_view()
+                                }}
+                            </tr>
+                        }
+                    }
+                />
+            </tbody>
+        </table>
+    }
+}
+
+/// A cell of the win rate matrix, colored by whether the confidence interval is above or below
+/// an even win rate, with the change from a previous version of the map if any.
+#[component]
+pub fn MatchupWinRateCell(
+    matchup: Option<MatchupWinRate>,
+    #[prop(optional_no_strip)] shift: Option<MatchupShift>,
+) -> impl IntoView {
+    let Some(matchup) = matchup else {
+        return view! { <td></td> }.into_any();
+    };
+    let class = if matchup.ci_low > 0.5 {
+        "text-success"
+    } else if matchup.ci_high < 0.5 {
+        "text-error"
+    } else {
+        ""
+    };
+    let title = format!(
+        "{} vs {}: {} wins of {} games, 95% CI {:.0}%-{:.0}%",
+        matchup.race,
+        matchup.opponent_race,
+        matchup.wins,
+        matchup.games,
+        matchup.ci_low * 100.0,
+        matchup.ci_high * 100.0,
+    );
+    view! {
+        <td class=class title=title>
+            {format!("{:.0}%", matchup.win_rate() * 100.0)}
+            <span class="text-xs text-gray-300">{format!(" n={}", matchup.games)}</span>
+            {shift
+                .map(|shift| {
+                    let class = match (shift.significant, shift.delta >= 0.0) {
+                        (false, _) => "text-xs text-gray-300",
+                        (true, true) => "text-xs text-success",
+                        (true, false) => "text-xs text-error",
+                    };
+                    view! {
+                        <span
+                            class=class
+                            title="Change from the previous version, highlighted when the confidence intervals do not overlap"
+                        >
+                            {format!(" {:+.0}%", shift.delta * 100.0)}
+                        </span>
+                    }
+                })}
+        </td>
+    }
+    .into_any()
 }
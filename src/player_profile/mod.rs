@@ -0,0 +1,3 @@
+//! Player profile module.
+
+pub mod view;
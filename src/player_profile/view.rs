@@ -0,0 +1,374 @@
+//! Leptos view for the player profile.
+use crate::export::view::ExportMenu;
+use crate::*;
+use chrono::{Datelike, Duration, NaiveDate};
+use leptos::leptos_dom::logging::console_log;
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use phosphor_leptos::{Icon, IconWeight, MAGNIFYING_GLASS, X_CIRCLE};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use swarmy_tauri_common::*;
+
+/// The arguments of the query_player_profile command.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct QueryPlayerProfileArgs {
+    query: PlayerProfileQuery,
+}
+
+fn trigger_fetch_player_profile(
+    profile: WriteSignal<PlayerProfile>,
+    query: PlayerProfileQuery,
+    backend_response: WriteSignal<ResponseStatus>,
+) {
+    if query.replay_paths.is_empty() || query.player.is_empty() {
+        console_log("Replay path or player is empty.");
+        return;
+    }
+    spawn_local(async move {
+        console_log(&format!(
+            "Invoking query_player_profile with query: {:?}",
+            query
+        ));
+        let res = invoke_command::<_, PlayerProfile>(
+            "query_player_profile",
+            &QueryPlayerProfileArgs { query },
+        )
+        .await;
+        backend_response.set(ResponseStatus::from(&res));
+        match res.into_result() {
+            Ok(res) => profile.set(res),
+            Err(e) => {
+                console_log(&format!("Error invoking query_player_profile: {}", e));
+                profile.set(PlayerProfile::default());
+            }
+        }
+    });
+}
+
+#[component]
+pub fn PlayerProfilePage() -> impl IntoView {
+    let navigation = expect_context::<Navigation>();
+    let (replay_paths, set_replay_paths) = signal(Vec::<String>::new());
+    let (player_input, set_player_input) = signal(navigation.profile_player.get_untracked());
+    let (profile, set_profile) = signal(PlayerProfile::default());
+    let (backend_response, set_backend_response) = signal(ResponseStatus::incomplete());
+
+    spawn_local(async move {
+        // The snapshot location is part of the app settings.
+        match invoke_command_without_args::<AppSettings>("get_current_app_config")
+            .await
+            .into_result()
+        {
+            Ok(config) => set_replay_paths.set(config.query_replay_paths),
+            Err(e) => {
+                console_log(&format!("Error invoking get_current_app_config: {:?}", e));
+            }
+        }
+    });
+    navigation.on_snapshot_updated(
+        move || replay_paths.get_untracked(),
+        move || {
+            trigger_fetch_player_profile(
+                set_profile,
+                PlayerProfileQuery {
+                    replay_paths: replay_paths.get_untracked(),
+                    player: navigation.profile_player.get_untracked(),
+                },
+                set_backend_response,
+            )
+        },
+    );
+    // Fetches the profile whenever another player is opened, from this page or any other.
+    Effect::new(move |_| {
+        let player = navigation.profile_player.get();
+        set_player_input.set(player.clone());
+        trigger_fetch_player_profile(
+            set_profile,
+            PlayerProfileQuery {
+                replay_paths: replay_paths.get(),
+                player,
+            },
+            set_backend_response,
+        );
+    });
+
+    view! {
+        <div class="grid grid-cols-8 gap-1">
+            <div class="col-span-4">
+                <label class="input input-sm w-full">
+                    <span class="label">"Player"</span>
+                    <input
+                        class="input input-sm my-0 mx-0"
+                        placeholder="Name or toon handle, i.e. 2-S2-1-12345"
+                        prop:value=move || player_input.get()
+                        on:input=move |ev| set_player_input.set(event_target_value(&ev))
+                        on:keydown=move |ev| {
+                            if ev.key() == "Enter" {
+                                navigation.profile_player.set(player_input.get_untracked());
+                            }
+                        }
+                        type="text"
+                    />
+                </label>
+            </div>
+            <div class="col-span-1">
+                <button
+                    class="btn btn-sm btn-ghost"
+                    title="Search"
+                    on:click=move |_| navigation.profile_player.set(player_input.get_untracked())
+                >
+                    <Icon icon=MAGNIFYING_GLASS weight=IconWeight::Bold prop:class="stroke-current" />
+                </button>
+            </div>
+            <div class="col-span-3"></div>
+            <Show when=move || {
+                !backend_response.get().meta.success && backend_response.get().meta.is_complete
+            }>
+                <div role="alert" class="alert alert-error shadow-lg m-1 p-1 col-span-8">
+                    <Icon icon=X_CIRCLE weight=IconWeight::Bold prop:class="stroke-current" />
+                    <span>{backend_response.get().message.clone()}</span>
+                </div>
+            </Show>
+            <Show when=move || { profile.get().record.games > 0 }>
+                <div class="col-span-8">
+                    <PlayerProfileView profile />
+                    <ExportMenu source=Signal::derive(move || {
+                        ExportSource::PlayerGames(PlayerProfileQuery {
+                            replay_paths: replay_paths.get(),
+                            player: navigation.profile_player.get(),
+                        })
+                    }) />
+                </div>
+            </Show>
+        </div>
+    }
+}
+
+#[component]
+pub fn PlayerProfileView(profile: ReadSignal<PlayerProfile>) -> impl IntoView {
+    let navigation = expect_context::<Navigation>();
+    view! {
+        <h2 class="text-xl text-neutral-content">
+            <span class="text-gray-400">{move || profile.get().clan}</span>
+            " "
+            {move || profile.get().name}
+        </h2>
+        <div class="text-xs text-gray-400">{move || profile.get().toon_handles.join(", ")}</div>
+        <div class="stats shadow m-1">
+            <div class="stat">
+                <div class="stat-title">"Games"</div>
+                <div class="stat-value">{move || profile.get().record.games}</div>
+            </div>
+            <div class="stat">
+                <div class="stat-title">"Wins"</div>
+                <div class="stat-value text-success">{move || profile.get().record.wins}</div>
+            </div>
+            <div class="stat">
+                <div class="stat-title">"Losses"</div>
+                <div class="stat-value text-error">{move || profile.get().record.losses}</div>
+            </div>
+            <div class="stat">
+                <div class="stat-title">"Win Rate"</div>
+                <div class="stat-value">
+                    {move || format!("{:.1}%", profile.get().record.win_rate())}
+                </div>
+            </div>
+        </div>
+        <h3 class="text-neutral-content bg-gray-800">"Activity"</h3>
+        <ActivityCalendar activity=Signal::derive(move || profile.get().activity) />
+        <div class="flex gap-4">
+            <div class="flex-item grow">
+                <h3 class="text-neutral-content flex justify-center bg-gray-800">"By Race"</h3>
+                <PlayerRecordTable
+                    label="Race"
+                    rows=Signal::derive(move || {
+                        profile
+                            .get()
+                            .races
+                            .into_iter()
+                            .map(|race| (race.race, race.record))
+                            .collect()
+                    })
+                />
+            </div>
+            <div class="flex-item grow">
+                <h3 class="text-neutral-content flex justify-center bg-gray-800">
+                    "Favourite Maps"
+                </h3>
+                <PlayerRecordTable
+                    label="Map Title"
+                    rows=Signal::derive(move || {
+                        profile
+                            .get()
+                            .favourite_maps
+                            .into_iter()
+                            .map(|map| (map.title, map.record))
+                            .collect()
+                    })
+                />
+            </div>
+            <Show when=move || !profile.get().openings.is_empty()>
+                <div class="flex-item grow">
+                    <h3 class="text-neutral-content flex justify-center bg-gray-800">
+                        "Openings"
+                    </h3>
+                    <PlayerRecordTable
+                        label="Opening"
+                        rows=Signal::derive(move || {
+                            profile
+                                .get()
+                                .openings
+                                .into_iter()
+                                .map(|opening| (opening.label, opening.record))
+                                .collect()
+                        })
+                    />
+                </div>
+            </Show>
+            <div class="flex-item grow">
+                <h3 class="text-neutral-content flex justify-center bg-gray-800">
+                    "Frequent Opponents"
+                </h3>
+                <table class="table bg-gray-500 table-xs table-zebra rounded-box">
+                    <thead class="bg-gray-700">
+                        <tr>
+                            <th>Clan</th>
+                            <th>Name</th>
+                            <th>Games</th>
+                            <th>W-L</th>
+                        </tr>
+                    </thead>
+                    <tbody>
+                        <For
+                            each=move || profile.get().opponents
+                            key=|row| format!("{}-{}", row.clan, row.name)
+                            children=move |row| {
+                                let name = row.name.clone();
+                                view! {
+                                    <tr>
+                                        <td>{row.clan}</td>
+                                        <td>
+                                            <a
+                                                class="link link-hover"
+                                                on:click=move |_| {
+                                                    navigation.open_player_profile(name.clone())
+                                                }
+                                            >
+                                                {row.name}
+                                            </a>
+                                        </td>
+                                        <td>{row.record.games}</td>
+                                        <td>{format!("{}-{}", row.record.wins, row.record.losses)}</td>
+                                    </tr>
+                                }
+                            }
+                        />
+                    </tbody>
+                </table>
+            </div>
+        </div>
+    }
+}
+
+#[component]
+pub fn PlayerRecordTable(
+    label: &'static str,
+    rows: Signal<Vec<(String, PlayerRecord)>>,
+) -> impl IntoView {
+    view! {
+        <table class="table bg-gray-500 table-xs table-zebra rounded-box">
+            <thead class="bg-gray-700">
+                <tr>
+                    <th>{label}</th>
+                    <th>Games</th>
+                    <th>W-L</th>
+                    <th>Win Rate</th>
+                </tr>
+            </thead>
+            <tbody>
+                <For
+                    each=move || rows.get()
+                    key=|(key, _)| key.clone()
+                    children=|(key, record)| {
+                        view! {
+                            <tr>
+                                <td>{key}</td>
+                                <td>{record.games}</td>
+                                <td>{format!("{}-{}", record.wins, record.losses)}</td>
+                                <td>{format!("{:.1}%", record.win_rate())}</td>
+                            </tr>
+                        }
+                    }
+                />
+            </tbody>
+        </table>
+    }
+}
+
+/// The number of weeks shown by the activity calendar.
+const ACTIVITY_CALENDAR_WEEKS: i64 = 53;
+
+/// The background of a day in the activity calendar by the number of games played.
+fn activity_day_class(games: u64) -> &'static str {
+    match games {
+        0 => "w-3 h-3 rounded-sm bg-gray-700",
+        1 => "w-3 h-3 rounded-sm bg-green-900",
+        2..=3 => "w-3 h-3 rounded-sm bg-green-700",
+        4..=6 => "w-3 h-3 rounded-sm bg-green-500",
+        _ => "w-3 h-3 rounded-sm bg-green-300",
+    }
+}
+
+/// The games played per day over the year before the last active day, one column per week.
+#[component]
+pub fn ActivityCalendar(activity: Signal<Vec<PlayerActivityDay>>) -> impl IntoView {
+    let weeks = move || {
+        let activity = activity.get();
+        let Some(end) = activity.last().map(|day| day.date) else {
+            return vec![];
+        };
+        let games: HashMap<NaiveDate, u64> = activity
+            .into_iter()
+            .map(|day| (day.date, day.games))
+            .collect();
+        let start = end - Duration::weeks(ACTIVITY_CALENDAR_WEEKS - 1);
+        // Weeks start on Monday.
+        let start = start - Duration::days(start.weekday().num_days_from_monday() as i64);
+        (0..ACTIVITY_CALENDAR_WEEKS)
+            .map(|week| {
+                (0..7)
+                    .map(|day| start + Duration::days(week * 7 + day))
+                    .filter(|date| *date <= end)
+                    .map(|date| (date, games.get(&date).copied().unwrap_or(0)))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>()
+    };
+    view! {
+        <div class="flex gap-1 m-1 overflow-x-auto">
+            {move || {
+                weeks()
+                    .into_iter()
+                    .map(|week| {
+                        view! {
+                            <div class="flex flex-col gap-1">
+                                {week
+                                    .into_iter()
+                                    .map(|(date, games)| {
+                                        view! {
+                                            <div
+                                                class=activity_day_class(games)
+                                                title=format!("{}: {} games", date, games)
+                                            ></div>
+                                        }
+                                    })
+                                    .collect_view()}
+                            </div>
+                        }
+                    })
+                    .collect_view()
+            }}
+        </div>
+    }
+}
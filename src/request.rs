@@ -0,0 +1,70 @@
+//! Sequencing of the requests sent while the user types.
+use leptos::leptos_dom::logging::console_log;
+use leptos::prelude::*;
+use std::time::Duration;
+
+/// How long the inputs wait for the typing to pause before querying the backend.
+pub const INPUT_DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Tags the requests of an input with increasing ids so that the responses of the requests
+/// superseded by a newer one are dropped, whatever order they arrive in.
+#[derive(Debug, Clone, Copy)]
+pub struct RequestSequence {
+    latest: StoredValue<u64>,
+}
+
+impl Default for RequestSequence {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RequestSequence {
+    pub fn new() -> Self {
+        Self {
+            latest: StoredValue::new(0),
+        }
+    }
+
+    /// Starts a request and returns its id, the requests started before it become stale.
+    pub fn next(&self) -> u64 {
+        self.latest.update_value(|latest| *latest += 1);
+        self.latest.get_value()
+    }
+
+    /// Whether no request was started after the request `id`.
+    pub fn is_latest(&self, id: u64) -> bool {
+        self.latest.try_get_value() == Some(id)
+    }
+}
+
+/// Delays a callback until the calls stop for a while, only the last call runs.
+#[derive(Debug, Clone, Copy)]
+pub struct Debouncer {
+    delay: Duration,
+    pending: StoredValue<Option<TimeoutHandle>>,
+}
+
+impl Debouncer {
+    pub fn new(delay: Duration) -> Self {
+        let pending = StoredValue::new(None::<TimeoutHandle>);
+        // The callback would use the signals of the view after it is gone.
+        on_cleanup(move || {
+            if let Some(Some(handle)) = pending.try_get_value() {
+                handle.clear();
+            }
+        });
+        Self { delay, pending }
+    }
+
+    /// Runs `callback` after the delay unless `call` is called again meanwhile.
+    pub fn call(&self, callback: impl FnOnce() + 'static) {
+        if let Some(handle) = self.pending.get_value() {
+            handle.clear();
+        }
+        match set_timeout_with_handle(callback, self.delay) {
+            Ok(handle) => self.pending.set_value(Some(handle)),
+            Err(e) => console_log(&format!("Error setting the debounce timeout: {:?}", e)),
+        }
+    }
+}
@@ -2,7 +2,8 @@ use chrono::DateTime;
 use chrono::{NaiveDateTime, Utc};
 use leptos::prelude::*;
 use phosphor_leptos::{
-    Icon, IconWeight, BOXING_GLOVE, CALENDAR_DOT, CALENDAR_STAR, CIRCUITRY, HARD_DRIVE,
+    Icon, IconWeight, BOXING_GLOVE, CALENDAR_DOT, CALENDAR_STAR, CIRCUITRY, GIT_BRANCH, HARD_DRIVE,
+    STACK, USERS,
 };
 use si_scale::helpers::bibytes2;
 use std::time::UNIX_EPOCH;
@@ -121,10 +122,7 @@ pub fn ArrowIpcStats(arrow_ipc_stats: ReadSignal<SnapshotStats>) -> impl IntoVie
                     <div class="stat-value text-primary">
                         {move || bibytes2(arrow_ipc_stats.get().directory_size as u32)}
                     </div>
-                    <div class="stat-desc">
-                        <code>"ipcs/"</code>
-                        " directory"
-                    </div>
+                    <div class="stat-desc">"In the snapshot directory"</div>
                 </div>
             </div>
             <div class="col-span-1"></div>
@@ -146,6 +144,62 @@ pub fn ArrowIpcStats(arrow_ipc_stats: ReadSignal<SnapshotStats>) -> impl IntoVie
                 </div>
             </div>
         </div>
+        <div class="grid grid-cols-7">
+            <div class="col-span-3 stats">
+                <div class="stat shadow">
+                    <div class="stat-figure text-primary">
+                        <Icon
+                            icon=GIT_BRANCH
+                            weight=IconWeight::Bold
+                            size="24px"
+                            prop:class="stroke-current"
+                        />
+                    </div>
+                    <div class="stat-title">"Map versions"</div>
+                    <div class="stat-value text-primary">
+                        {move || arrow_ipc_stats.get().num_map_versions}
+                    </div>
+                    <div class="stat-desc">"(unique cache handles)"</div>
+                </div>
+            </div>
+            <div class="col-span-1"></div>
+            <div class="col-span-3 stats">
+                <div class="stat shadow">
+                    <div class="stat-figure text-primary">
+                        <Icon
+                            icon=USERS
+                            weight=IconWeight::Bold
+                            size="24px"
+                            prop:class="stroke-current"
+                        />
+                    </div>
+                    <div class="stat-title">"Players"</div>
+                    <div class="stat-value text-primary">
+                        {move || arrow_ipc_stats.get().num_players}
+                    </div>
+                    <div class="stat-desc">"(unique toons)"</div>
+                </div>
+            </div>
+        </div>
+        <div class="grid grid-cols-7">
+            <div class="col-span-3 stats">
+                <div class="stat shadow">
+                    <div class="stat-figure text-primary">
+                        <Icon
+                            icon=STACK
+                            weight=IconWeight::Bold
+                            size="24px"
+                            prop:class="stroke-current"
+                        />
+                    </div>
+                    <div class="stat-title">"Game versions"</div>
+                    <div class="stat-value text-primary">
+                        {move || arrow_ipc_stats.get().num_game_versions}
+                    </div>
+                    <div class="stat-desc">"(unique protocol versions)"</div>
+                </div>
+            </div>
+        </div>
         <div class="grid grid-cols-7">
             <div class="col-span-3 stats">
                 <div class="stat shadow">
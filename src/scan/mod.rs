@@ -7,6 +7,7 @@ pub mod view;
 use reactive_stores::Store;
 use s2protocol::cli::SC2ReplaysDirStats;
 use serde::{Deserialize, Serialize};
+use swarmy_tauri_common::split_clan_tag;
 
 #[derive(Store, Debug, Clone, Serialize, Deserialize)]
 pub struct SC2ReplaysDirStatsTable {
@@ -45,12 +46,7 @@ impl From<SC2ReplaysDirStats> for SC2ReplaysDirStatsTable {
                 .into_iter()
                 .enumerate()
                 .map(|(idx, (name, count))| {
-                    let (clan, name) = if let Some((clan, name)) = name.split_once("<sp/>") {
-                        let clan = clan.replace("&gt;", "").replace("&lt;", "");
-                        (clan.to_string(), name.to_string())
-                    } else {
-                        (String::new(), name)
-                    };
+                    let (clan, name) = split_clan_tag(&name);
                     SC2ReplaysDirPlayerEntry {
                         idx: idx + 1,
                         clan,
@@ -2,9 +2,11 @@ use reactive_stores::Store;
 use leptos::prelude::*;
 use super::*;
 use reactive_graph::traits::Read;
+use crate::Navigation;
 
 #[component]
 pub fn ReplayScanTable(dir_stats_data: Store<SC2ReplaysDirStatsTable>) -> impl IntoView {
+    let navigation = expect_context::<Navigation>();
     view! {
         <div class="flex flex-row">
             <div class="flex-item basis-128">
@@ -53,16 +55,27 @@ pub fn ReplayScanTable(dir_stats_data: Store<SC2ReplaysDirStatsTable>) -> impl I
                         <For
                             each=move || dir_stats_data.top_10_players()
                             key=|row| row.read().name.clone()
-                            children=|child| {
+                            children=move |child| {
                                 let idx = child.clone().idx();
                                 let clan = child.clone().clan().clone();
                                 let name = child.clone().name().clone();
+                                let profile_name = name.clone();
                                 let count = child.clone().count();
                                 view! {
                                     <tr>
                                         <th>{move || idx.get()}</th>
                                         <td>{move || clan.get()}</td>
-                                        <td>{move || name.get()}</td>
+                                        <td>
+                                            <a
+                                                class="link link-hover"
+                                                title="Open player profile"
+                                                on:click=move |_| {
+                                                    navigation.open_player_profile(profile_name.get())
+                                                }
+                                            >
+                                                {move || name.get()}
+                                            </a>
+                                        </td>
                                         <td>{move || count.get()}</td>
                                     </tr>
                                 }
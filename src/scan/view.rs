@@ -6,10 +6,13 @@ use leptos::ev::MouseEvent;
 use leptos::leptos_dom::logging::console_log;
 use leptos::prelude::*;
 use leptos::task::spawn_local;
-use phosphor_leptos::{Icon, IconWeight, BARCODE, CPU, DATABASE, FOLDERS, HOURGLASS, X_CIRCLE};
+use phosphor_leptos::{
+    Icon, IconWeight, BARCODE, CPU, DATABASE, FOLDERS, HOURGLASS, STOP, WARNING, X_CIRCLE,
+};
 use reactive_graph::traits::Write;
 use reactive_stores::{Patch, Store};
 use s2protocol::SC2ReplaysDirStats;
+use serde::{Deserialize, Serialize};
 use swarmy_tauri_common::*;
 use super::mpq_file_scan::ReplayScanTable;
 use super::arrow_ipc_stats::ArrowIpcStats;
@@ -18,11 +21,11 @@ pub fn trigger_optimize_replay_path(
     ev: MouseEvent,
     app_settings: ReadSignal<AppSettings>,
     set_optimize_button_enabled: WriteSignal<bool>,
-    backend_response: WriteSignal<ApiResponse>,
+    backend_response: WriteSignal<ResponseStatus>,
 ) {
     ev.prevent_default();
     // Reset backend response status.
-    *backend_response.write() = ApiResponse::new_incomplete();
+    *backend_response.write() = ResponseStatus::incomplete();
 
     if app_settings.get_untracked().replay_path.is_empty() {
         console_log("Replay path is empty.");
@@ -32,48 +35,97 @@ pub fn trigger_optimize_replay_path(
 
     let app_settings_cp = app_settings.get_untracked();
     spawn_local(async move {
-        let args = serde_wasm_bindgen::to_value(&app_settings_cp).unwrap();
         console_log(&format!(
             "Invoking optimize_replay_path with args: {:?}",
-            args
+            app_settings_cp
         ));
         // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
-        let response = invoke("optimize_replay_path", args).await;
-        console_log(&format!("optimize_replay_path response: {:?}", response));
-        match serde_wasm_bindgen::from_value::<ApiResponse>(response) {
-            Ok(res) => {
-                if res.meta.success {
-                    console_log("Optimize replay path succeeded.");
-                } else {
-                    console_log(&format!("Optimize replay path failed: {:?}", res.message));
-                }
-                backend_response.set(res);
-                set_optimize_button_enabled.set(true);
-            }
+        let res = invoke_command::<_, String>("optimize_replay_path", &app_settings_cp).await;
+        match &res.error {
+            None => console_log("Optimize replay path succeeded."),
+            Some(e) => console_log(&format!("Optimize replay path failed: {}", e)),
+        }
+        backend_response.set(ResponseStatus::from(&res));
+        set_optimize_button_enabled.set(true);
+    });
+}
+
+/// The arguments of the set_snapshot_dir command.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SetSnapshotDirArgs {
+    snapshot_dir: String,
+}
+
+/// Moves the snapshots to `snapshot_dir`, empty for the default, and applies the settings the
+/// command returns.
+fn trigger_set_snapshot_dir(
+    snapshot_dir: String,
+    set_app_settings: WriteSignal<AppSettings>,
+    backend_response: WriteSignal<ResponseStatus>,
+) {
+    backend_response.set(ResponseStatus::incomplete());
+    spawn_local(async move {
+        let res = invoke_command::<_, AppSettings>(
+            "set_snapshot_dir",
+            &SetSnapshotDirArgs { snapshot_dir },
+        )
+        .await;
+        backend_response.set(ResponseStatus::from(&res));
+        match res.into_result() {
+            Ok(config) => set_app_settings.set(config),
+            Err(e) => console_log(&format!("Error invoking set_snapshot_dir: {}", e)),
+        }
+    });
+}
+
+/// The arguments of the set_replay_watcher command.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SetReplayWatcherArgs {
+    enabled: bool,
+}
+
+/// Starts or stops adding the new replays of the saved replay path to its snapshot.
+fn trigger_set_replay_watcher(
+    enabled: bool,
+    set_app_settings: WriteSignal<AppSettings>,
+    backend_response: WriteSignal<ResponseStatus>,
+) {
+    backend_response.set(ResponseStatus::incomplete());
+    spawn_local(async move {
+        let res = invoke_command::<_, AppSettings>(
+            "set_replay_watcher",
+            &SetReplayWatcherArgs { enabled },
+        )
+        .await;
+        backend_response.set(ResponseStatus::from(&res));
+        // Only the watcher changed, the path being edited is kept, the toggle is reverted on errors.
+        let watch_replay_path = match res.into_result() {
+            Ok(config) => config.watch_replay_path,
             Err(e) => {
-                console_log(&format!("Error invoking optimize_replay_path: {:?}", e));
-                set_optimize_button_enabled.set(true);
-                backend_response.set(ApiResponse {
-                    meta: ResponseMeta {
-                        success: false,
-                        duration_ms: 0,
-                        is_complete: true,
-                    },
-                    message: format!("Error invoking optimize_replay_path: {:?}", e),
-                });
+                console_log(&format!("Error invoking set_replay_watcher: {}", e));
+                !enabled
             }
-        }
+        };
+        set_app_settings.update(|settings| settings.watch_replay_path = watch_replay_path);
+    });
+}
+
+pub fn trigger_cancel_optimize(ev: MouseEvent) {
+    ev.prevent_default();
+    spawn_local(async move {
+        let res = invoke_command_without_args::<()>("cancel_optimize").await;
+        console_log(&format!("cancel_optimize response: {:?}", res));
     });
 }
 
 pub fn trigger_basic_scan_replay_path(
     ev: MouseEvent,
     app_settings: ReadSignal<AppSettings>,
-    backend_response: WriteSignal<ApiResponse>,
+    backend_response: WriteSignal<ResponseStatus>,
     data: Store<SC2ReplaysDirStatsTable>,
 ) {
     ev.prevent_default();
-    *backend_response.write() = ApiResponse::new_incomplete();
+    *backend_response.write() = ResponseStatus::incomplete();
     if app_settings.get().replay_path.is_empty() {
         console_log("Replay path is empty.");
         return;
@@ -82,11 +134,11 @@ pub fn trigger_basic_scan_replay_path(
     let app_settings_cp = app_settings.get_untracked();
 
     spawn_local(async move {
-        let args = serde_wasm_bindgen::to_value(&app_settings_cp).unwrap();
         // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
-        match serde_wasm_bindgen::from_value::<SC2ReplaysDirStats>(
-            invoke("basic_scan_replay_path", args).await,
-        ) {
+        let res = invoke_command::<_, SC2ReplaysDirStats>("basic_scan_replay_path", &app_settings_cp)
+            .await;
+        backend_response.set(ResponseStatus::from(&res));
+        match res.into_result() {
             Ok(stats) => {
                 let mut stats_table: SC2ReplaysDirStatsTable = stats.into();
                 console_log(&format!("New data = {:?}", stats_table));
@@ -105,7 +157,7 @@ pub fn trigger_basic_scan_replay_path(
                     .patch(stats_table.ability_supported_replays);
             }
             Err(e) => {
-                console_log(&format!("Error invoking basic_scan_replay_path: {:?}", e));
+                console_log(&format!("Error invoking basic_scan_replay_path: {}", e));
             }
         }
     });
@@ -116,17 +168,30 @@ pub fn ScanDirectory() -> impl IntoView {
     let (app_settings, set_app_settings) = signal(AppSettings::default());
     let (optimize_button_enabled, set_optimize_button_enabled) = signal(false);
     let (disable_parallel_scans, set_disable_parallel_scans) = signal(false);
-    let (backend_response, set_backend_response) = signal(ApiResponse {
-        meta: ResponseMeta::incomplete(),
-        message: String::new(),
-    });
+    let (backend_response, set_backend_response) = signal(ResponseStatus::incomplete());
     let (arrow_ipc_stats, set_arrow_ipc_stats) = signal(SnapshotStats::default());
+    let (optimize_progress, set_optimize_progress) = signal(OptimizeProgress::default());
+    let unlisten_optimize_progress = StoredValue::new_local(None::<js_sys::Function>);
+
+    spawn_local(async move {
+        let unlisten = listen_event(OPTIMIZE_PROGRESS_EVENT, move |progress: OptimizeProgress| {
+            set_optimize_progress.set(progress);
+        })
+        .await;
+        unlisten_optimize_progress.set_value(Some(unlisten));
+    });
+    on_cleanup(move || {
+        if let Some(unlisten) = unlisten_optimize_progress.get_value() {
+            let _ = unlisten.call0(&wasm_bindgen::JsValue::NULL);
+        }
+    });
 
     spawn_local(async move {
         // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
-        match serde_wasm_bindgen::from_value::<AppSettings>(
-            invoke_without_args("get_current_app_config").await,
-        ) {
+        match invoke_command_without_args::<AppSettings>("get_current_app_config")
+            .await
+            .into_result()
+        {
             Ok(config) => {
                 console_log(&format!("Loaded app config: {:?}", config));
                 *set_arrow_ipc_stats.write() = config.arrow_ipc_stats.clone();
@@ -198,12 +263,16 @@ pub fn ScanDirectory() -> impl IntoView {
                             "btn btn-disabled btn-sm m-0"
                         }
                     }
-                    on:click=move |ev: MouseEvent| trigger_optimize_replay_path(
-                        ev,
-                        app_settings,
-                        set_optimize_button_enabled,
-                        set_backend_response,
-                    )
+                    on:click=move |ev: MouseEvent| {
+                        // The optimized snapshot is current, failures show their own error.
+                        set_app_settings.update(|settings| settings.snapshot_outdated = None);
+                        trigger_optimize_replay_path(
+                            ev,
+                            app_settings,
+                            set_optimize_button_enabled,
+                            set_backend_response,
+                        )
+                    }
                     disabled=move || !optimize_button_enabled.get()
                     title="Optimize the replay generating Arrow files (may take some time)"
                 >
@@ -218,8 +287,54 @@ pub fn ScanDirectory() -> impl IntoView {
                         }
                     }}
                 </button>
+                <Show when=move || {
+                    !app_settings.get().replay_path.is_empty() && !optimize_button_enabled.get()
+                }>
+                    <button
+                        class="btn btn-error btn-sm m-0"
+                        on:click=trigger_cancel_optimize
+                        title="Stop the optimization, the current snapshot is kept"
+                    >
+                        <Icon icon=STOP weight=IconWeight::Light prop:class="stroke-current" />
+                        "Cancel"
+                    </button>
+                </Show>
             </div>
-            <div class="col-span-1 flex justify-end">
+            <div class="col-span-1 flex justify-end gap-1">
+                <label
+                    class="label text-xs"
+                    title="Only process the replays added or changed since the last optimize"
+                >
+                    <input
+                        type="checkbox"
+                        class="toggle toggle-xs toggle-success"
+                        checked=move || app_settings.get().optimize_settings.incremental
+                        on:change=move |ev| {
+                            let checked = event_target_checked(&ev);
+                            set_app_settings
+                                .update(|settings| settings.optimize_settings.incremental = checked);
+                        }
+                    />
+                    "Incremental"
+                </label>
+                <label
+                    class="label text-xs"
+                    title="Add the new replays of the folder to its optimized snapshot as they are saved"
+                >
+                    <input
+                        type="checkbox"
+                        class="toggle toggle-xs toggle-success"
+                        prop:checked=move || app_settings.get().watch_replay_path
+                        on:change=move |ev| {
+                            trigger_set_replay_watcher(
+                                event_target_checked(&ev),
+                                set_app_settings,
+                                set_backend_response,
+                            )
+                        }
+                    />
+                    "Watch"
+                </label>
                 <label
                     class="btn btn-sm btn-circle swap swap-rotate"
                     title=move || {
@@ -259,6 +374,12 @@ pub fn ScanDirectory() -> impl IntoView {
                 </label>
             </div>
         </div>
+        <OptimizeSettingsPanel app_settings set_app_settings set_backend_response />
+        <Show when=move || {
+            !app_settings.get().replay_path.is_empty() && !optimize_button_enabled.get()
+        }>
+            <OptimizeProgressBar optimize_progress />
+        </Show>
         <Show when=move || {
             !backend_response.get().meta.success && backend_response.get().meta.is_complete
         }>
@@ -275,13 +396,20 @@ pub fn ScanDirectory() -> impl IntoView {
                 <Icon icon=FOLDERS weight=IconWeight::Bold prop:class="stroke-current" />
                 <span>
                     "Directory is not optimized, click on Optimize to generate the optimized snapshot, this may take a while. "
-                    "A subdirectory named "<b>
-                        <code>"ipc"</code>
-                    </b>" will be created in the chosen folder with the optimized snapshot."
+                    "The snapshot is written to the snapshot directory of the Advanced settings, the replay folder is not modified."
                 </span>
             </div>
             <ReplayScanTable dir_stats_data />
         </Show>
+        <Show when=move || app_settings.get().snapshot_outdated.is_some()>
+            <div role="alert" class="alert alert-warning alert-soft m-1 p-1">
+                <Icon icon=WARNING weight=IconWeight::Bold prop:class="stroke-current" />
+                <span>
+                    {move || app_settings.get().snapshot_outdated.unwrap_or_default()}
+                    ". Click on Optimize to write the snapshot again."
+                </span>
+            </div>
+        </Show>
         <Show when=move || { app_settings.get().arrow_ipc_stats.directory_size > 0 }>
             <div role="alert" class="alert alert-success alert-soft m-1 p-1">
                 <Icon icon=DATABASE weight=IconWeight::Bold prop:class="stroke-current" />
@@ -291,3 +419,184 @@ pub fn ScanDirectory() -> impl IntoView {
         </Show>
     }
 }
+
+#[component]
+pub fn OptimizeProgressBar(optimize_progress: ReadSignal<OptimizeProgress>) -> impl IntoView {
+    view! {
+        <div class="m-1 p-1">
+            <progress
+                class="progress progress-success w-full"
+                value=move || optimize_progress.get().percent()
+                max="100"
+            ></progress>
+            <div class="flex flex-row gap-4 text-sm text-neutral-content">
+                <span>{move || format!("{:?}", optimize_progress.get().stage)}</span>
+                <span>
+                    "Discovered: "
+                    <div class="badge badge-sm badge-ghost">
+                        {move || optimize_progress.get().files_discovered}
+                    </div>
+                </span>
+                <span>
+                    "Parsed: "
+                    <div class="badge badge-sm badge-info">
+                        {move || optimize_progress.get().files_parsed}
+                    </div>
+                </span>
+                <span>
+                    "Failed: "
+                    <div class="badge badge-sm badge-warning">
+                        {move || optimize_progress.get().files_failed}
+                    </div>
+                </span>
+                <Show when=move || !optimize_progress.get().current_ipc.is_empty()>
+                    <span>
+                        "Writing "
+                        <code>{move || optimize_progress.get().current_ipc}</code>
+                        {move || {
+                            format!(
+                                " ({}/{})",
+                                optimize_progress.get().ipcs_written + 1,
+                                optimize_progress.get().ipcs_total,
+                            )
+                        }}
+                    </span>
+                </Show>
+            </div>
+        </div>
+    }
+}
+
+#[component]
+pub fn OptimizeSettingsPanel(
+    app_settings: ReadSignal<AppSettings>,
+    set_app_settings: WriteSignal<AppSettings>,
+    set_backend_response: WriteSignal<ResponseStatus>,
+) -> impl IntoView {
+    let parse_limit = |ev: leptos::ev::Event, current: usize| {
+        event_target_value(&ev).parse::<usize>().unwrap_or(current)
+    };
+    let parse_version = |ev: leptos::ev::Event| event_target_value(&ev).parse::<u32>().ok();
+    view! {
+        <div class="collapse collapse-arrow bg-gray-900 m-1">
+            <input type="checkbox" />
+            <div class="collapse-title text-sm text-neutral-content">"Advanced"</div>
+            <div class="collapse-content grid grid-cols-3 gap-1">
+                <label
+                    class="input input-sm col-span-3 w-full"
+                    title="Where the snapshots are written, leave empty for the default in the app data directory. The replay folders must be optimized again after a change."
+                >
+                    <span class="label">"Snapshot directory"</span>
+                    <input
+                        type="text"
+                        class="my-0 mx-0"
+                        value=move || app_settings.get().snapshot_dir
+                        on:change=move |ev| {
+                            trigger_set_snapshot_dir(
+                                event_target_value(&ev),
+                                set_app_settings,
+                                set_backend_response,
+                            )
+                        }
+                    />
+                </label>
+                <label class="input input-sm" title="Files read while recursing, valid or not">
+                    <span class="label">"Scan max files"</span>
+                    <input
+                        type="number"
+                        min="1"
+                        value=move || app_settings.get().optimize_settings.scan_max_files
+                        on:change=move |ev| {
+                            set_app_settings
+                                .update(|settings| {
+                                    let current = settings.optimize_settings.scan_max_files;
+                                    settings.optimize_settings.scan_max_files = parse_limit(
+                                        ev,
+                                        current,
+                                    );
+                                })
+                        }
+                    />
+                </label>
+                <label class="input input-sm" title="Maximum number of replays processed">
+                    <span class="label">"Process max files"</span>
+                    <input
+                        type="number"
+                        min="1"
+                        value=move || app_settings.get().optimize_settings.process_max_files
+                        on:change=move |ev| {
+                            set_app_settings
+                                .update(|settings| {
+                                    let current = settings.optimize_settings.process_max_files;
+                                    settings.optimize_settings.process_max_files = parse_limit(
+                                        ev,
+                                        current,
+                                    );
+                                })
+                        }
+                    />
+                </label>
+                <label class="input input-sm" title="Maximum directory depth traversed">
+                    <span class="label">"Max depth"</span>
+                    <input
+                        type="number"
+                        min="1"
+                        value=move || app_settings.get().optimize_settings.traverse_max_depth
+                        on:change=move |ev| {
+                            set_app_settings
+                                .update(|settings| {
+                                    let current = settings.optimize_settings.traverse_max_depth;
+                                    settings.optimize_settings.traverse_max_depth = parse_limit(
+                                        ev,
+                                        current,
+                                    );
+                                })
+                        }
+                    />
+                </label>
+                <label class="input input-sm" title="Leave empty to process every version">
+                    <span class="label">"Min protocol version"</span>
+                    <input
+                        type="number"
+                        min="0"
+                        value=move || {
+                            app_settings
+                                .get()
+                                .optimize_settings
+                                .min_version
+                                .map(|v| v.to_string())
+                                .unwrap_or_default()
+                        }
+                        on:change=move |ev| {
+                            set_app_settings
+                                .update(|settings| {
+                                    settings.optimize_settings.min_version = parse_version(ev)
+                                })
+                        }
+                    />
+                </label>
+                <label class="input input-sm" title="Leave empty to process every version">
+                    <span class="label">"Max protocol version"</span>
+                    <input
+                        type="number"
+                        min="0"
+                        value=move || {
+                            app_settings
+                                .get()
+                                .optimize_settings
+                                .max_version
+                                .map(|v| v.to_string())
+                                .unwrap_or_default()
+                        }
+                        on:change=move |ev| {
+                            set_app_settings
+                                .update(|settings| {
+                                    settings.optimize_settings.max_version = parse_version(ev)
+                                })
+                        }
+                    />
+                </label>
+            </div>
+        </div>
+    }
+}
@@ -0,0 +1,68 @@
+//! SQL console module.
+
+pub mod view;
+
+use reactive_stores::Store;
+use serde::{Deserialize, Serialize};
+use swarmy_tauri_common::SqlQueryResult;
+
+#[derive(Store, Debug, Default, Clone, Serialize, Deserialize)]
+pub struct SqlQueryTable {
+    pub columns: Vec<SqlQueryColumn>,
+    #[store(key: usize = |row| row.idx)]
+    pub rows: Vec<SqlQueryRow>,
+    pub total: usize,
+    pub truncated: bool,
+    pub start: usize,
+    pub end: usize,
+    pub page: usize,
+    pub per_page: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SqlQueryColumn {
+    pub name: String,
+    pub dtype: String,
+}
+
+#[derive(Store, Debug, Clone, Serialize, Deserialize)]
+pub struct SqlQueryRow {
+    /// The position of the row in the whole result, unique per row.
+    pub idx: usize,
+    pub cells: Vec<String>,
+}
+
+impl From<SqlQueryResult> for SqlQueryTable {
+    fn from(data: SqlQueryResult) -> Self {
+        Self {
+            columns: data
+                .columns
+                .into_iter()
+                .zip(data.dtypes)
+                .map(|(name, dtype)| SqlQueryColumn { name, dtype })
+                .collect(),
+            rows: data
+                .rows
+                .into_iter()
+                .enumerate()
+                .map(|(idx, row)| SqlQueryRow {
+                    idx: data.start + idx + 1,
+                    cells: row
+                        .into_iter()
+                        .map(|value| match value {
+                            serde_json::Value::Null => String::new(),
+                            serde_json::Value::String(s) => s,
+                            other => other.to_string(),
+                        })
+                        .collect(),
+                })
+                .collect(),
+            total: data.total,
+            truncated: data.truncated,
+            start: data.start,
+            end: data.end,
+            page: data.page,
+            per_page: data.per_page,
+        }
+    }
+}
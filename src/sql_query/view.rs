@@ -0,0 +1,308 @@
+//! Leptos view for the SQL console.
+use crate::export::view::ExportMenu;
+use crate::sql_query::*;
+use crate::*;
+use leptos::leptos_dom::logging::console_log;
+use leptos::prelude::*;
+use leptos::task::spawn_local;
+use phosphor_leptos::{Icon, IconWeight, CARET_LEFT, CARET_RIGHT, PLAY, X_CIRCLE};
+use reactive_graph::traits::Read;
+use reactive_stores::Store;
+use serde::{Deserialize, Serialize};
+use swarmy_tauri_common::*;
+
+/// The arguments of the run_sql_query command.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RunSqlQueryArgs {
+    query: SqlQuery,
+}
+
+/// Runs the query and, like the backend, puts its SQL first in the `history` if its first page
+/// ran.
+fn trigger_run_sql_query(
+    data: Store<SqlQueryTable>,
+    query_cp: SqlQuery,
+    history: WriteSignal<Vec<String>>,
+    backend_response: WriteSignal<ResponseStatus>,
+) {
+    if query_cp.replay_paths.is_empty() || query_cp.sql.trim().is_empty() {
+        console_log("Replay path or SQL is empty.");
+        return;
+    }
+    backend_response.set(ResponseStatus::incomplete());
+    spawn_local(async move {
+        console_log(&format!(
+            "Invoking run_sql_query with query: {:?}",
+            query_cp
+        ));
+        let sql = (query_cp.page == 0).then(|| query_cp.sql.clone());
+        let res = invoke_command::<_, SqlQueryResult>(
+            "run_sql_query",
+            &RunSqlQueryArgs { query: query_cp },
+        )
+        .await;
+        backend_response.set(ResponseStatus::from(&res));
+        match res.into_result() {
+            Ok(res) => {
+                *data.write() = res.into();
+                if let Some(sql) = sql {
+                    history.update(|history| {
+                        history.retain(|entry| entry != &sql);
+                        history.insert(0, sql);
+                        history.truncate(SQL_QUERY_HISTORY_SIZE);
+                    });
+                }
+            }
+            Err(e) => {
+                console_log(&format!("Error invoking run_sql_query: {}", e));
+                *data.write() = SqlQueryTable::default();
+            }
+        }
+    });
+}
+
+#[component]
+pub fn QueryConsole() -> impl IntoView {
+    let navigation = expect_context::<Navigation>();
+    let (query, set_query) = signal(SqlQuery {
+        sql: String::from(
+            "SELECT title, COUNT(*) AS players FROM details GROUP BY title ORDER BY players DESC",
+        ),
+        ..Default::default()
+    });
+    let (history, set_history) = signal(Vec::<String>::new());
+    let (backend_response, set_backend_response) = signal(ResponseStatus::default());
+    let query_data = Store::new(SqlQueryTable::default());
+    // The query of the rows shown, the SQL may have been edited since.
+    let shown = StoredValue::new(None::<SqlQuery>);
+    let fetch_page = move |query: SqlQuery| {
+        shown.set_value(Some(query.clone()));
+        trigger_run_sql_query(query_data, query, set_history, set_backend_response);
+    };
+
+    spawn_local(async move {
+        // The snapshot location and the query history are part of the app settings.
+        match invoke_command_without_args::<AppSettings>("get_current_app_config")
+            .await
+            .into_result()
+        {
+            Ok(config) => {
+                set_query.update(|query| query.replay_paths = config.query_replay_paths);
+                set_history.set(config.sql_history);
+            }
+            Err(e) => {
+                console_log(&format!("Error invoking get_current_app_config: {:?}", e));
+            }
+        }
+    });
+    let run_query = move || {
+        set_query.update(|query| query.page = 0);
+        fetch_page(query.get_untracked());
+    };
+    let change_page = move |delta: isize| {
+        set_query.update(|query| {
+            query.page = query.page.saturating_add_signed(delta);
+        });
+        fetch_page(query.get_untracked());
+    };
+    navigation.on_snapshot_updated(
+        move || query.get_untracked().replay_paths,
+        move || {
+            if let Some(query) = shown.get_value() {
+                fetch_page(query);
+            }
+        },
+    );
+
+    view! {
+        <div class="grid grid-cols-8 gap-1">
+            <div class="col-span-6">
+                <textarea
+                    class="textarea textarea-sm w-full h-32 font-mono"
+                    placeholder="SELECT * FROM details LIMIT 10"
+                    prop:value=move || query.get().sql
+                    on:input=move |ev| {
+                        set_query.update(|query| query.sql = event_target_value(&ev));
+                    }
+                    on:keydown=move |ev| {
+                        if ev.key() == "Enter" && (ev.ctrl_key() || ev.meta_key()) {
+                            ev.prevent_default();
+                            run_query();
+                        }
+                    }
+                ></textarea>
+                <div class="flex items-center gap-2">
+                    <button
+                        class="btn btn-sm btn-primary"
+                        title="Run (Ctrl+Enter)"
+                        on:click=move |_| run_query()
+                    >
+                        <Icon icon=PLAY weight=IconWeight::Bold prop:class="stroke-current" />
+                        "Run"
+                    </button>
+                    <label class="input input-sm">
+                        <span class="label">"Row limit"</span>
+                        <input
+                            type="number"
+                            min="0"
+                            class="my-0 mx-0 w-24"
+                            placeholder=SQL_QUERY_DEFAULT_ROW_LIMIT.to_string()
+                            prop:value=move || query.get().row_limit
+                            on:change=move |ev| {
+                                let row_limit = event_target_value(&ev).parse().unwrap_or(0);
+                                set_query.update(|query| query.row_limit = row_limit);
+                            }
+                        />
+                    </label>
+                    <label class="input input-sm">
+                        <span class="label">"Timeout (ms)"</span>
+                        <input
+                            type="number"
+                            min="0"
+                            class="my-0 mx-0 w-24"
+                            placeholder=SQL_QUERY_DEFAULT_TIMEOUT_MS.to_string()
+                            prop:value=move || query.get().timeout_ms
+                            on:change=move |ev| {
+                                let timeout_ms = event_target_value(&ev).parse().unwrap_or(0);
+                                set_query.update(|query| query.timeout_ms = timeout_ms);
+                            }
+                        />
+                    </label>
+                    <Show when=move || backend_response.get().meta.success>
+                        <span class="text-neutral-content text-sm">
+                            {move || format!("{} ms", backend_response.get().meta.duration_ms)}
+                        </span>
+                    </Show>
+                </div>
+            </div>
+            <div class="col-span-2">
+                <ul class="menu menu-xs bg-gray-900 rounded-box h-40 overflow-auto flex-nowrap">
+                    <li class="menu-title">"History"</li>
+                    <For
+                        each=move || history.get()
+                        key=|sql| sql.clone()
+                        children=move |sql| {
+                            let sql_cp = sql.clone();
+                            view! {
+                                <li>
+                                    <a
+                                        class="truncate font-mono"
+                                        title=sql.clone()
+                                        on:click=move |_| {
+                                            let sql = sql_cp.clone();
+                                            set_query.update(|query| query.sql = sql);
+                                        }
+                                    >
+                                        {sql}
+                                    </a>
+                                </li>
+                            }
+                        }
+                    />
+                </ul>
+            </div>
+            <Show when=move || {
+                !backend_response.get().meta.success && backend_response.get().meta.is_complete
+            }>
+                <div role="alert" class="alert alert-error shadow-lg m-1 p-1 col-span-8">
+                    <Icon icon=X_CIRCLE weight=IconWeight::Bold prop:class="stroke-current" />
+                    <span>{backend_response.get().message.clone()}</span>
+                </div>
+            </Show>
+            <Show when=move || { !query_data.columns().get().is_empty() }>
+                <div class="col-span-8 overflow-auto">
+                    <SqlQueryDataTable query_data />
+                    <div class="flex justify-center items-center gap-2 m-1">
+                        <button
+                            class="btn btn-sm btn-ghost"
+                            disabled=move || query_data.start().get() == 0
+                            on:click=move |_| change_page(-1)
+                            title="Previous page"
+                        >
+                            <Icon icon=CARET_LEFT weight=IconWeight::Bold prop:class="stroke-current" />
+                        </button>
+                        <span class="text-neutral-content text-sm">
+                            {move || {
+                                format!(
+                                    "{}-{} of {}{}",
+                                    (query_data.start().get() + 1).min(query_data.total().get()),
+                                    query_data.end().get(),
+                                    query_data.total().get(),
+                                    if query_data.truncated().get() { " (truncated)" } else { "" },
+                                )
+                            }}
+                        </span>
+                        <button
+                            class="btn btn-sm btn-ghost"
+                            disabled=move || query_data.end().get() >= query_data.total().get()
+                            on:click=move |_| change_page(1)
+                            title="Next page"
+                        >
+                            <Icon icon=CARET_RIGHT weight=IconWeight::Bold prop:class="stroke-current" />
+                        </button>
+                    </div>
+                    <ExportMenu source=Signal::derive(move || ExportSource::Sql(query.get())) />
+                </div>
+            </Show>
+        </div>
+    }
+}
+
+#[component]
+pub fn SqlQueryDataTable(query_data: Store<SqlQueryTable>) -> impl IntoView {
+    view! {
+        <table class="table bg-gray-500 table-xs table-zebra rounded-box">
+            <thead class="bg-gray-700">
+                <tr>
+                    <th></th>
+                    {move || {
+                        query_data
+                            .columns()
+                            .get()
+                            .into_iter()
+                            .map(|column| {
+                                view! {
+                                    <th>
+                                        <div>{column.name}</div>
+                                        <div class="font-normal text-neutral-content">
+                                            {column.dtype}
+                                        </div>
+                                    </th>
+                                }
+                            })
+                            .collect_view()
+                    }}
+                </tr>
+            </thead>
+            <tbody>
+                <For
+                    each=move || query_data.rows()
+                    key=|row| row.read().idx
+                    children=|child| {
+                        let idx = child.clone().idx();
+                        let cells = child.clone().cells();
+                        view! {
+                            <tr>
+                                <th>{move || idx.get()}</th>
+                                {move || {
+                                    cells
+                                        .get()
+                                        .into_iter()
+                                        .map(|cell| {
+                                            let title = cell.clone();
+                                            view! {
+                                                <td class="truncate max-w-xs" title=title>
+                                                    {cell}
+                                                </td>
+                                            }
+                                        })
+                                        .collect_view()
+                                }}
+                            </tr>
+                        }
+                    }
+                />
+            </tbody>
+        </table>
+    }
+}
@@ -0,0 +1,302 @@
+//! Swarmy CLI - Scans, optimizes and queries replay directories without the UI.
+
+use clap::{Args, Parser, Subcommand};
+use s2protocol::SC2ReplaysDirStats;
+use serde::Serialize;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::atomic::AtomicBool;
+use swarmy_tauri_common::*;
+use swarmy_tauri_lib::build_labels::{load_build_label_rules, try_classify_build_orders};
+use swarmy_tauri_lib::map_stats::data::try_get_map_list;
+use swarmy_tauri_lib::mpq_file_scan::try_optimize_replay_path;
+use swarmy_tauri_lib::settings::{configured_snapshot_dir, read_settings_file};
+use swarmy_tauri_lib::snapshot_registry::{SnapshotRegistry, SNAPSHOTS_DIR};
+use swarmy_tauri_lib::snapshot_stats::try_get_snapshot_metadata;
+use swarmy_tauri_lib::sql_query::{try_run_sql_query, AbandonedQueries};
+
+#[cfg(feature = "server")]
+mod server;
+
+/// The identifier of the app in tauri.conf.json, the CLI shares the snapshots of the app.
+const APP_IDENTIFIER: &str = "com.swarmy-tauri.app";
+
+#[derive(Debug, Parser)]
+#[command(version, about)]
+struct Cli {
+    /// Prints the result as JSON instead of human-readable text.
+    #[arg(long, global = true)]
+    json: bool,
+    /// The directory with the snapshots of the replay folders, the one configured in the app by
+    /// default.
+    #[arg(long, global = true)]
+    snapshot_dir: Option<PathBuf>,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Counts the replays of a directory and their top players and maps.
+    Scan {
+        replay_path: String,
+        #[arg(long)]
+        disable_parallel_scans: bool,
+    },
+    /// Parses the replays of a directory into its Arrow IPC snapshot under the snapshot directory.
+    Optimize(OptimizeArgs),
+    /// Prints the metadata of the snapshot of a directory.
+    SnapshotStats { replay_path: String },
+    /// Lists the maps of the snapshot of a directory, most played first.
+    MapList {
+        replay_path: String,
+        /// Only the maps played by players whose name contains this.
+        #[arg(long, default_value = "")]
+        player_name: String,
+    },
+    /// Runs a SQL query over the snapshot of a directory, each IPC file is a table.
+    Query {
+        replay_path: String,
+        sql: String,
+        /// Another directory whose snapshot is queried together with replay_path, repeatable.
+        #[arg(long = "union")]
+        union: Vec<String>,
+        #[arg(long, default_value_t = SQL_QUERY_DEFAULT_ROW_LIMIT)]
+        row_limit: usize,
+        #[arg(long, default_value_t = SQL_QUERY_DEFAULT_TIMEOUT_MS)]
+        timeout_ms: u64,
+    },
+    /// Serves the snapshot queries of the libraries of the app as a JSON API on localhost.
+    #[cfg(feature = "server")]
+    Serve {
+        #[arg(long, default_value_t = 3030)]
+        port: u16,
+        /// The origin of the web pages allowed to call the API, i.e. `http://localhost:8080`.
+        #[arg(long)]
+        allow_origin: Option<String>,
+    },
+}
+
+#[derive(Debug, Args)]
+struct OptimizeArgs {
+    replay_path: String,
+    /// Only processes the replays added or changed since the last optimize.
+    #[arg(long)]
+    incremental: bool,
+    #[arg(long)]
+    disable_parallel_scans: bool,
+    #[arg(long)]
+    scan_max_files: Option<usize>,
+    #[arg(long)]
+    process_max_files: Option<usize>,
+    #[arg(long)]
+    traverse_max_depth: Option<usize>,
+    #[arg(long)]
+    min_version: Option<u32>,
+    #[arg(long)]
+    max_version: Option<u32>,
+    /// The TOML or JSON file with the opening label rules, the built-in rules by default.
+    #[arg(long, default_value = "")]
+    build_rules_path: String,
+}
+
+impl OptimizeArgs {
+    fn optimize_settings(&self) -> OptimizeSettings {
+        let defaults = OptimizeSettings::default();
+        OptimizeSettings {
+            scan_max_files: self.scan_max_files.unwrap_or(defaults.scan_max_files),
+            process_max_files: self.process_max_files.unwrap_or(defaults.process_max_files),
+            traverse_max_depth: self
+                .traverse_max_depth
+                .unwrap_or(defaults.traverse_max_depth),
+            min_version: self.min_version,
+            max_version: self.max_version,
+            incremental: self.incremental,
+        }
+    }
+}
+
+impl Cli {
+    /// The `--snapshot-dir`, or the snapshot directory of the app so that both query the same
+    /// snapshots.
+    fn snapshot_root(&self) -> Result<PathBuf, SwarmyTauriError> {
+        if let Some(snapshot_dir) = &self.snapshot_dir {
+            return Ok(snapshot_dir.clone());
+        }
+        let app_data_dir = app_data_dir()?;
+        let settings = read_settings_file(&app_data_dir)?;
+        Ok(configured_snapshot_dir(|key| settings.get(key).cloned())
+            .unwrap_or_else(|| app_data_dir.join(SNAPSHOTS_DIR)))
+    }
+}
+
+/// The data directory of the app, where it keeps its settings store.
+fn app_data_dir() -> Result<PathBuf, SwarmyTauriError> {
+    dirs::data_dir()
+        .map(|data_dir| data_dir.join(APP_IDENTIFIER))
+        .ok_or_else(|| {
+            SwarmyTauriError::Other(String::from("No data directory, pass --snapshot-dir"))
+        })
+}
+
+fn main() -> ExitCode {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn")).init();
+    let cli = Cli::parse();
+    match run(cli) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(cli: Cli) -> Result<(), SwarmyTauriError> {
+    let snapshots = SnapshotRegistry::new(cli.snapshot_root()?);
+    match cli.command {
+        Command::Scan {
+            replay_path,
+            disable_parallel_scans,
+        } => {
+            let stats = SC2ReplaysDirStats::from_directory(&replay_path, disable_parallel_scans)?;
+            print_output(cli.json, &stats, print_scan)
+        }
+        Command::Optimize(args) => {
+            let json = cli.json;
+            let on_progress = |progress: &OptimizeProgress| {
+                // The progress goes to stderr so that stdout only has the result.
+                if !json {
+                    eprintln!(
+                        "{:?} {}% {}",
+                        progress.stage,
+                        progress.percent(),
+                        progress.current_ipc
+                    );
+                }
+            };
+            let ipc_dir = snapshots.ipc_dir(&args.replay_path);
+            let message = try_optimize_replay_path(
+                args.replay_path.clone(),
+                &ipc_dir,
+                &args.optimize_settings(),
+                args.disable_parallel_scans,
+                &AtomicBool::new(false),
+                on_progress,
+            )?;
+            // The build labels are not part of the optimized snapshot, they are recomputed
+            // from the new IPC files.
+            let rules = load_build_label_rules(&args.build_rules_path)?;
+            try_classify_build_orders(&ipc_dir, &rules)?;
+            print_output(json, &message, |message| println!("{}", message))
+        }
+        Command::SnapshotStats { replay_path } => {
+            let stats = try_get_snapshot_metadata(&snapshots, replay_path)?;
+            print_output(cli.json, &stats, print_snapshot_stats)
+        }
+        Command::MapList {
+            replay_path,
+            player_name,
+        } => {
+            let maps = try_get_map_list(&snapshots, vec![replay_path], player_name)?;
+            print_output(cli.json, &maps, |maps| print_map_list(maps))
+        }
+        Command::Query {
+            replay_path,
+            sql,
+            union,
+            row_limit,
+            timeout_ms,
+        } => {
+            let res = try_run_sql_query(
+                &snapshots,
+                &AbandonedQueries::default(),
+                SqlQuery {
+                    replay_paths: std::iter::once(replay_path).chain(union).collect(),
+                    sql,
+                    row_limit,
+                    timeout_ms,
+                    page: 0,
+                    // All the computed rows are printed.
+                    per_page: row_limit,
+                },
+            )?;
+            print_output(cli.json, &res, print_query_result)
+        }
+        #[cfg(feature = "server")]
+        Command::Serve { port, allow_origin } => tokio::runtime::Runtime::new()?.block_on(
+            server::serve(port, allow_origin, app_data_dir()?, snapshots),
+        ),
+    }
+}
+
+/// Prints `value` as pretty JSON or with the human-readable `print` function.
+fn print_output<T, F>(json: bool, value: &T, print: F) -> Result<(), SwarmyTauriError>
+where
+    T: Serialize,
+    F: FnOnce(&T),
+{
+    if json {
+        println!("{}", serde_json::to_string_pretty(value)?);
+    } else {
+        print(value);
+    }
+    Ok(())
+}
+
+fn print_scan(stats: &SC2ReplaysDirStats) {
+    println!("Total files:              {}", stats.total_files);
+    println!(
+        "Supported replays:        {}",
+        stats.total_supported_replays
+    );
+    println!(
+        "Ability supported:        {}",
+        stats.ability_supported_replays
+    );
+    println!("Top players:");
+    for (player, count) in &stats.top_10_players {
+        println!("  {:>6}  {}", count, player);
+    }
+    println!("Top maps:");
+    for (map, count) in &stats.top_10_maps {
+        println!("  {:>6}  {}", count, map);
+    }
+}
+
+fn print_snapshot_stats(stats: &SnapshotStats) {
+    println!("Games:            {}", stats.num_games);
+    println!("Maps:             {}", stats.num_maps);
+    println!("Map versions:     {}", stats.num_map_versions);
+    println!("Players:          {}", stats.num_players);
+    println!("Game versions:    {}", stats.num_game_versions);
+    println!("First game:       {}", stats.min_date);
+    println!("Last game:        {}", stats.max_date);
+    println!("Snapshot size:    {} bytes", stats.directory_size);
+}
+
+fn print_map_list(maps: &[MapStats]) {
+    for map in maps {
+        println!(
+            "{:>6}  {}  {}  {}",
+            map.num_games, map.min_date, map.max_date, map.title
+        );
+    }
+}
+
+fn print_query_result(res: &SqlQueryResult) {
+    println!("{}", res.columns.join("\t"));
+    for row in &res.rows {
+        let values: Vec<String> = row
+            .iter()
+            .map(|value| match value {
+                serde_json::Value::String(value) => value.clone(),
+                serde_json::Value::Null => String::new(),
+                value => value.to_string(),
+            })
+            .collect();
+        println!("{}", values.join("\t"));
+    }
+    if res.truncated {
+        eprintln!("The result was cut at {} rows", res.total);
+    }
+}
@@ -0,0 +1,238 @@
+//! Serves the snapshot queries as a JSON API on localhost.
+//! Each endpoint is `POST /api/<command>` and takes the same arguments as the Tauri command of
+//! the same name, the response is the same [`ApiResponse`] envelope.
+//! Only the replay folders of the libraries configured in the app are served.
+
+use axum::extract::{FromRef, State};
+use axum::http::{header, HeaderValue, Method};
+use axum::routing::post;
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use swarmy_tauri_common::*;
+use swarmy_tauri_lib::map_stats::data::try_get_map_list;
+use swarmy_tauri_lib::map_stats::{try_query_head_to_head, try_query_map_stats};
+use swarmy_tauri_lib::player_profile::try_query_player_profile;
+use swarmy_tauri_lib::settings::{load_libraries, read_settings_file};
+use swarmy_tauri_lib::snapshot_registry::SnapshotRegistry;
+use swarmy_tauri_lib::snapshot_stats::try_get_snapshot_metadata;
+use swarmy_tauri_lib::sql_query::{try_run_sql_query, AbandonedQueries};
+use tower_http::cors::CorsLayer;
+
+/// The arguments of the commands that only take the replay path.
+#[derive(Debug, Deserialize)]
+struct ReplayPathArgs {
+    replay_path: String,
+}
+
+/// The arguments of the get_map_list command.
+#[derive(Debug, Deserialize)]
+struct MapListArgs {
+    replay_paths: Vec<String>,
+    #[serde(default)]
+    player_name: String,
+}
+
+/// The arguments of the commands that take a query struct.
+#[derive(Debug, Deserialize)]
+struct QueryArgs<Q> {
+    query: Q,
+}
+
+/// The libraries configured in the app, read from its settings store on each request so that the
+/// libraries added meanwhile are served too.
+#[derive(Clone)]
+struct Libraries {
+    app_data_dir: PathBuf,
+}
+
+impl Libraries {
+    /// Refuses the `replay_paths` that are not the folder of a library.
+    fn check(&self, replay_paths: &[String]) -> Result<(), SwarmyTauriError> {
+        let settings = read_settings_file(&self.app_data_dir)?;
+        let (libraries, _) = load_libraries(|key| settings.get(key).cloned());
+        match replay_paths.iter().find(|replay_path| {
+            !libraries
+                .iter()
+                .any(|library| library.replay_path == **replay_path)
+        }) {
+            Some(replay_path) => Err(SwarmyTauriError::Other(format!(
+                "{} is not the folder of a library",
+                replay_path
+            ))),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The state shared by the handlers.
+#[derive(Clone)]
+struct ServerState {
+    snapshots: SnapshotRegistry,
+    abandoned_queries: AbandonedQueries,
+    libraries: Libraries,
+}
+
+impl FromRef<ServerState> for SnapshotRegistry {
+    fn from_ref(state: &ServerState) -> Self {
+        state.snapshots.clone()
+    }
+}
+
+impl FromRef<ServerState> for AbandonedQueries {
+    fn from_ref(state: &ServerState) -> Self {
+        state.abandoned_queries.clone()
+    }
+}
+
+impl FromRef<ServerState> for Libraries {
+    fn from_ref(state: &ServerState) -> Self {
+        state.libraries.clone()
+    }
+}
+
+/// Listens on `127.0.0.1:port` until the process is stopped.
+/// The snapshots stay open between requests.
+/// The browsers only let the pages of `allow_origin` call the API, i.e. a frontend served by
+/// trunk on another port, by default no other page can.
+pub async fn serve(
+    port: u16,
+    allow_origin: Option<String>,
+    app_data_dir: PathBuf,
+    snapshots: SnapshotRegistry,
+) -> Result<(), SwarmyTauriError> {
+    let app = Router::new()
+        .route("/api/get_snapshot_metadata", post(get_snapshot_metadata))
+        .route("/api/get_map_list", post(get_map_list))
+        .route("/api/query_map_stats", post(query_map_stats))
+        .route("/api/query_player_profile", post(query_player_profile))
+        .route("/api/query_head_to_head", post(query_head_to_head))
+        .route("/api/run_sql_query", post(run_sql_query))
+        .with_state(ServerState {
+            snapshots,
+            abandoned_queries: AbandonedQueries::default(),
+            libraries: Libraries { app_data_dir },
+        });
+    let app = match allow_origin {
+        Some(allow_origin) => {
+            let origin = HeaderValue::from_str(&allow_origin).map_err(|e| {
+                SwarmyTauriError::Other(format!("Invalid origin {}: {}", allow_origin, e))
+            })?;
+            app.layer(
+                CorsLayer::new()
+                    .allow_origin(origin)
+                    .allow_methods([Method::POST])
+                    .allow_headers([header::CONTENT_TYPE]),
+            )
+        }
+        None => app,
+    };
+    let listener = tokio::net::TcpListener::bind(("127.0.0.1", port)).await?;
+    log::info!("Serving the snapshot API on: {}", listener.local_addr()?);
+    axum::serve(listener, app).await?;
+    Ok(())
+}
+
+/// Runs a query on the blocking pool and wraps its result in the response envelope.
+async fn run_blocking<T, F>(name: &'static str, f: F) -> Json<serde_json::Value>
+where
+    T: Serialize,
+    F: FnOnce() -> Result<T, SwarmyTauriError> + Send + 'static,
+{
+    // The response is serialized in the blocking task as the errors are not Send.
+    let res = tokio::task::spawn_blocking(move || {
+        let init_time = std::time::Instant::now();
+        let res = f();
+        if let Err(e) = &res {
+            log::error!("Error running {}: {}", name, e);
+        }
+        to_json(ApiResponse::from_result(
+            res,
+            init_time.elapsed().as_millis() as u64,
+        ))
+    })
+    .await;
+    Json(res.unwrap_or_else(|e| {
+        to_json(ApiResponse::<()>::err(
+            ResponseMetaBuilder::new(false).build(),
+            SwarmyTauriError::Other(format!("The {} handler failed: {}", name, e)),
+        ))
+    }))
+}
+
+fn to_json<T: Serialize>(response: ApiResponse<T>) -> serde_json::Value {
+    serde_json::to_value(response).unwrap_or_default()
+}
+
+async fn get_snapshot_metadata(
+    State(snapshots): State<SnapshotRegistry>,
+    State(libraries): State<Libraries>,
+    Json(args): Json<ReplayPathArgs>,
+) -> Json<serde_json::Value> {
+    run_blocking("get_snapshot_metadata", move || {
+        libraries.check(std::slice::from_ref(&args.replay_path))?;
+        try_get_snapshot_metadata(&snapshots, args.replay_path)
+    })
+    .await
+}
+
+async fn get_map_list(
+    State(snapshots): State<SnapshotRegistry>,
+    State(libraries): State<Libraries>,
+    Json(args): Json<MapListArgs>,
+) -> Json<serde_json::Value> {
+    run_blocking("get_map_list", move || {
+        libraries.check(&args.replay_paths)?;
+        try_get_map_list(&snapshots, args.replay_paths, args.player_name)
+    })
+    .await
+}
+
+async fn query_map_stats(
+    State(snapshots): State<SnapshotRegistry>,
+    State(libraries): State<Libraries>,
+    Json(args): Json<QueryArgs<MapStatsQuery>>,
+) -> Json<serde_json::Value> {
+    run_blocking("query_map_stats", move || {
+        libraries.check(&args.query.replay_paths)?;
+        try_query_map_stats(&snapshots, args.query)
+    })
+    .await
+}
+
+async fn query_player_profile(
+    State(snapshots): State<SnapshotRegistry>,
+    State(libraries): State<Libraries>,
+    Json(args): Json<QueryArgs<PlayerProfileQuery>>,
+) -> Json<serde_json::Value> {
+    run_blocking("query_player_profile", move || {
+        libraries.check(&args.query.replay_paths)?;
+        try_query_player_profile(&snapshots, args.query)
+    })
+    .await
+}
+
+async fn query_head_to_head(
+    State(snapshots): State<SnapshotRegistry>,
+    State(libraries): State<Libraries>,
+    Json(args): Json<QueryArgs<HeadToHeadQuery>>,
+) -> Json<serde_json::Value> {
+    run_blocking("query_head_to_head", move || {
+        libraries.check(&args.query.replay_paths)?;
+        try_query_head_to_head(&snapshots, args.query)
+    })
+    .await
+}
+
+async fn run_sql_query(
+    State(snapshots): State<SnapshotRegistry>,
+    State(abandoned_queries): State<AbandonedQueries>,
+    State(libraries): State<Libraries>,
+    Json(args): Json<QueryArgs<SqlQuery>>,
+) -> Json<serde_json::Value> {
+    run_blocking("run_sql_query", move || {
+        libraries.check(&args.query.replay_paths)?;
+        try_run_sql_query(&snapshots, &abandoned_queries, args.query)
+    })
+    .await
+}
@@ -0,0 +1,152 @@
+//! Opening labels assigned to the build orders of the snapshot by user-defined rules.
+use serde::{Deserialize, Serialize};
+
+/// The label of the build orders that match none of the rules.
+pub const BUILD_LABEL_UNMATCHED: &str = "Other";
+
+/// The rules that label the openings, the first rule that matches a build order labels it.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct BuildLabelRules {
+    #[serde(default, rename = "rule")]
+    pub rules: Vec<BuildLabelRule>,
+}
+
+/// A named opening, a build order has it when all of its conditions match.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct BuildLabelRule {
+    /// The label of the opening, i.e. `Hatch-Pool-Gas`.
+    pub label: String,
+    /// The race of the player in any client language, see [`crate::race_id`], any race when not
+    /// set.
+    #[serde(default)]
+    pub race: Option<String>,
+    #[serde(default, rename = "condition")]
+    pub conditions: Vec<BuildLabelCondition>,
+}
+
+/// The number of units of a type born within a window of game loops.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct BuildLabelCondition {
+    /// The name of the unit type, i.e. `SpawningPool`.
+    pub unit: String,
+    /// The minimum number of units in the window.
+    pub min_count: u32,
+    /// The maximum number of units in the window, unbounded when not set.
+    pub max_count: Option<u32>,
+    /// The first game loop of the window, inclusive.
+    pub from_loop: i64,
+    /// The last game loop of the window, inclusive, 0 uses [`crate::BUILD_ORDER_DEFAULT_MAX_LOOP`].
+    pub to_loop: i64,
+    /// A unit type whose first unit must be born before the first unit of this condition.
+    pub after: Option<String>,
+}
+
+impl BuildLabelCondition {
+    /// The last game loop of the window.
+    pub fn to_loop(&self) -> i64 {
+        if self.to_loop == 0 {
+            crate::BUILD_ORDER_DEFAULT_MAX_LOOP
+        } else {
+            self.to_loop
+        }
+    }
+
+    /// Whether the steps, unit names and game loops ordered by loop, match the condition.
+    pub fn matches(&self, steps: &[(String, i64)]) -> bool {
+        let in_window: Vec<i64> = steps
+            .iter()
+            .filter(|(name, ext_replay_loop)| {
+                name == &self.unit
+                    && *ext_replay_loop >= self.from_loop
+                    && *ext_replay_loop <= self.to_loop()
+            })
+            .map(|(_, ext_replay_loop)| *ext_replay_loop)
+            .collect();
+        let count = in_window.len() as u32;
+        if count < self.min_count || self.max_count.is_some_and(|max_count| count > max_count) {
+            return false;
+        }
+        match (&self.after, in_window.first()) {
+            (Some(after), Some(first_loop)) => steps
+                .iter()
+                .find(|(name, _)| name == after)
+                .is_some_and(|(_, after_loop)| after_loop < first_loop),
+            _ => true,
+        }
+    }
+}
+
+impl BuildLabelRules {
+    /// The last game loop any of the rules looks at.
+    pub fn max_loop(&self) -> i64 {
+        self.rules
+            .iter()
+            .flat_map(|rule| rule.conditions.iter())
+            .map(|condition| condition.to_loop())
+            .max()
+            .unwrap_or(crate::BUILD_ORDER_DEFAULT_MAX_LOOP)
+    }
+
+    /// Returns the label of the first rule that matches the steps of a player of `race`, the
+    /// steps are the unit names and game loops ordered by loop.
+    pub fn classify(&self, race: &str, steps: &[(String, i64)]) -> &str {
+        self.rules
+            .iter()
+            .find(|rule| {
+                rule.race
+                    .as_ref()
+                    .is_none_or(|rule_race| crate::race_id(rule_race) == crate::race_id(race))
+                    && rule
+                        .conditions
+                        .iter()
+                        .all(|condition| condition.matches(steps))
+            })
+            .map(|rule| rule.label.as_str())
+            .unwrap_or(BUILD_LABEL_UNMATCHED)
+    }
+}
+
+/// The number of build orders with a label.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct BuildLabelCount {
+    pub label: String,
+    pub count: u64,
+}
+
+/// The labels of the snapshot as returned by the build label commands.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct BuildLabelSummary {
+    /// The rules file used, empty for the built-in rules.
+    pub rules_path: String,
+    /// The number of rules.
+    pub num_rules: usize,
+    /// The number of labelled build orders, one per player per game.
+    pub num_build_orders: u64,
+    /// The number of build orders per label, most frequent first.
+    pub labels: Vec<BuildLabelCount>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rules_match_localized_races() {
+        let rules = BuildLabelRules {
+            rules: vec![BuildLabelRule {
+                label: String::from("Hatch first"),
+                race: Some(String::from("Zerg")),
+                conditions: vec![BuildLabelCondition {
+                    unit: String::from("Hatchery"),
+                    min_count: 1,
+                    ..Default::default()
+                }],
+            }],
+        };
+        let steps = [(String::from("Hatchery"), 500)];
+        assert_eq!(rules.classify("저그", &steps), "Hatch first");
+        assert_eq!(rules.classify("Zerg", &steps), "Hatch first");
+        assert_eq!(rules.classify("Terraner", &steps), BUILD_LABEL_UNMATCHED);
+    }
+}
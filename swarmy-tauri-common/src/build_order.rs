@@ -0,0 +1,145 @@
+//! Opening build orders from the unit born IPC file.
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// The game loop up to which build orders are extracted when the query does not specify it,
+/// around five minutes of game time at about 68.6 ms per loop.
+pub const BUILD_ORDER_DEFAULT_MAX_LOOP: i64 = 4375;
+
+/// Units that are born on their own or in large numbers and do not make part of a build order.
+pub const BUILD_ORDER_IGNORED_UNITS: [&str; 12] = [
+    "Larva",
+    "Egg",
+    "Broodling",
+    "BroodlingEscort",
+    "LocustMP",
+    "LocustMPFlying",
+    "Interceptor",
+    "AdeptPhaseShift",
+    "MULE",
+    "CreepTumor",
+    "CreepTumorBurrowed",
+    "CreepTumorQueen",
+];
+
+/// The query params of the build order of a player in a game.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct BuildOrderQuery {
+    /// The replay folders whose snapshots are queried, see
+    /// [`crate::LibrarySelection::replay_paths`].
+    pub replay_paths: Vec<String>,
+    /// The game in the snapshot.
+    pub ext_fs_id: u64,
+    /// The name of the player in the game, with or without clan tag.
+    pub player_name: String,
+    /// The last game loop of the build order, 0 uses [`BUILD_ORDER_DEFAULT_MAX_LOOP`].
+    pub max_loop: i64,
+}
+
+/// A unit or structure of a build order.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct BuildOrderStep {
+    /// The name of the unit type.
+    pub name: String,
+    /// The game loop the unit was born or the structure was finished.
+    pub ext_replay_loop: i64,
+    /// The game time in seconds.
+    pub game_seconds: u32,
+    /// The supply used at the time, as of the last stats sample.
+    pub supply_used: i32,
+    /// The supply available at the time, as of the last stats sample.
+    pub supply_made: i32,
+}
+
+/// The build order of a player in a game, in the order the units were born.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct BuildOrder {
+    pub ext_fs_id: u64,
+    pub player_name: String,
+    pub steps: Vec<BuildOrderStep>,
+}
+
+/// The n-th unit of a type in two build orders.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct BuildOrderComparisonRow {
+    pub name: String,
+    /// The 1-based occurrence of the unit type in the build orders.
+    pub occurrence: usize,
+    /// The game time of the unit in build order A, if it has that many units of the type.
+    pub seconds_a: Option<u32>,
+    /// The game time of the unit in build order B, if it has that many units of the type.
+    pub seconds_b: Option<u32>,
+}
+
+impl BuildOrderComparisonRow {
+    /// How many seconds later the unit came in B than in A.
+    pub fn delta_seconds(&self) -> Option<i64> {
+        Some(self.seconds_b? as i64 - self.seconds_a? as i64)
+    }
+}
+
+/// Two build orders side by side.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct BuildOrderComparison {
+    pub a: BuildOrder,
+    pub b: BuildOrder,
+    /// The index of the first step where the unit types differ, None if one is a prefix of the
+    /// other.
+    pub diverged_at: Option<usize>,
+    /// The units of both build orders matched by type and occurrence, earliest first.
+    pub rows: Vec<BuildOrderComparisonRow>,
+}
+
+/// The query params of the comparison of two build orders.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct BuildOrderComparisonQuery {
+    pub a: BuildOrderQuery,
+    pub b: BuildOrderQuery,
+}
+
+impl BuildOrder {
+    /// Compares this build order, A, with `other`, B.
+    pub fn compare(&self, other: &BuildOrder) -> BuildOrderComparison {
+        let diverged_at = self
+            .steps
+            .iter()
+            .zip(other.steps.iter())
+            .position(|(a, b)| a.name != b.name);
+        let mut rows: Vec<BuildOrderComparisonRow> = vec![];
+        let mut index: HashMap<(String, usize), usize> = HashMap::new();
+        for (steps, is_a) in [(&self.steps, true), (&other.steps, false)] {
+            let mut occurrences: HashMap<&str, usize> = HashMap::new();
+            for step in steps {
+                let occurrence = occurrences.entry(step.name.as_str()).or_default();
+                *occurrence += 1;
+                let idx = *index
+                    .entry((step.name.clone(), *occurrence))
+                    .or_insert_with(|| {
+                        rows.push(BuildOrderComparisonRow {
+                            name: step.name.clone(),
+                            occurrence: *occurrence,
+                            ..Default::default()
+                        });
+                        rows.len() - 1
+                    });
+                if is_a {
+                    rows[idx].seconds_a = Some(step.game_seconds);
+                } else {
+                    rows[idx].seconds_b = Some(step.game_seconds);
+                }
+            }
+        }
+        rows.sort_by_key(|row| match (row.seconds_a, row.seconds_b) {
+            (Some(a), Some(b)) => a.min(b),
+            (Some(a), None) => a,
+            (None, Some(b)) => b,
+            (None, None) => 0,
+        });
+        BuildOrderComparison {
+            a: self.clone(),
+            b: other.clone(),
+            diverged_at,
+            rows,
+        }
+    }
+}
@@ -0,0 +1,111 @@
+//! Economy timelines from the player stats IPC file.
+use serde::{Deserialize, Serialize};
+
+/// The query params of the economy of the players of a game.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct EconomyQuery {
+    /// The replay folders whose snapshots are queried, see
+    /// [`crate::LibrarySelection::replay_paths`].
+    pub replay_paths: Vec<String>,
+    /// The game in the snapshot.
+    pub ext_fs_id: u64,
+}
+
+/// A player stats sample, taken by the game every 160 loops, about 10 seconds.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct EconomySample {
+    pub ext_replay_loop: i64,
+    pub game_seconds: u32,
+    /// The minerals collected per minute.
+    pub minerals_collection_rate: i32,
+    /// The vespene collected per minute.
+    pub vespene_collection_rate: i32,
+    pub workers: i32,
+    pub supply_used: i32,
+    pub supply_made: i32,
+    /// The minerals and vespene spent on the army that is alive.
+    pub army_value: i32,
+}
+
+/// The stats samples of a player in a game, oldest first.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct PlayerEconomy {
+    /// The id of the player in the tracker events.
+    pub player_id: u8,
+    /// The name of the player, with clan tag.
+    pub player_name: String,
+    pub samples: Vec<EconomySample>,
+}
+
+/// The economy of the players of a game as returned by the query_economy_timeline command.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct EconomyTimeline {
+    pub ext_fs_id: u64,
+    pub players: Vec<PlayerEconomy>,
+}
+
+/// The game loops grouped into each point of a benchmark, the interval of the stats samples.
+pub const BENCHMARK_BUCKET_LOOPS: i64 = 160;
+
+/// The games a benchmark compares a game against.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum BenchmarkScope {
+    /// The other games of the player with the same races in 1v1.
+    #[default]
+    Matchup,
+    /// The games of every player on the same map.
+    Map,
+}
+
+/// The query params of the benchmark of a player in a game.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct BenchmarkQuery {
+    /// The replay folders whose snapshots are queried, see
+    /// [`crate::LibrarySelection::replay_paths`].
+    pub replay_paths: Vec<String>,
+    /// The game in the snapshot.
+    pub ext_fs_id: u64,
+    /// The name of the player, a name without clan tag matches any clan tag.
+    pub player_name: String,
+    pub scope: BenchmarkScope,
+}
+
+/// The value of the game and the spread of the benchmark games in a bucket of game loops.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq)]
+pub struct BenchmarkPoint {
+    /// The first game loop of the bucket.
+    pub ext_replay_loop: i64,
+    pub game_seconds: u32,
+    /// The value in the game, none once it ended.
+    pub value: Option<f64>,
+    pub median: f64,
+    /// The 25th percentile.
+    pub q1: f64,
+    /// The 75th percentile.
+    pub q3: f64,
+    /// The number of benchmark games that lasted until this bucket.
+    pub games: u64,
+}
+
+/// A stats metric of a game over time against the same metric in the benchmark games.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq)]
+pub struct BenchmarkSeries {
+    /// The name of the metric, i.e. `Workers`.
+    pub metric: String,
+    pub scope: BenchmarkScope,
+    /// What the benchmark games have in common, i.e. `ZvP` or the map title.
+    pub description: String,
+    /// The number of benchmark games, not including the game itself.
+    pub num_games: u64,
+    pub points: Vec<BenchmarkPoint>,
+}
+
+/// The benchmarks of a player in a game as returned by the query_benchmark command.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Benchmark {
+    pub ext_fs_id: u64,
+    /// The name of the player, with clan tag.
+    pub player_name: String,
+    pub workers: BenchmarkSeries,
+    pub army_value: BenchmarkSeries,
+}
@@ -4,8 +4,9 @@ use thiserror::Error;
 pub enum SwarmyTauriError {
     // #[error("Store Error")]
     // TauriPluginStore(#[from] tauri_plugin_store::Error),
-    #[error("StdError")]
-    StdErr(#[from] Box<dyn std::error::Error>),
+    /// Kept as its message so that the error can be sent across threads.
+    #[error("StdError: {0}")]
+    StdErr(String),
     #[error("S2proto Error")]
     S2ProtoErr(#[from] s2protocol::error::S2ProtocolError),
     #[error(transparent)]
@@ -21,13 +22,37 @@ pub enum SwarmyTauriError {
     #[error("Serde Error: {0}")]
     SerdeJson(#[from] serde_json::Error),
 
+    /// Kept as its message so that the error can be sent across threads.
     #[error("Serde Wasm Bindgen Error: {0}")]
-    SerdeWasmBindgen(#[from] serde_wasm_bindgen::Error),
+    SerdeWasmBindgen(String),
+
+    #[error("Operation cancelled")]
+    Cancelled,
+
+    /// The snapshot was written by another version of the app or s2protocol.
+    #[error("Snapshot outdated, re-optimize: {0}")]
+    SnapshotOutdated(String),
+
+    /// An error reported by the backend, as received by the frontend.
+    #[error("{message}")]
+    Remote { kind: String, message: String },
 
     #[error("Other Error: {0}")]
     Other(String),
 }
 
+impl From<Box<dyn std::error::Error>> for SwarmyTauriError {
+    fn from(err: Box<dyn std::error::Error>) -> Self {
+        Self::StdErr(err.to_string())
+    }
+}
+
+impl From<serde_wasm_bindgen::Error> for SwarmyTauriError {
+    fn from(err: serde_wasm_bindgen::Error) -> Self {
+        Self::SerdeWasmBindgen(err.to_string())
+    }
+}
+
 impl From<SwarmyTauriError> for String {
     fn from(err: SwarmyTauriError) -> Self {
         match err {
@@ -42,6 +67,11 @@ impl From<SwarmyTauriError> for String {
             SwarmyTauriError::Utf8(e) => format!("UTF8 Error: {}", e),
             SwarmyTauriError::SerdeJson(e) => format!("Serde Error: {}", e),
             SwarmyTauriError::SerdeWasmBindgen(e) => format!("Serde Wasm Bindgen Error: {}", e),
+            SwarmyTauriError::Cancelled => String::from("Operation cancelled"),
+            SwarmyTauriError::SnapshotOutdated(e) => {
+                format!("Snapshot outdated, re-optimize: {}", e)
+            }
+            SwarmyTauriError::Remote { message, .. } => message,
             SwarmyTauriError::Other(e) => format!("Other Error: {}", e),
         }
     }
@@ -61,6 +91,8 @@ enum ErrorKind {
 
     Serde(String),
     SerdeWasmBindgen(String),
+    Cancelled(String),
+    SnapshotOutdated(String),
     Other(String),
 }
 
@@ -81,8 +113,48 @@ impl serde::Serialize for SwarmyTauriError {
 
             Self::SerdeJson(_) => ErrorKind::Serde(error_message),
             Self::SerdeWasmBindgen(_) => ErrorKind::SerdeWasmBindgen(error_message),
+            Self::Cancelled => ErrorKind::Cancelled(error_message),
+            Self::SnapshotOutdated(_) => ErrorKind::SnapshotOutdated(error_message),
+            // Already in its serialized form, keep the kind reported by the backend.
+            Self::Remote { kind, message } => {
+                return SerializedError {
+                    kind: kind.clone(),
+                    message: message.clone(),
+                }
+                .serialize(serializer)
+            }
             Self::Other(_) => ErrorKind::Other(error_message),
         };
         error_kind.serialize(serializer)
     }
 }
+
+/// The shape of a serialized [`SwarmyTauriError`].
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SerializedError {
+    kind: String,
+    message: String,
+}
+
+impl<'de> serde::Deserialize<'de> for SwarmyTauriError {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::de::Deserializer<'de>,
+    {
+        let error = SerializedError::deserialize(deserializer)?;
+        Ok(match error.kind.as_str() {
+            "cancelled" => Self::Cancelled,
+            // The message was serialized with the prefix of the variant.
+            "snapshotOutdated" => Self::SnapshotOutdated(
+                error
+                    .message
+                    .trim_start_matches("Snapshot outdated, re-optimize: ")
+                    .to_string(),
+            ),
+            _ => Self::Remote {
+                kind: error.kind,
+                message: error.message,
+            },
+        })
+    }
+}
@@ -0,0 +1,79 @@
+//! Exports of query results to files.
+use crate::{MapStatsQuery, PlayerProfileQuery, SqlQuery};
+use serde::{Deserialize, Serialize};
+
+/// The file formats a query result can be exported to.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    Parquet,
+    /// Newline-delimited JSON, one object per row.
+    NdJson,
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 3] = [
+        ExportFormat::Csv,
+        ExportFormat::Parquet,
+        ExportFormat::NdJson,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "CSV",
+            ExportFormat::Parquet => "Parquet",
+            ExportFormat::NdJson => "JSON Lines",
+        }
+    }
+
+    /// The extension of the exported file, Parquet compresses its pages and CSV and NDJSON files
+    /// are gzipped instead.
+    pub fn extension(&self, compression: bool) -> &'static str {
+        match (self, compression) {
+            (ExportFormat::Csv, false) => "csv",
+            (ExportFormat::Csv, true) => "csv.gz",
+            (ExportFormat::Parquet, _) => "parquet",
+            (ExportFormat::NdJson, false) => "ndjson",
+            (ExportFormat::NdJson, true) => "ndjson.gz",
+        }
+    }
+}
+
+/// The query whose result is exported, all its rows regardless of paging and row limits.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub enum ExportSource {
+    /// The per-map aggregates of the StatsByMap page.
+    MapStats(MapStatsQuery),
+    /// The details rows of the games of a player.
+    PlayerGames(PlayerProfileQuery),
+    Sql(SqlQuery),
+}
+
+impl ExportSource {
+    /// The file name suggested in the save dialog, without extension.
+    pub fn file_stem(&self) -> &'static str {
+        match self {
+            ExportSource::MapStats(_) => "map_stats",
+            ExportSource::PlayerGames(_) => "player_games",
+            ExportSource::Sql(_) => "query",
+        }
+    }
+}
+
+/// The query params of the export_query command.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct ExportQuery {
+    pub source: ExportSource,
+    pub format: ExportFormat,
+    /// Whether to compress the file, see [`ExportFormat::extension`].
+    pub compression: bool,
+}
+
+/// The file written by the export_query command.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct ExportResult {
+    pub path: String,
+    /// The size of the file in bytes.
+    pub file_size: u64,
+}
@@ -0,0 +1,32 @@
+//! Background jobs run by the backend on behalf of the commands.
+use serde::{Deserialize, Serialize};
+
+/// The number of finished jobs kept for the UI to list.
+pub const JOB_HISTORY_SIZE: usize = 100;
+
+/// The stages a job goes through.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum JobStatus {
+    /// Waiting for a thread of the pool.
+    #[default]
+    Queued,
+    Running,
+    Finished,
+    /// The job returned an error or panicked.
+    Failed,
+}
+
+/// A job submitted to the job runner.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct JobInfo {
+    pub id: u64,
+    /// The command that submitted the job.
+    pub name: String,
+    pub status: JobStatus,
+    /// The time the job was submitted, in milliseconds since the Unix epoch.
+    pub queued_at_ms: u64,
+    /// The time the job spent running, 0 until it ends.
+    pub duration_ms: u64,
+    /// The error message if the job failed.
+    pub error: Option<String>,
+}
@@ -8,7 +8,33 @@ pub mod snapshot_stats;
 pub use snapshot_stats::*;
 pub mod map_stats;
 pub use map_stats::*;
+pub mod optimize_progress;
+pub use optimize_progress::*;
+pub mod sql_query;
+pub use sql_query::*;
+pub mod player_profile;
+pub use player_profile::*;
+pub mod build_order;
+pub use build_order::*;
+pub mod build_labels;
+pub use build_labels::*;
+pub mod economy;
+pub use economy::*;
+pub mod jobs;
+pub use jobs::*;
+pub mod export;
+pub use export::*;
+pub mod library;
+pub use library::*;
+pub mod replay_watcher;
+pub use replay_watcher::*;
 
 pub const DETAILS_IPC: &str = "details.ipc";
 pub const INIT_DATA_IPC: &str = "init_data.ipc";
 pub const UNIT_BORN_IPC: &str = "unit_born.ipc";
+pub const UNIT_DIED_IPC: &str = "unit_died.ipc";
+pub const STATS_IPC: &str = "stats.ipc";
+pub const UPGRADES_IPC: &str = "upgrades.ipc";
+pub const CMD_TARGET_POINT_IPC: &str = "cmd_target_point.ipc";
+pub const CMD_TARGET_UNIT_IPC: &str = "cmd_target_unit.ipc";
+pub const BUILD_LABELS_IPC: &str = "build_labels.ipc";
@@ -0,0 +1,38 @@
+//! Replay libraries, the folders of replays that each have their own snapshot.
+use crate::OptimizeSettings;
+use serde::{Deserialize, Serialize};
+
+/// The name of the library created from the settings of older versions.
+pub const DEFAULT_LIBRARY_NAME: &str = "Default";
+
+/// A folder of replays with its own optimize settings and snapshot, stored in its own directory
+/// under the snapshot directory.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct ReplayLibrary {
+    /// Unique among the libraries, i.e. "Ladder".
+    pub name: String,
+    pub replay_path: String,
+    pub optimize_settings: OptimizeSettings,
+}
+
+/// The libraries the queries target.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct LibrarySelection {
+    /// The library that is scanned and optimized, always queried.
+    pub active: String,
+    /// The other libraries queried together with the active one.
+    pub union: Vec<String>,
+}
+
+impl LibrarySelection {
+    /// The replay paths of the selected `libraries`, the snapshots of all of them are queried
+    /// as one.
+    pub fn replay_paths(&self, libraries: &[ReplayLibrary]) -> Vec<String> {
+        std::iter::once(&self.active)
+            .chain(self.union.iter())
+            .filter_map(|name| libraries.iter().find(|library| library.name == *name))
+            .map(|library| library.replay_path.clone())
+            .filter(|replay_path| !replay_path.is_empty())
+            .collect()
+    }
+}
@@ -1,3 +1,4 @@
+use crate::{PlayerMapRecord, PlayerRecord};
 use serde::{Deserialize, Serialize};
 
 /// Contains metadata information related to the minimun, maximum date of the map in the snapshot.
@@ -20,6 +21,8 @@ pub struct MapStats {
     pub min_date: chrono::NaiveDate,
     /// The maximum date of the snapshot taken
     pub max_date: chrono::NaiveDate,
+    /// The win rate of each race pairing in the 1v1 games of the map.
+    pub matchups: Vec<MatchupWinRate>,
 }
 
 impl Default for MapStats {
@@ -32,18 +35,326 @@ impl Default for MapStats {
             num_games: 0,
             title: String::new(),
             cache_handles: String::new(),
+            matchups: vec![],
         }
     }
 }
 
+/// The race names of the game clients by the English name that identifies the race, the details
+/// of a replay name the races in the language of the client that saved it.
+pub const LOCALIZED_RACES: [(&str, &[&str]); 3] = [
+    (
+        "Terran",
+        &[
+            "Terran",
+            "Terraner",
+            "Terrano",
+            "Terranie",
+            "Терран",
+            "테란",
+            "人类",
+            "人類",
+        ],
+    ),
+    (
+        "Protoss",
+        &["Protoss", "Protosi", "Протосс", "프로토스", "星灵", "神族"],
+    ),
+    ("Zerg", &["Zerg", "Zergi", "Зерг", "저그", "异虫", "蟲族"]),
+];
+
+/// The English name of a race named in any client language, unknown names are kept as they are.
+pub fn race_id(race: &str) -> &str {
+    LOCALIZED_RACES
+        .iter()
+        .find(|(_, localized): &&(&str, &[&str])| localized.contains(&race))
+        .map(|(name, _)| *name)
+        .unwrap_or(race)
+}
+
+/// The English name of the race named in the client language in `race`, see [`LOCALIZED_RACES`].
+/// The races of the snapshot are compared and grouped by it.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn race_id_expr(race: polars::prelude::Expr) -> polars::prelude::Expr {
+    use polars::prelude::*;
+    LOCALIZED_RACES
+        .iter()
+        .fold(race.clone(), |expr, (name, localized)| {
+            when(
+                race.clone()
+                    .is_in(lit(Series::new("race".into(), *localized)).implode(), false),
+            )
+            .then(lit(*name))
+            .otherwise(expr)
+        })
+}
+
+/// The games won by a race against another race, i.e. `ZvP` for Zerg against Protoss.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq)]
+pub struct MatchupWinRate {
+    /// The initials of the races, i.e. `ZvP`.
+    pub matchup: String,
+    pub race: String,
+    pub opponent_race: String,
+    pub games: u64,
+    pub wins: u64,
+    /// The lower bound of the 95% confidence interval of the win rate, from 0 to 1.
+    pub ci_low: f64,
+    /// The upper bound of the 95% confidence interval of the win rate, from 0 to 1.
+    pub ci_high: f64,
+}
+
+impl MatchupWinRate {
+    pub fn new(race: String, opponent_race: String, games: u64, wins: u64) -> Self {
+        let initial = |race: &str| race_id(race).chars().next().unwrap_or('?');
+        let (ci_low, ci_high) = wilson_interval(wins, games);
+        Self {
+            matchup: format!("{}v{}", initial(&race), initial(&opponent_race)),
+            race,
+            opponent_race,
+            games,
+            wins,
+            ci_low,
+            ci_high,
+        }
+    }
+
+    /// The share of games won, from 0 to 1.
+    pub fn win_rate(&self) -> f64 {
+        if self.games == 0 {
+            0.0
+        } else {
+            self.wins as f64 / self.games as f64
+        }
+    }
+}
+
+/// The Wilson score interval at 95% confidence of `wins` out of `games`, which unlike the normal
+/// approximation stays within 0 and 1 for the small samples of a single map.
+pub fn wilson_interval(wins: u64, games: u64) -> (f64, f64) {
+    if games == 0 {
+        return (0.0, 1.0);
+    }
+    const Z: f64 = 1.96;
+    let n = games as f64;
+    let p = wins as f64 / n;
+    let denominator = 1.0 + Z * Z / n;
+    let center = (p + Z * Z / (2.0 * n)) / denominator;
+    let margin = Z * (p * (1.0 - p) / n + Z * Z / (4.0 * n * n)).sqrt() / denominator;
+    ((center - margin).max(0.0), (center + margin).min(1.0))
+}
+
 /// Initial set of query params for the map stats arrow IPC file.
 /// XXX: We need to figure out how to handle multiple players.
 #[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
 pub struct MapStatsQuery {
-    /// The location of the arrow IPC files.
-    pub replay_path: String,
+    /// The replay folders whose snapshots are queried, see
+    /// [`crate::LibrarySelection::replay_paths`].
+    pub replay_paths: Vec<String>,
     /// The name of the map.
     pub map_title: String,
-    /// A player that must have played a game in the map.
+    /// A player that must have played a game in the map, a toon handle or an exact name.
     pub player_name: String,
+    /// An opening label that a player, or the player above if set, must have used in the game.
+    pub opening: String,
+    /// The zero-based page of results to return.
+    pub page: usize,
+    /// The number of results per page, 0 uses [`MAP_STATS_DEFAULT_PER_PAGE`].
+    pub per_page: usize,
+}
+
+/// The number of map stats rows returned per page when the query does not specify it.
+pub const MAP_STATS_DEFAULT_PER_PAGE: usize = 25;
+
+/// A page of per-map aggregates as returned by the query_map_stats command.
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct MapStatsDataFrame {
+    /// The total number of rows matching the query, across all pages.
+    pub total: usize,
+    /// The rows in the current page.
+    pub res: Vec<MapStats>,
+    /// The index of the first row of the page, inclusive.
+    pub start: usize,
+    /// The index of the last row of the page, exclusive.
+    pub end: usize,
+    /// The zero-based page number.
+    pub page: usize,
+    /// The number of rows per page.
+    pub per_page: usize,
+}
+
+/// The query params of a head-to-head report between two players, each a name or toon handle.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct HeadToHeadQuery {
+    /// The replay folders whose snapshots are queried, see
+    /// [`crate::LibrarySelection::replay_paths`].
+    pub replay_paths: Vec<String>,
+    pub player_a: String,
+    pub player_b: String,
+}
+
+/// The record of player A against player B with a race pairing.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct HeadToHeadRaceRecord {
+    pub race_a: String,
+    pub race_b: String,
+    pub record: PlayerRecord,
+}
+
+/// The record of player A against player B in a month, formatted as `%Y-%m`.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct HeadToHeadMonthRecord {
+    pub month: String,
+    pub record: PlayerRecord,
+}
+
+/// A game where player A and player B played in different teams.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct HeadToHeadGame {
+    pub ext_fs_id: u64,
+    pub date: chrono::NaiveDateTime,
+    /// The name of the map.
+    pub title: String,
+    /// The name of player A in the game, with clan tag.
+    pub player_name_a: String,
+    /// The name of player B in the game, with clan tag.
+    pub player_name_b: String,
+    pub race_a: String,
+    pub race_b: String,
+    /// The result of player A, i.e. `Win` or `Loss`.
+    pub result_a: String,
+    /// The length of the game in seconds, 0 when the stats IPC file is not available.
+    pub game_seconds: u32,
+}
+
+/// The games between two players as returned by the query_head_to_head command.
+/// The records are from the side of player A.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq)]
+pub struct HeadToHead {
+    pub player_a: String,
+    pub player_b: String,
+    pub record: PlayerRecord,
+    /// The record per map, most played first.
+    pub maps: Vec<PlayerMapRecord>,
+    /// The record per race pairing, most played first.
+    pub races: Vec<HeadToHeadRaceRecord>,
+    /// The record per month, oldest first.
+    pub months: Vec<HeadToHeadMonthRecord>,
+    /// The average length of the games in seconds.
+    pub avg_game_seconds: f64,
+    /// The games, newest first.
+    pub games: Vec<HeadToHeadGame>,
+}
+
+/// The query params of the versions of a map, identified by its exact title.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct MapVersionsQuery {
+    /// The replay folders whose snapshots are queried, see
+    /// [`crate::LibrarySelection::replay_paths`].
+    pub replay_paths: Vec<String>,
+    /// The name of the map.
+    pub map_title: String,
+}
+
+/// The change of the win rate of a matchup from the previous version of a map.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq)]
+pub struct MatchupShift {
+    /// The initials of the races, i.e. `ZvP`.
+    pub matchup: String,
+    /// The win rate of this version minus the one of the previous version, from -1 to 1.
+    pub delta: f64,
+    /// Whether the confidence intervals of both versions do not overlap.
+    pub significant: bool,
+}
+
+impl MatchupShift {
+    pub fn between(previous: &MatchupWinRate, current: &MatchupWinRate) -> Self {
+        Self {
+            matchup: current.matchup.clone(),
+            delta: current.win_rate() - previous.win_rate(),
+            significant: current.ci_low > previous.ci_high || current.ci_high < previous.ci_low,
+        }
+    }
+}
+
+/// A distinct set of cache_handles of a map.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct MapVersion {
+    pub cache_handles: String,
+    /// The date of the first game played in this version.
+    pub min_date: chrono::NaiveDate,
+    /// The date of the last game played in this version.
+    pub max_date: chrono::NaiveDate,
+    pub num_games: u64,
+    /// The win rate of each race pairing in the 1v1 games of this version.
+    pub matchups: Vec<MatchupWinRate>,
+    /// The change of each matchup also played in the previous version.
+    pub shifts: Vec<MatchupShift>,
+}
+
+/// The versions of a map as returned by the query_map_versions command, oldest first.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq)]
+pub struct MapVersions {
+    pub title: String,
+    pub versions: Vec<MapVersion>,
+}
+
+/// The query params of the autocomplete commands, which suggest the values of the snapshot that
+/// contain `term`, ignoring case.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct AutocompleteQuery {
+    /// The replay folders whose snapshots are queried, see
+    /// [`crate::LibrarySelection::replay_paths`].
+    pub replay_paths: Vec<String>,
+    /// The text typed so far.
+    pub term: String,
+    /// The maximum number of suggestions, 0 uses [`AUTOCOMPLETE_DEFAULT_LIMIT`].
+    pub limit: usize,
+}
+
+/// The number of suggestions returned when the query does not specify it.
+pub const AUTOCOMPLETE_DEFAULT_LIMIT: usize = 10;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use polars::prelude::*;
+
+    #[test]
+    fn localized_races_are_canonical() {
+        assert_eq!(race_id("Terraner"), "Terran");
+        assert_eq!(race_id("저그"), "Zerg");
+        assert_eq!(race_id("Protoss"), "Protoss");
+        assert_eq!(race_id("Random"), "Random");
+        assert_eq!(
+            MatchupWinRate::new("异虫".into(), "Протосс".into(), 0, 0).matchup,
+            "ZvP"
+        );
+    }
+
+    #[test]
+    fn localized_race_column_is_canonical() {
+        let df = df!("player_race" => ["Terraner", "저그", "Protoss", "Random"])
+            .unwrap()
+            .lazy()
+            .select([race_id_expr(col("player_race")).alias("player_race")])
+            .collect()
+            .unwrap();
+        let races: Vec<Option<&str>> = df
+            .column("player_race")
+            .unwrap()
+            .str()
+            .unwrap()
+            .into_iter()
+            .collect();
+        assert_eq!(
+            races,
+            [
+                Some("Terran"),
+                Some("Zerg"),
+                Some("Protoss"),
+                Some("Random")
+            ]
+        );
+    }
 }
@@ -0,0 +1,62 @@
+//! Progress reporting for the optimize command.
+use serde::{Deserialize, Serialize};
+
+/// The event emitted by the backend while an optimize job runs.
+pub const OPTIMIZE_PROGRESS_EVENT: &str = "optimize-progress";
+
+/// The stages an optimize job goes through.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub enum OptimizeStage {
+    /// Traversing the replay directory for SC2Replay files.
+    #[default]
+    Discovering,
+    /// Reading the InitData and Details of the discovered files.
+    Parsing,
+    /// Writing the Arrow IPC files.
+    Writing,
+    /// The snapshot has been replaced by the new one.
+    Finished,
+    /// The job was cancelled, the previous snapshot is left untouched.
+    Cancelled,
+}
+
+/// The payload of the [`OPTIMIZE_PROGRESS_EVENT`].
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct OptimizeProgress {
+    pub stage: OptimizeStage,
+    /// The number of SC2Replay files found in the replay directory.
+    pub files_discovered: usize,
+    /// The number of files that were parsed successfully.
+    pub files_parsed: usize,
+    /// The number of files that could not be parsed.
+    pub files_failed: usize,
+    /// The IPC file currently being written.
+    pub current_ipc: String,
+    /// The number of IPC files already written.
+    pub ipcs_written: usize,
+    /// The number of IPC files the snapshot is made of.
+    pub ipcs_total: usize,
+}
+
+impl OptimizeProgress {
+    /// Returns the overall completion in the 0..=100 range, parsing and writing weigh half each.
+    pub fn percent(&self) -> u32 {
+        let parsing = if self.files_discovered == 0 {
+            0.
+        } else {
+            (self.files_parsed + self.files_failed) as f64 / self.files_discovered as f64
+        };
+        let writing = if self.ipcs_total == 0 {
+            0.
+        } else {
+            self.ipcs_written as f64 / self.ipcs_total as f64
+        };
+        match self.stage {
+            OptimizeStage::Discovering => 0,
+            OptimizeStage::Parsing => (parsing * 50.) as u32,
+            OptimizeStage::Writing => 50 + (writing * 50.) as u32,
+            OptimizeStage::Finished => 100,
+            OptimizeStage::Cancelled => 0,
+        }
+    }
+}
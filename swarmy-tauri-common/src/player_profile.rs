@@ -0,0 +1,123 @@
+//! Per-player aggregates of the details IPC file.
+use serde::{Deserialize, Serialize};
+
+/// The number of favourite maps and frequent opponents returned in a profile.
+pub const PLAYER_PROFILE_TOP_N: usize = 10;
+
+/// Splits the clan tag out of a player name as stored in the replays, i.e.
+/// `&lt;TAG&gt;<sp/>Name` is returned as `("TAG", "Name")`.
+pub fn split_clan_tag(name: &str) -> (String, String) {
+    if let Some((clan, name)) = name.split_once("<sp/>") {
+        let clan = clan.replace("&gt;", "").replace("&lt;", "");
+        (clan, name.to_string())
+    } else {
+        (String::new(), name.to_string())
+    }
+}
+
+/// Formats a toon handle as shown in battle.net, `region-S2-realm-id`.
+pub fn format_toon_handle(region: u8, realm: u32, id: u64) -> String {
+    format!("{}-S2-{}-{}", region, realm, id)
+}
+
+/// Parses a toon handle formatted as `region-S2-realm-id` into its region, realm and id.
+pub fn parse_toon_handle(handle: &str) -> Option<(u8, u32, u64)> {
+    let mut parts = handle.trim().split('-');
+    let region = parts.next()?.parse().ok()?;
+    if parts.next()? != "S2" {
+        return None;
+    }
+    let realm = parts.next()?.parse().ok()?;
+    let id = parts.next()?.parse().ok()?;
+    if parts.next().is_some() {
+        return None;
+    }
+    Some((region, realm, id))
+}
+
+/// The query params of a player profile.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct PlayerProfileQuery {
+    /// The replay folders whose snapshots are queried, see
+    /// [`crate::LibrarySelection::replay_paths`].
+    pub replay_paths: Vec<String>,
+    /// The name of the player, with or without clan tag, or its toon handle.
+    pub player: String,
+}
+
+/// The games won and lost out of a number of games.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+pub struct PlayerRecord {
+    pub games: u64,
+    pub wins: u64,
+    pub losses: u64,
+}
+
+impl PlayerRecord {
+    /// The percentage of games won, 0 when there are no games.
+    pub fn win_rate(&self) -> f64 {
+        if self.games == 0 {
+            0.0
+        } else {
+            self.wins as f64 * 100.0 / self.games as f64
+        }
+    }
+}
+
+/// The record of a player with a race.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct PlayerRaceRecord {
+    pub race: String,
+    pub record: PlayerRecord,
+}
+
+/// The record of a player in a map.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct PlayerMapRecord {
+    pub title: String,
+    pub record: PlayerRecord,
+}
+
+/// The record of a player with an opening label.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct PlayerOpeningRecord {
+    pub label: String,
+    pub record: PlayerRecord,
+}
+
+/// The record of a player against an opponent, wins and losses are from the player side.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct PlayerOpponentRecord {
+    pub clan: String,
+    pub name: String,
+    pub record: PlayerRecord,
+}
+
+/// The number of games played in a day.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct PlayerActivityDay {
+    pub date: chrono::NaiveDate,
+    pub games: u64,
+}
+
+/// The profile of a player as returned by the query_player_profile command.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct PlayerProfile {
+    /// The clan tag the player used the most.
+    pub clan: String,
+    /// The name the player used the most.
+    pub name: String,
+    /// The toon handles of the player, `region-S2-realm-id`.
+    pub toon_handles: Vec<String>,
+    pub record: PlayerRecord,
+    /// The record per race, most played first.
+    pub races: Vec<PlayerRaceRecord>,
+    /// The most played maps, most played first.
+    pub favourite_maps: Vec<PlayerMapRecord>,
+    /// The record per opening label, most played first, empty if the snapshot is not classified.
+    pub openings: Vec<PlayerOpeningRecord>,
+    /// The most frequent opponents, most frequent first.
+    pub opponents: Vec<PlayerOpponentRecord>,
+    /// The games played per day, oldest first.
+    pub activity: Vec<PlayerActivityDay>,
+}
@@ -0,0 +1,14 @@
+//! Updates of the snapshot made by the replay folder watcher.
+use serde::{Deserialize, Serialize};
+
+/// The event emitted by the backend when the replay watcher updated a snapshot.
+pub const SNAPSHOT_UPDATED_EVENT: &str = "snapshot-updated";
+
+/// The payload of the [`SNAPSHOT_UPDATED_EVENT`].
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct SnapshotUpdated {
+    /// The replay folder whose snapshot was updated.
+    pub replay_path: String,
+    /// The number of SC2Replay files added or changed since the previous update.
+    pub replays: usize,
+}
@@ -1,6 +1,7 @@
+use crate::error::SwarmyTauriError;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ResponseMeta {
     pub success: bool,
     pub duration_ms: u64,
@@ -47,22 +48,103 @@ impl ResponseMetaBuilder {
     }
 }
 
-#[derive(Debug, Default, Clone, Serialize, Deserialize)]
-pub struct ApiResponse {
+/// The response of every backend command, `data` is set on success and `error` otherwise.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ApiResponse<T> {
     pub meta: ResponseMeta,
-    pub message: String,
+    pub data: Option<T>,
+    pub error: Option<SwarmyTauriError>,
+}
+
+impl<T> Default for ApiResponse<T> {
+    fn default() -> Self {
+        Self::new_incomplete()
+    }
 }
 
-impl ApiResponse {
-    pub fn new(meta: ResponseMeta, message: String) -> Self {
-        Self { meta, message }
+impl<T> ApiResponse<T> {
+    pub fn ok(meta: ResponseMeta, data: T) -> Self {
+        Self {
+            meta,
+            data: Some(data),
+            error: None,
+        }
+    }
+
+    pub fn err(meta: ResponseMeta, error: SwarmyTauriError) -> Self {
+        Self {
+            meta,
+            data: None,
+            error: Some(error),
+        }
+    }
+
+    /// Creates the response of a command from its result and how long it took.
+    pub fn from_result(res: Result<T, SwarmyTauriError>, duration_ms: u64) -> Self {
+        match res {
+            Ok(data) => Self::ok(
+                ResponseMetaBuilder::new(true)
+                    .duration_ms(duration_ms)
+                    .build(),
+                data,
+            ),
+            Err(e) => Self::err(
+                ResponseMetaBuilder::new(false)
+                    .duration_ms(duration_ms)
+                    .build(),
+                e,
+            ),
+        }
     }
 
     /// Creates an ApiResponse from client side to indicate an incomplete/pending state.
     pub fn new_incomplete() -> Self {
+        Self {
+            meta: ResponseMeta::incomplete(),
+            data: None,
+            error: None,
+        }
+    }
+
+    /// Returns the data, or the error if the command failed.
+    pub fn into_result(self) -> Result<T, SwarmyTauriError> {
+        match (self.data, self.error) {
+            (_, Some(error)) => Err(error),
+            (Some(data), None) => Ok(data),
+            (None, None) => Err(SwarmyTauriError::Other(String::from(
+                "Response contains no data",
+            ))),
+        }
+    }
+}
+
+/// The outcome of a command without its payload, used to keep track of requests in the UI.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResponseStatus {
+    pub meta: ResponseMeta,
+    /// The error message if the command failed.
+    pub message: String,
+}
+
+impl ResponseStatus {
+    /// Creates a ResponseStatus from client side to indicate an incomplete/pending state.
+    pub fn incomplete() -> Self {
         Self {
             meta: ResponseMeta::incomplete(),
             message: String::new(),
         }
     }
 }
+
+impl<T> From<&ApiResponse<T>> for ResponseStatus {
+    fn from(response: &ApiResponse<T>) -> Self {
+        Self {
+            meta: response.meta.clone(),
+            message: response
+                .error
+                .as_ref()
+                .map(|e| e.to_string())
+                .unwrap_or_default(),
+        }
+    }
+}
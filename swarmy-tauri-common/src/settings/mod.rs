@@ -1,10 +1,59 @@
 //! Module for application settings management.
+use super::library::{LibrarySelection, ReplayLibrary};
 use super::snapshot_stats::SnapshotStats;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Default, Serialize, Deserialize, Clone)]
 pub struct AppSettings {
     pub disable_parallel_scans: bool,
+    /// The replay path of the active library.
     pub replay_path: String,
+    /// The optimize settings of the active library.
+    pub optimize_settings: OptimizeSettings,
+    pub libraries: Vec<ReplayLibrary>,
+    pub library_selection: LibrarySelection,
+    /// The replay paths of the queries, the selected libraries.
+    pub query_replay_paths: Vec<String>,
+    /// The most recent SQL queries, newest first.
+    pub sql_history: Vec<String>,
+    /// The TOML or JSON file with the opening label rules, empty for the built-in rules.
+    pub build_rules_path: String,
     pub arrow_ipc_stats: SnapshotStats,
+    /// Why the snapshot must be optimized again, None if it is current or there is none.
+    pub snapshot_outdated: Option<String>,
+    /// The directory with the snapshots of the replay folders, under the app data dir by default.
+    pub snapshot_dir: String,
+    /// Whether new replays in the folder of the active library are added to its snapshot.
+    pub watch_replay_path: bool,
+}
+
+/// Limits applied when optimizing a replay directory into the Arrow IPC snapshot.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct OptimizeSettings {
+    /// Reads these many files recursing, these files may or may not be valid.
+    pub scan_max_files: usize,
+    /// The maximum number of files to process.
+    pub process_max_files: usize,
+    /// The maximum directory depth to traverse.
+    pub traverse_max_depth: usize,
+    /// The minimum protocol version.
+    pub min_version: Option<u32>,
+    /// The maximum protocol version.
+    pub max_version: Option<u32>,
+    /// Only process the replays added or changed since the last optimize.
+    pub incremental: bool,
+}
+
+impl Default for OptimizeSettings {
+    fn default() -> Self {
+        Self {
+            scan_max_files: 10000,
+            process_max_files: 10000,
+            traverse_max_depth: 8,
+            min_version: None,
+            max_version: None,
+            incremental: false,
+        }
+    }
 }
@@ -13,6 +13,12 @@ pub struct SnapshotStats {
     pub num_games: u64,
     /// The number of maps in the snapshot
     pub num_maps: u32,
+    /// The number of distinct map versions, identified by their cache_handles.
+    pub num_map_versions: u32,
+    /// The number of distinct players, identified by their toon.
+    pub num_players: u64,
+    /// The number of distinct protocol versions the games were played on.
+    pub num_game_versions: u32,
     /// The minimum date of the snapshot taken
     pub min_date: chrono::NaiveDate,
     /// The maximum date of the snapshot taken
@@ -25,6 +31,9 @@ impl Default for SnapshotStats {
             directory_size: 0,
             date_modified: std::time::SystemTime::UNIX_EPOCH,
             num_maps: 0,
+            num_map_versions: 0,
+            num_players: 0,
+            num_game_versions: 0,
             min_date: chrono::NaiveDate::from_ymd_opt(1970, 1, 1).unwrap(),
             max_date: chrono::NaiveDate::from_ymd_opt(1970, 1, 1).unwrap(),
             num_games: 0,
@@ -0,0 +1,55 @@
+//! Ad-hoc SQL queries over the Arrow IPC snapshot.
+use serde::{Deserialize, Serialize};
+
+/// The number of rows returned per page when the query does not specify it.
+pub const SQL_QUERY_DEFAULT_PER_PAGE: usize = 50;
+/// The maximum number of rows a query may return when it does not specify it.
+pub const SQL_QUERY_DEFAULT_ROW_LIMIT: usize = 10000;
+/// How long a query may run when it does not specify it.
+pub const SQL_QUERY_DEFAULT_TIMEOUT_MS: u64 = 30000;
+/// The number of timed out queries that may still be computing before new queries are refused.
+pub const SQL_QUERY_MAX_ABANDONED: usize = 2;
+/// The number of queries kept in the history.
+pub const SQL_QUERY_HISTORY_SIZE: usize = 50;
+
+/// A SQL query to run over the IPC files of the snapshot, each file is a table named after it,
+/// i.e. `details.ipc` is the `details` table.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct SqlQuery {
+    /// The replay folders whose snapshots are queried, see
+    /// [`crate::LibrarySelection::replay_paths`].
+    pub replay_paths: Vec<String>,
+    /// The SQL statement.
+    pub sql: String,
+    /// The maximum number of rows to compute, 0 uses [`SQL_QUERY_DEFAULT_ROW_LIMIT`].
+    pub row_limit: usize,
+    /// The time after which the query is abandoned, 0 uses [`SQL_QUERY_DEFAULT_TIMEOUT_MS`].
+    pub timeout_ms: u64,
+    /// The zero-based page of results to return.
+    pub page: usize,
+    /// The number of rows per page, 0 uses [`SQL_QUERY_DEFAULT_PER_PAGE`].
+    pub per_page: usize,
+}
+
+/// A page of rows of a SQL query result.
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SqlQueryResult {
+    /// The names of the columns.
+    pub columns: Vec<String>,
+    /// The data types of the columns, in the same order.
+    pub dtypes: Vec<String>,
+    /// The rows of the page, each value in the same order as the columns.
+    pub rows: Vec<Vec<serde_json::Value>>,
+    /// The total number of rows computed, up to the row limit.
+    pub total: usize,
+    /// Whether the result was cut at the row limit.
+    pub truncated: bool,
+    /// The index of the first row of the page, inclusive.
+    pub start: usize,
+    /// The index of the last row of the page, exclusive.
+    pub end: usize,
+    /// The zero-based page number.
+    pub page: usize,
+    /// The number of rows per page.
+    pub per_page: usize,
+}